@@ -0,0 +1,43 @@
+//! Nanoseconds per unit of work of `estimate_runtime` on this machine
+//!
+//! Run with `cargo bench --bench calibration` and build with the printed `KMEANS_CALIBRATION`
+//! to calibrate the runtime estimate for this machine.
+
+use k_means_rs::algo::{estimate_runtime, CALIBRATION_NS};
+use k_means_rs::{ClusteringAlgorithm, FuzzyKMeansConfig};
+use ndarray::Array2;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Number of timed fits, the median is used
+const RUNS: usize = 7;
+
+fn main() {
+    let (n, d, k, n_iter) = (20_000, 8, 5, 20);
+    // uniformly spread pseudo-random data, so no fit converges early
+    let data = Array2::from_shape_fn((n, d), |(i, j)| {
+        ((i * 7919 + j * 104_729) % 1000) as f64 / 100.0
+    });
+    let config = FuzzyKMeansConfig {
+        seed: Some(0),
+        ..FuzzyKMeansConfig::new(k, n_iter, 2.0)
+    };
+    let mut seconds: Vec<f64> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(config.fit(black_box(&data)).unwrap());
+            start.elapsed().as_secs_f64()
+        })
+        .collect();
+    seconds.sort_by(f64::total_cmp);
+    let measured = seconds[RUNS / 2];
+
+    // the estimate is linear in the calibration
+    let estimated = estimate_runtime(n, d, k, n_iter).as_secs_f64();
+    let calibration = CALIBRATION_NS * measured / estimated;
+    println!(
+        "fit of {}x{} with k={}, {} iterations: {:.3}s, estimated {:.3}s",
+        n, d, k, n_iter, measured, estimated
+    );
+    println!("KMEANS_CALIBRATION={:.3}", calibration);
+}
@@ -0,0 +1,87 @@
+//! Squared euclidean distances: matrix product against direct differences
+//!
+//! Run with `cargo bench --bench distances`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use k_means_rs::algo::{
+    euclidean_dist_sq_matrix, ClusteringAlgorithm, DistanceMetric, Euclidean, FuzzyKMeansConfig,
+};
+use ndarray::{Array2, ArrayView1, ArrayView2};
+use std::hint::black_box;
+use std::sync::Arc;
+
+/// Deterministic pseudo-random data, `rows` data points with `cols` features
+fn data(rows: usize, cols: usize) -> Array2<f64> {
+    Array2::from_shape_fn((rows, cols), |(i, j)| {
+        ((i * 7919 + j * 104_729) % 1000) as f64 / 100.0
+    })
+}
+
+/// Euclidean distance with the squared distances summed from differences, one pair at a time
+#[derive(Debug)]
+struct Direct;
+
+impl DistanceMetric for Direct {
+    fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+        Euclidean.distance(a, b)
+    }
+
+    fn squared_distances(&self, x: ArrayView2<f64>, centers: &Array2<f64>) -> Array2<f64> {
+        let mut dists = Array2::<f64>::zeros((x.nrows(), centers.nrows()));
+        for (point, mut row) in x.outer_iter().zip(dists.outer_iter_mut()) {
+            for (center, dist) in centers.outer_iter().zip(row.iter_mut()) {
+                *dist = point
+                    .iter()
+                    .zip(center.iter())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum();
+            }
+        }
+        dists
+    }
+}
+
+fn distances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("euclidean_dist_sq");
+    for &cols in &[2, 8, 16, 32, 64, 128] {
+        let data = data(4096, cols);
+        let centers = data.slice(ndarray::s![..32, ..]).to_owned() + 0.5;
+
+        group.bench_function(BenchmarkId::new("matrix", cols), |b| {
+            b.iter(|| euclidean_dist_sq_matrix(black_box(&data), &centers).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("direct", cols), |b| {
+            b.iter(|| Direct.squared_distances(black_box(&data).view(), &centers))
+        });
+    }
+    group.finish();
+}
+
+/// Whole fits, where the distances compete with turning them into memberships and centers
+fn fits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fit");
+    group.sample_size(10);
+    for &(rows, cols, k) in &[(20_000, 32, 32), (10_000, 128, 64)] {
+        let data = data(rows, cols);
+        let id = format!("{}x{}/k={}", rows, cols, k);
+        let config = |metric: Arc<dyn DistanceMetric>| FuzzyKMeansConfig {
+            tol: 0.0,
+            seed: Some(1),
+            metric,
+            ..FuzzyKMeansConfig::new(k, 5, 2.0)
+        };
+
+        let matrix = config(Arc::new(Euclidean));
+        group.bench_function(BenchmarkId::new("matrix", &id), |b| {
+            b.iter(|| matrix.fit(black_box(&data)).unwrap())
+        });
+        let direct = config(Arc::new(Direct));
+        group.bench_function(BenchmarkId::new("direct", &id), |b| {
+            b.iter(|| direct.fit(black_box(&data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, distances, fits);
+criterion_main!(benches);
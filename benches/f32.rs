@@ -0,0 +1,72 @@
+//! Fuzzy k means computing in f32 against the f64 path
+//!
+//! Run with `cargo bench --bench f32`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use k_means_rs::algo::{cluster_k_means_fuzzy, cluster_k_means_fuzzy_f32};
+use ndarray::Array2;
+use rand::{rngs::StdRng, SeedableRng};
+use std::hint::black_box;
+
+const K: usize = 4;
+const N_ITER: usize = 10;
+
+/// Deterministic pseudo-random data in `K` separated clouds
+fn data(rows: usize, cols: usize) -> Array2<f64> {
+    Array2::from_shape_fn((rows, cols), |(i, j)| {
+        (i % K) as f64 * 10.0 + ((i * 7919 + j * 104_729) % 1000) as f64 / 500.0
+    })
+}
+
+fn f32_vs_f64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fuzzy_k_means");
+    for &(rows, cols) in &[(10_000, 4), (100_000, 8)] {
+        let data64 = data(rows, cols);
+        let data32 = data64.mapv(|val| val as f32);
+        let id = format!("{}x{}", rows, cols);
+
+        // both start from the same centers, the results must agree
+        let centers64 =
+            cluster_k_means_fuzzy(K, N_ITER, 2.0, &data64, &mut StdRng::seed_from_u64(1)).unwrap();
+        let centers32 =
+            cluster_k_means_fuzzy_f32(K, N_ITER, 2.0, &data32, &mut StdRng::seed_from_u64(1))
+                .unwrap()
+                .centers;
+        for (a, b) in centers64.iter().zip(centers32.iter()) {
+            assert!((a - f64::from(*b)).abs() < 1e-3, "{} differs from {}", a, b);
+        }
+
+        group.bench_function(BenchmarkId::new("f64", &id), |b| {
+            b.iter(|| {
+                cluster_k_means_fuzzy(
+                    K,
+                    N_ITER,
+                    2.0,
+                    black_box(&data64),
+                    &mut StdRng::seed_from_u64(1),
+                )
+                .unwrap()
+            })
+        });
+        group.bench_function(BenchmarkId::new("f32", &id), |b| {
+            b.iter(|| {
+                cluster_k_means_fuzzy_f32(
+                    K,
+                    N_ITER,
+                    2.0,
+                    black_box(&data32),
+                    &mut StdRng::seed_from_u64(1),
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = f32_vs_f64
+}
+criterion_main!(benches);
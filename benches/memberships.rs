@@ -0,0 +1,49 @@
+//! Membership computation with and without reusing buffers
+//!
+//! Run with `cargo bench --bench memberships`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use k_means_rs::algo::{compute_memberships, compute_memberships_with_scratch};
+use ndarray::{Array1, Array2};
+use std::hint::black_box;
+
+/// Deterministic pseudo-random data, `rows` data points with `cols` features
+fn data(rows: usize, cols: usize) -> Array2<f64> {
+    Array2::from_shape_fn((rows, cols), |(i, j)| {
+        ((i * 7919 + j * 104_729) % 1000) as f64 / 100.0
+    })
+}
+
+fn memberships(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memberships");
+    for &(rows, cols, k) in &[(1_000, 4, 4), (10_000, 16, 8)] {
+        let data = data(rows, cols);
+        let centers = data.slice(ndarray::s![..k, ..]).to_owned() + 0.5;
+        let id = format!("{}x{}/k={}", rows, cols, k);
+
+        group.bench_function(BenchmarkId::new("allocating", &id), |b| {
+            let mut memberships = Array2::<f64>::zeros((rows, k));
+            b.iter(|| {
+                compute_memberships(2.0, black_box(&data), &centers, &mut memberships).unwrap()
+            })
+        });
+        group.bench_function(BenchmarkId::new("with_scratch", &id), |b| {
+            let mut memberships = Array2::<f64>::zeros((rows, k));
+            let mut scratch = Array1::<f64>::zeros(rows);
+            b.iter(|| {
+                compute_memberships_with_scratch(
+                    2.0,
+                    black_box(&data),
+                    &centers,
+                    &mut memberships,
+                    &mut scratch,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, memberships);
+criterion_main!(benches);
@@ -0,0 +1,76 @@
+//! Writes the calibration of the runtime estimate and the C header of the ffi module,
+//! k_means_rs.h, next to the built libraries
+
+/// Nanoseconds per unit of work of `algo::estimate_runtime`, measured with
+/// `cargo bench --bench calibration` on a reference machine
+const DEFAULT_CALIBRATION_NS: f64 = 2.16;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    write_calibration();
+    #[cfg(feature = "ffi")]
+    write_header();
+}
+
+/// Write `CALIBRATION_NS` to calibration.rs in OUT_DIR, the environment variable
+/// `KMEANS_CALIBRATION` replaces the default measured on the reference machine
+fn write_calibration() {
+    println!("cargo:rerun-if-env-changed=KMEANS_CALIBRATION");
+    let calibration = match std::env::var("KMEANS_CALIBRATION") {
+        Ok(val) => match val.parse::<f64>() {
+            Ok(ns) if ns.is_finite() && ns > 0.0 => ns,
+            _ => panic!(
+                "KMEANS_CALIBRATION must be a positive number of nanoseconds, got \"{}\"",
+                val
+            ),
+        },
+        Err(_) => DEFAULT_CALIBRATION_NS,
+    };
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(
+        out_dir.join("calibration.rs"),
+        format!(
+            "/// Nanoseconds per unit of work in `estimate_runtime`, set with the environment \
+             variable\n/// `KMEANS_CALIBRATION` at build time\npub const CALIBRATION_NS: f64 = \
+             {:?};\n",
+            calibration
+        ),
+    )
+    .unwrap();
+}
+
+#[cfg(feature = "ffi")]
+fn write_header() {
+    use std::path::PathBuf;
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    // OUT_DIR is target/<profile>/build/<package>-<hash>/out
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let profile_dir = out_dir.ancestors().nth(3).unwrap();
+
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some(String::from("K_MEANS_RS_H")),
+        header: Some(String::from(
+            "/* Fuzzy k-means clustering, generated by cbindgen from src/lib.rs */",
+        )),
+        cpp_compat: true,
+        usize_is_size_t: true,
+        // public items of the Rust API that cbindgen picks up as well
+        export: cbindgen::ExportConfig {
+            exclude: ["FORMAT_VERSION", "DEFAULT_MIN_VARIANCE", "ValidityMeasure"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(profile_dir.join("k_means_rs.h"));
+}
@@ -1,6 +1,117 @@
+//! Fuzzy k-means clustering on ndarray data
+//!
+//! [`algo`] holds the clustering algorithms, distance metrics and validity measures, [`io`]
+//! reading and writing data and results as csv, JSON and npy, and [`preprocess`] scaling,
+//! sampling and cleaning of the data before clustering. The most used types are re-exported at
+//! the top level.
+//!
+//! On `wasm32` targets there is no file io, so [`io`] and everything else touching the file
+//! system is left out. The `wasm` feature adds JavaScript bindings in `wasm`, the `ffi` feature a
+//! C API in `ffi`.
+//!
+//! ```
+//! use k_means_rs::{ClusteringAlgorithm, FuzzyKMeansConfig};
+//! use ndarray::array;
+//!
+//! let data = array![[0.0, 0.1], [0.2, 0.0], [5.0, 5.1], [5.2, 4.9]];
+//! let result = FuzzyKMeansConfig::new(2, 100, 2.0).fit(&data).unwrap();
+//! assert_eq!(result.labels[0], result.labels[1]);
+//! assert_ne!(result.labels[0], result.labels[2]);
+//! ```
+#![deny(missing_docs)]
+
+pub use algo::{
+    AlgoError, ClusteringAlgorithm, ClusteringResult, DistanceMetric, FuzzyKMeansConfig,
+    HardKMeansConfig, InitStrategy,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use io::{CsvError, FloatFormat};
+
+/// Any error returned by the crate, for callers that don't need to tell reading and writing
+/// data from clustering apart
+///
+/// Functions return the error of their module, `io::CsvError` or `algo::AlgoError`, which
+/// convert into this one with `?`. Parameters read from elsewhere, like a command line, that are
+/// invalid or don't fit together are a `ConfigError`.
+///
+/// # Example
+///
+/// ```
+/// use k_means_rs::io::try_read_csv_delimited;
+/// use k_means_rs::{AlgoError, ClusteringAlgorithm, Error, FuzzyKMeansConfig};
+/// use ndarray::Array2;
+///
+/// fn cluster(fname: &str, k: usize) -> Result<Vec<usize>, Error> {
+///     let data: Array2<f64> = try_read_csv_delimited(fname, b';', true)?;
+///     let result = FuzzyKMeansConfig::new(k, 100, 2.0).fit(&data)?;
+///     Ok(result.labels.to_vec())
+/// }
+///
+/// let err = cluster("does/not/exist.csv", 2).unwrap_err();
+/// assert!(matches!(err, Error::Io(_)));
+///
+/// let fname = std::env::temp_dir().join("k_means_rs_error_example.csv");
+/// std::fs::write(&fname, "x;y\n0;1\n2;3\n").unwrap();
+/// let err = cluster(fname.to_str().unwrap(), 3).unwrap_err();
+/// assert!(matches!(
+///     err,
+///     Error::Fit(AlgoError::TooManyClusters { k: 3, n: 2 })
+/// ));
+/// assert_eq!(err.to_string(), "Can't form 3 clusters from 2 data points");
+///
+/// // usable with error handling crates that require thread-safe errors
+/// fn is_thread_safe<E: std::error::Error + Send + Sync + 'static>(_: &E) {}
+/// is_thread_safe(&err);
+/// ```
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading or writing data failed
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(transparent)]
+    Io(#[from] io::CsvError),
+    /// Parameters are invalid or don't fit together
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    /// Clustering failed
+    #[error(transparent)]
+    Fit(#[from] algo::AlgoError),
+}
+
+/// Parameters that are invalid or don't fit together, e.g. given on a command line or in a
+/// configuration file
+///
+/// ```
+/// use k_means_rs::{ConfigError, Error};
+///
+/// let err = Error::from(ConfigError::new("-q and --hard can't be used together"));
+/// assert_eq!(err.to_string(), "-q and --hard can't be used together");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{message}")]
+pub struct ConfigError {
+    message: String,
+}
+
+impl ConfigError {
+    /// Create an error that explains what is wrong with the parameters
+    pub fn new(message: impl Into<String>) -> Self {
+        ConfigError {
+            message: message.into(),
+        }
+    }
+
+    /// What is wrong with the parameters
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Reading data and writing results as csv, JSON and npy
+#[cfg(not(target_arch = "wasm32"))]
 pub mod io {
-    use ndarray::{Array2, ArrayView1};
+    use ndarray::{Array1, Array2, ArrayView1, Axis};
     use num_traits;
+    use std::collections::HashMap;
 
     /// Read csv file into Array2
     ///
@@ -11,240 +122,7108 @@ pub mod io {
     /// # Example
     ///
     /// ```
-    /// use ndarray::Array2
-    /// let values: Array2<f64> = String::from("values.csv");
+    /// use k_means_rs::io::read_csv;
+    /// use ndarray::{array, Array2};
+    ///
+    /// let fname = std::env::temp_dir().join("k_means_rs_read_csv_example.csv");
+    /// std::fs::write(&fname, "x;y\n0.5;1\n2;-3.25\n").unwrap();
+    ///
+    /// let values: Array2<f64> = read_csv(fname.to_str().unwrap().to_string()).unwrap();
+    /// assert_eq!(values, array![[0.5, 1.0], [2.0, -3.25]]);
+    ///
+    /// assert!(read_csv::<f64>(String::from("does/not/exist.csv")).is_err());
     /// ```
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if parsing `T` from the string in a file fails
-    pub fn read_csv<T>(fname: String) -> Array2<T>
+    /// `CsvError` if the file can't be read or parsing `T` from the string in a file fails
+    pub fn read_csv<T>(fname: String) -> Result<Array2<T>, CsvError>
     where
         T: Clone + num_traits::identities::Zero + std::str::FromStr + std::fmt::Debug,
         T::Err: std::fmt::Debug,
+    {
+        read_csv_delimited(fname, b';', true)
+    }
+
+    /// Read csv file with the given field delimiter into Array2
+    ///
+    /// # Arguments
+    ///
+    /// * `fname`       - filename
+    /// * `delimiter`   - field delimiter
+    /// * `has_headers` - whether the first line is a header instead of data
+    ///
+    /// # Errors
+    ///
+    /// `CsvError` if the file can't be read or parsing `T` from the string in a file fails
+    pub fn read_csv_delimited<T>(
+        fname: String,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> Result<Array2<T>, CsvError>
+    where
+        T: Clone + num_traits::identities::Zero + std::str::FromStr + std::fmt::Debug,
+        T::Err: std::fmt::Debug,
+    {
+        try_read_csv_delimited(&fname, delimiter, has_headers)
+    }
+
+    /// Like `read_csv_delimited`, with the file name borrowed
+    ///
+    /// # Arguments
+    ///
+    /// * `fname`       - filename
+    /// * `delimiter`   - field delimiter
+    /// * `has_headers` - whether the first line is a header instead of data
+    pub fn try_read_csv_delimited<T>(
+        fname: &str,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> Result<Array2<T>, CsvError>
+    where
+        T: Clone + num_traits::identities::Zero + std::str::FromStr,
+        T::Err: std::fmt::Debug,
     {
         let mut reader = csv::ReaderBuilder::new()
-            .has_headers(true)
-            .delimiter(b';')
-            .from_path(fname)
-            .unwrap();
+            .has_headers(has_headers)
+            .delimiter(delimiter)
+            .from_path(fname)?;
 
-        let cols = reader.headers().unwrap().len();
+        let cols = reader.headers()?.len();
         let rows = reader.records().count();
 
-        println!("Reading csv...");
-        println!("Found {} columns and {} rows.", rows, cols);
+        log::info!("Reading csv...");
+        log::info!("Found {} rows and {} columns.", rows, cols);
 
-        // move to beginning of file
+        // move to beginning of file, which yields the header line again
         let pos = csv::Position::new();
-        reader.seek(pos).unwrap();
+        reader.seek(pos)?;
 
         let mut out_vals = Array2::<T>::zeros((rows, cols));
         for (row_idx, (record, mut out_row)) in reader
             .records()
-            .skip(1)
+            .skip(usize::from(has_headers))
             .zip(out_vals.outer_iter_mut())
             .enumerate()
         {
-            let cur_record: csv::StringRecord = record.unwrap();
+            let cur_record: csv::StringRecord = record?;
             for (col_idx, (out_field, record_field)) in
                 out_row.iter_mut().zip(cur_record.iter()).enumerate()
             {
-                *out_field = match record_field.parse() {
-                    Result::Err(err) => panic!(
-                        "Error tring to parse value in ({}, {})!\n Error: {:?}",
-                        row_idx, col_idx, err
-                    ),
-                    Result::Ok(val) => val,
-                };
+                *out_field = record_field.parse().map_err(|err| CsvError::Parse {
+                    row: row_idx,
+                    col: col_idx,
+                    message: format!("{:?}", err),
+                })?;
             }
         }
-        out_vals
+        Ok(out_vals)
     }
 
-    /// Convert values from ndarray::Array1 to csv::StringRecord
+    /// Read several csv files with a header line each and stack their rows in the given order
     ///
     /// # Arguments
     ///
-    /// * `vals` - values to convert
-    fn to_record<T>(vals: &ArrayView1<T>) -> csv::StringRecord
-    where
-        T: std::fmt::Display,
-    {
-        let mut record = csv::StringRecord::with_capacity(10, vals.dim());
-        for val in vals.iter() {
-            record.push_field(&format!("{}", val).as_str());
+    /// * `fnames`    - filenames
+    /// * `delimiter` - field delimiter
+    ///
+    /// # Errors
+    ///
+    /// `ColumnCountMismatch` if a file has a different number of columns than the first one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{concat_csv_files, CsvError};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let monday = dir.join("k_means_rs_concat_monday.csv");
+    /// let tuesday = dir.join("k_means_rs_concat_tuesday.csv");
+    /// let broken = dir.join("k_means_rs_concat_broken.csv");
+    /// std::fs::write(&monday, "x;y\n0;1\n2;3\n").unwrap();
+    /// std::fs::write(&tuesday, "x;y\n4;5\n").unwrap();
+    /// std::fs::write(&broken, "x\n6\n").unwrap();
+    /// let (monday, tuesday, broken) = (
+    ///     monday.to_str().unwrap(),
+    ///     tuesday.to_str().unwrap(),
+    ///     broken.to_str().unwrap(),
+    /// );
+    ///
+    /// let data = concat_csv_files(&[monday, tuesday], b';').unwrap();
+    /// assert_eq!(data, ndarray::array![[0.0, 1.0], [2.0, 3.0], [4.0, 5.0]]);
+    ///
+    /// let err = concat_csv_files(&[monday, broken], b';').unwrap_err();
+    /// assert!(matches!(
+    ///     err,
+    ///     CsvError::ColumnCountMismatch { expected: 2, got: 1, .. }
+    /// ));
+    /// ```
+    pub fn concat_csv_files(fnames: &[&str], delimiter: u8) -> Result<Array2<f64>, CsvError> {
+        let mut parts: Vec<Array2<f64>> = Vec::with_capacity(fnames.len());
+        for fname in fnames {
+            let part = try_read_csv_delimited(fname, delimiter, true)?;
+            if let Some(first) = parts.first() {
+                if part.ncols() != first.ncols() {
+                    return Err(CsvError::ColumnCountMismatch {
+                        file: fname.to_string(),
+                        expected: first.ncols(),
+                        got: part.ncols(),
+                    });
+                }
+            }
+            parts.push(part);
         }
-        record
+        if parts.is_empty() {
+            return Ok(Array2::zeros((0, 0)));
+        }
+        let views: Vec<_> = parts.iter().map(Array2::view).collect();
+        Ok(ndarray::concatenate(Axis(0), &views).unwrap())
     }
 
-    /// Write ndarray::Array2 to csv file
+    /// Read `k` cluster centers of `n_features` values each from a csv file
+    ///
+    /// Takes rows of center coordinates as written by `to_csv`, or a file written by
+    /// `write_centers`, whose header line and leading cluster column are skipped. A first line
+    /// that doesn't hold numbers is taken for a header.
     ///
     /// # Arguments
     ///
-    /// * `arr` - array to write to file
-    /// * `fname` - target filename
-    /// * `delimiter' - delimiter to use in csv
-    pub fn to_csv<T>(arr: Array2<T>, fname: String, delimiter: u8)
-    where
-        T: std::fmt::Display,
-    {
-        let mut writer = csv::WriterBuilder::new()
+    /// * `fname`      - filename
+    /// * `k`          - number of centers (rows) expected
+    /// * `n_features` - number of values (columns) expected per center
+    /// * `delimiter`  - field delimiter
+    ///
+    /// # Errors
+    ///
+    /// `ShapeMismatch` if the file doesn't hold `k` rows of `n_features` values
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{read_cluster_centers, to_csv, CsvError};
+    /// use ndarray::array;
+    ///
+    /// let centers = array![[0.5, -1.25], [3.0, 4.0], [1e-3, 2.5e6]];
+    /// let fname = std::env::temp_dir().join("k_means_rs_centers_example.csv");
+    /// let fname = fname.to_str().unwrap();
+    /// to_csv(centers.clone(), fname.to_string(), b';').unwrap();
+    /// assert_eq!(read_cluster_centers(fname, 3, 2, b';').unwrap(), centers);
+    /// match read_cluster_centers(fname, 2, 2, b';') {
+    ///     Err(CsvError::ShapeMismatch { expected, got }) => {
+    ///         assert_eq!(expected, (2, 2));
+    ///         assert_eq!(got, (3, 2));
+    ///     }
+    ///     other => panic!("expected a shape mismatch, got {:?}", other),
+    /// }
+    /// # std::fs::remove_file(fname).unwrap();
+    /// ```
+    pub fn read_cluster_centers(
+        fname: &str,
+        k: usize,
+        n_features: usize,
+        delimiter: u8,
+    ) -> Result<Array2<f64>, CsvError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
             .delimiter(delimiter)
-            .from_path(fname)
-            .unwrap();
+            .from_path(fname)?;
+        let mut records = reader
+            .records()
+            .collect::<Result<Vec<csv::StringRecord>, csv::Error>>()?;
+        let header = match records.first() {
+            Some(first)
+                if first
+                    .iter()
+                    .any(|field| field.trim().parse::<f64>().is_err()) =>
+            {
+                Some(records.remove(0))
+            }
+            _ => None,
+        };
+        let skip = usize::from(header.is_some_and(|header| header.get(0) == Some("cluster")));
+        let n_cols = records
+            .first()
+            .map_or(0, |record| record.len().saturating_sub(skip));
+        if (records.len(), n_cols) != (k, n_features) {
+            return Err(CsvError::ShapeMismatch {
+                expected: (k, n_features),
+                got: (records.len(), n_cols),
+            });
+        }
 
-        for row in arr.outer_iter() {
-            writer.write_record(&to_record(&row)).unwrap();
+        let mut centers = Array2::<f64>::zeros((k, n_features));
+        for (row, (record, mut center)) in records.iter().zip(centers.outer_iter_mut()).enumerate()
+        {
+            for (col, (value, field)) in center.iter_mut().zip(record.iter().skip(skip)).enumerate()
+            {
+                *value = field.trim().parse().map_err(|err| CsvError::Parse {
+                    row,
+                    col,
+                    message: format!("{:?}", err),
+                })?;
+            }
         }
-        writer.flush().unwrap();
+        Ok(centers)
     }
-}
 
-pub mod algo {
-    use ndarray::{self, Array1, Array2, ArrayView1, Axis};
-    use std::ops::{Div, Mul, Sub};
+    /// Read the header line of a csv file
+    ///
+    /// # Arguments
+    ///
+    /// * `fname`     - filename
+    /// * `delimiter` - field delimiter
+    ///
+    /// # Errors
+    ///
+    /// `CsvError` if the file can't be read
+    pub fn read_csv_header(fname: String, delimiter: u8) -> Result<Vec<String>, CsvError> {
+        try_read_csv_header(&fname, delimiter)
+    }
 
-    /// Compute Squared distance between 2 Arrays / Points of Data
+    /// Like `read_csv_header`, with the file name borrowed
     ///
     /// # Arguments
     ///
-    /// * `a`, `b` - Arrays to compute distances between
-    fn dist_sq<T>(a: ArrayView1<T>, b: ArrayView1<T>) -> f64
-    where
-        T: Clone + Copy + Mul<Output = T> + Sub<Output = T> + num_traits::Zero,
-        f64: From<T>,
-        Array1<T>: Sub<Output = Array1<T>>,
-    {
-        f64::from(
-            (a.into_owned() - b.into_owned())
-                .mapv(|val: T| -> T { val * val })
-                .sum(),
-        )
+    /// * `fname`     - filename
+    /// * `delimiter` - field delimiter
+    pub fn try_read_csv_header(fname: &str, delimiter: u8) -> Result<Vec<String>, CsvError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .delimiter(delimiter)
+            .from_path(fname)?;
+        Ok(reader.headers()?.iter().map(String::from).collect())
     }
 
-    /// Calculate fuzzy memberships for elements of data to clusters in cluster and write to memberships
+    /// Read csv data from any reader, e.g. stdin, in a single pass
+    ///
+    /// Returns the header line, empty if `has_headers` is false, and the values.
     ///
     /// # Arguments
     ///
-    /// * `q`            - fuzzifier
-    /// * `data`         - data to compute memberships for
-    /// * `clusters`     - clusters
-    /// * `memberships`  - write membership information here
-    pub fn compute_memberships<T>(
-        q: f64,
-        data: &Array2<T>,
-        clusters: &Array2<T>,
-        memberships: &mut Array2<f64>,
-    ) where
-        T: Clone + Copy + Mul<Output = T> + Sub<Output = T> + num_traits::Zero + Div<Output = T>,
-        f64: From<T>,
-        Array1<T>: Sub<Output = Array1<T>>,
+    /// * `reader`      - where to read the csv from
+    /// * `delimiter`   - field delimiter
+    /// * `has_headers` - whether the first line is a header instead of data
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::read_csv_from_reader;
+    /// use ndarray::{array, Array2};
+    ///
+    /// let (header, values): (_, Array2<f64>) =
+    ///     read_csv_from_reader("x,y\n1,2\n3,4\n".as_bytes(), b',', true).unwrap();
+    /// assert_eq!(header, vec!["x", "y"]);
+    /// assert_eq!(values, array![[1.0, 2.0], [3.0, 4.0]]);
+    /// ```
+    pub fn read_csv_from_reader<T, R>(
+        reader: R,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> Result<(Vec<String>, Array2<T>), CsvError>
+    where
+        T: Clone + std::str::FromStr,
+        T::Err: std::fmt::Debug,
+        R: std::io::Read,
     {
-        // Membrships are distances for now
-        for (i, val) in data.outer_iter().enumerate() {
-            for (j, cluster) in clusters.outer_iter().enumerate() {
-                memberships[[i, j]] = dist_sq(val, cluster).powf(1.0 / (1.0 - q));
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(has_headers)
+            .delimiter(delimiter)
+            .from_reader(reader);
+
+        let header: Vec<String> = if has_headers {
+            reader.headers()?.iter().map(String::from).collect()
+        } else {
+            Vec::new()
+        };
+        let mut values = Vec::new();
+        let mut n_rows = 0;
+        let mut n_cols = header.len();
+        for (row, record) in reader.records().enumerate() {
+            let record = record?;
+            n_cols = record.len();
+            for (col, field) in record.iter().enumerate() {
+                values.push(field.parse().map_err(|err| CsvError::Parse {
+                    row,
+                    col,
+                    message: format!("{:?}", err),
+                })?);
             }
+            n_rows += 1;
         }
+        log::info!("Found {} rows and {} columns.", n_rows, n_cols);
+        let values = Array2::from_shape_vec((n_rows, n_cols), values)
+            .expect("the csv reader enforces equal record lengths");
+        Ok((header, values))
+    }
+
+    /// Summary statistics of one csv column, updated one field at a time
+    ///
+    /// Mean and variance are accumulated with Welford's algorithm, so a file of any size can be
+    /// summarized in a single pass without holding its values. Empty fields and `NA`, `NaN` or
+    /// `null` (in any case) count as missing; any other field that isn't a number counts as
+    /// non-numeric. Neither enters the statistics.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ColumnStats {
+        /// Number of numeric values
+        pub count: usize,
+        /// Number of missing values
+        pub missing: usize,
+        /// Number of values that aren't numbers
+        pub non_numeric: usize,
+        /// Smallest numeric value, infinite if there are none
+        pub min: f64,
+        /// Largest numeric value, negative infinite if there are none
+        pub max: f64,
+        mean: f64,
+        m2: f64,
+    }
 
-        // compute cluster memberships
-        let dist_sums = memberships.sum_axis(Axis(1));
-        for (mut cluster_dists, dist_sum) in memberships.outer_iter_mut().zip(dist_sums.iter()) {
-            cluster_dists.mapv_inplace(|val| val / *dist_sum);
+    impl Default for ColumnStats {
+        fn default() -> Self {
+            ColumnStats {
+                count: 0,
+                missing: 0,
+                non_numeric: 0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+                mean: 0.0,
+                m2: 0.0,
+            }
         }
     }
 
-    /// Compute nearest cluster per data point from clusters
+    impl ColumnStats {
+        /// Add one field of the column
+        ///
+        /// # Arguments
+        ///
+        /// * `field` - the field as read from the file
+        pub fn push_field(&mut self, field: &str) {
+            let field = field.trim();
+            if field.is_empty() || ["na", "nan", "null"].contains(&field.to_lowercase().as_str()) {
+                self.missing += 1;
+                return;
+            }
+            match field.parse::<f64>() {
+                Ok(val) => self.push(val),
+                Err(_) => self.non_numeric += 1,
+            }
+        }
+
+        /// Add one numeric value of the column
+        ///
+        /// # Arguments
+        ///
+        /// * `val` - the value
+        pub fn push(&mut self, val: f64) {
+            self.count += 1;
+            let delta = val - self.mean;
+            self.mean += delta / self.count as f64;
+            self.m2 += delta * (val - self.mean);
+            self.min = self.min.min(val);
+            self.max = self.max.max(val);
+        }
+
+        /// Mean of the numeric values, NaN if there are none
+        pub fn mean(&self) -> f64 {
+            if self.count == 0 {
+                f64::NAN
+            } else {
+                self.mean
+            }
+        }
+
+        /// Population standard deviation of the numeric values, NaN if there are none
+        pub fn std_dev(&self) -> f64 {
+            if self.count == 0 {
+                f64::NAN
+            } else {
+                (self.m2 / self.count as f64).sqrt()
+            }
+        }
+
+        /// Whether every field of the column is a number
+        pub fn is_numeric(&self) -> bool {
+            self.missing == 0 && self.non_numeric == 0
+        }
+    }
+
+    /// Summarize every column of a csv file in a single pass
+    ///
+    /// Unlike `read_csv_from_reader`, fields that aren't numbers don't stop the scan, they are
+    /// counted in the statistics instead. Returns the header (empty without one), the number of
+    /// rows and the statistics of every column.
     ///
     /// # Arguments
     ///
-    /// `data` - datapoints to compute memberships for.
-    /// `clusters` - Cluster Centers to compute nearest cluster for
-    pub fn compute_nearest<T>(data: &Array2<T>, clusters: &Array2<T>) -> Array2<T>
+    /// * `reader`      - source of the csv data
+    /// * `delimiter`   - field delimiter
+    /// * `has_headers` - whether the first line holds column names
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::scan_csv_columns;
+    ///
+    /// let csv = "x;y;name\n1;10;a\n2;;b\n3;20;c\n6;NA;d\n";
+    /// let (header, n_rows, stats) = scan_csv_columns(csv.as_bytes(), b';', true).unwrap();
+    /// assert_eq!(header, vec!["x", "y", "name"]);
+    /// assert_eq!(n_rows, 4);
+    /// assert_eq!((stats[0].min, stats[0].max, stats[0].mean()), (1.0, 6.0, 3.0));
+    /// assert!((stats[0].std_dev() - 3.5f64.sqrt()).abs() < 1e-12);
+    /// assert_eq!((stats[1].count, stats[1].missing, stats[1].mean()), (2, 2, 15.0));
+    /// assert_eq!(stats[2].non_numeric, 4);
+    /// assert!(stats[0].is_numeric() && !stats[1].is_numeric());
+    /// ```
+    pub fn scan_csv_columns<R>(
+        reader: R,
+        delimiter: u8,
+        has_headers: bool,
+    ) -> Result<(Vec<String>, usize, Vec<ColumnStats>), CsvError>
     where
-        T: Clone
-            + Copy
-            + Mul<Output = T>
-            + Sub<Output = T>
-            + PartialOrd
-            + num_traits::Zero
-            + num_traits::Pow<f64, Output = T>
-            + Div<Output = T>
-            + std::convert::From<i32>,
-        f64: From<T>,
-        Array1<T>: Sub<Output = Array1<T>>,
+        R: std::io::Read,
     {
-        let mut out = data.clone();
-        let mut nearest_clusters = Array1::<T>::zeros(data.dim().0);
-
-        for (val, nearest) in data.outer_iter().zip(nearest_clusters.iter_mut()) {
-            let mut min_cluster: i32 = 0;
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(has_headers)
+            .delimiter(delimiter)
+            .from_reader(reader);
 
-            let dists = clusters.map_axis(Axis(1), |cluster| dist_sq(val, cluster));
-            let mut min_dist = dists[0];
-            for (j, cluster) in clusters.outer_iter().enumerate().skip(1) {
-                let cur_dist = dist_sq(val, cluster);
-                if cur_dist < min_dist {
-                    min_dist = cur_dist;
-                    min_cluster = j as i32;
-                }
+        let header: Vec<String> = if has_headers {
+            reader.headers()?.iter().map(String::from).collect()
+        } else {
+            Vec::new()
+        };
+        let mut stats = vec![ColumnStats::default(); header.len()];
+        let mut n_rows = 0;
+        let mut record = csv::StringRecord::new();
+        while reader.read_record(&mut record)? {
+            if stats.len() < record.len() {
+                stats.resize(record.len(), ColumnStats::default());
             }
-            *nearest = T::from(min_cluster);
+            for (col_stats, field) in stats.iter_mut().zip(record.iter()) {
+                col_stats.push_field(field);
+            }
+            n_rows += 1;
         }
+        Ok((header, n_rows, stats))
+    }
 
-        out.push_column(nearest_clusters.view()).unwrap();
-        out
+    /// Error produced while reading or writing csv files
+    #[derive(Debug, thiserror::Error)]
+    pub enum CsvError {
+        /// The underlying csv reader failed (I/O or malformed record)
+        #[error("{0}")]
+        Csv(#[from] csv::Error),
+        /// A field could not be parsed into the requested type
+        #[error("Error trying to parse value in ({row}, {col}): {message}")]
+        Parse {
+            /// Zero-based row of the field, not counting the header
+            row: usize,
+            /// Zero-based column of the field
+            col: usize,
+            /// Why parsing failed
+            message: String,
+        },
+        /// The data doesn't have the expected number of rows and columns
+        #[error(
+            "Expected {} rows and {} columns, got {} rows and {} columns",
+            expected.0, expected.1, got.0, got.1
+        )]
+        ShapeMismatch {
+            /// Rows and columns that were expected
+            expected: (usize, usize),
+            /// Rows and columns that were read
+            got: (usize, usize),
+        },
+        /// A file has a different number of columns than the files read before it
+        #[error("{file} has {got} columns, expected {expected}")]
+        ColumnCountMismatch {
+            /// Name of the file
+            file: String,
+            /// Number of columns of the files before
+            expected: usize,
+            /// Number of columns of the file
+            got: usize,
+        },
     }
 
-    /// Compute cluster means using fuzzy k means clustering
+    impl From<std::io::Error> for CsvError {
+        fn from(err: std::io::Error) -> Self {
+            CsvError::Csv(err.into())
+        }
+    }
+
+    /// Lazy row-by-row csv reader
+    ///
+    /// Yields one parsed row at a time instead of loading the whole file like `read_csv`, so
+    /// files larger than memory can be processed. The first line is treated as a header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{read_csv, CsvReader};
+    /// use ndarray::{Array1, Array2};
+    ///
+    /// let fname = std::env::temp_dir().join("k_means_rs_csv_reader_example.csv");
+    /// std::fs::write(&fname, "a;b\n1.5;2\n3;-4.25\n").unwrap();
+    /// let fname = fname.to_str().unwrap().to_string();
+    ///
+    /// let rows: Vec<Array1<f64>> = CsvReader::open(fname.clone(), b';')
+    ///     .unwrap()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// let all: Array2<f64> = read_csv(fname).unwrap();
+    /// assert_eq!(rows.len(), all.nrows());
+    /// for (row, expected) in rows.iter().zip(all.outer_iter()) {
+    ///     assert_eq!(row.view(), expected);
+    /// }
+    /// ```
+    pub struct CsvReader<T> {
+        records: csv::StringRecordsIntoIter<std::fs::File>,
+        row: usize,
+        _marker: std::marker::PhantomData<T>,
+    }
+
+    impl<T> CsvReader<T> {
+        /// Open a csv file for lazy reading
+        ///
+        /// # Arguments
+        ///
+        /// * `fname` - filename
+        /// * `delimiter` - field delimiter
+        pub fn open(fname: String, delimiter: u8) -> Result<Self, CsvError> {
+            let reader = csv::ReaderBuilder::new()
+                .has_headers(true)
+                .delimiter(delimiter)
+                .from_path(fname)?;
+            Ok(CsvReader {
+                records: reader.into_records(),
+                row: 0,
+                _marker: std::marker::PhantomData,
+            })
+        }
+    }
+
+    impl<T> Iterator for CsvReader<T>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Debug,
+    {
+        type Item = Result<Array1<T>, CsvError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(err) => return Some(Err(err.into())),
+            };
+            let row = self.row;
+            self.row += 1;
+            let parsed: Result<Vec<T>, CsvError> = record
+                .iter()
+                .enumerate()
+                .map(|(col, field)| {
+                    field.parse().map_err(|err| CsvError::Parse {
+                        row,
+                        col,
+                        message: format!("{:?}", err),
+                    })
+                })
+                .collect();
+            Some(parsed.map(Array1::from))
+        }
+    }
+
+    /// Convert values from ndarray::Array1 to csv::StringRecord
     ///
     /// # Arguments
-    /// `k` - number of clusters
-    /// `n_iter` - number of iterations to perform
-    /// `q` - fuzzifier
-    /// `data` - data to cluster (rows are data points)
-    pub fn cluster_k_means_fuzzy<T>(k: usize, n_iter: usize, q: f64, data: &Array2<T>) -> Array2<T>
+    ///
+    /// * `vals` - values to convert
+    fn to_record<T>(vals: &ArrayView1<T>) -> csv::StringRecord
     where
-        T: Clone
-            + Copy
-            + Mul<Output = T>
-            + Sub<Output = T>
-            + PartialOrd
-            + num_traits::Zero
-            + num_traits::Pow<f64, Output = T>
-            + Div<Output = T>
-            + std::convert::From<i32>
-            + std::convert::From<f64>,
-        f64: From<T>,
-        rand::distributions::Standard: rand::prelude::Distribution<T>,
-        Array1<T>: Sub<Output = Array1<T>>,
+        T: std::fmt::Display,
     {
-        let size = data.dim();
+        let mut record = csv::StringRecord::with_capacity(10, vals.dim());
+        for val in vals.iter() {
+            record.push_field(format!("{}", val).as_str());
+        }
+        record
+    }
 
-        // cluster initialisation as random between 0 and 1
-        let mut clusters = Array2::<T>::zeros((k, size.1));
-        for mut cluster in clusters.outer_iter_mut() {
-            for i in 0..size.1 {
-                cluster[i] = rand::random();
+    /// A float that is displayed with at most `decimals` decimal places
+    ///
+    /// The value is rounded and trailing zeros are dropped, so whole numbers such as cluster
+    /// labels stay integers. Without `decimals` it is displayed like a plain `f64`. Any writer
+    /// taking `Display` values accepts arrays of these, see `round_values`.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Rounded {
+        /// The value to display
+        pub value: f64,
+        /// Largest number of decimal places
+        pub decimals: Option<usize>,
+    }
+
+    impl std::fmt::Display for Rounded {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            let decimals = match self.decimals {
+                Some(decimals) => decimals,
+                None => return write!(f, "{}", self.value),
+            };
+            let formatted = format!("{:.*}", decimals, self.value);
+            let trimmed = if formatted.contains('.') {
+                formatted.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                &formatted
+            };
+            // values that round to zero shouldn't keep their sign
+            if trimmed == "-0" {
+                write!(f, "0")
+            } else {
+                write!(f, "{}", trimmed)
             }
         }
+    }
 
-        for _ in 0..n_iter {
-            let mut memberships = Array2::<f64>::zeros((size.0, k));
-            compute_memberships(q, &data, &clusters, &mut memberships);
+    /// How floats are written by `format_values`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum FloatFormat {
+        /// At most this many decimal places with trailing zeros dropped, like `Rounded`
+        Fixed(usize),
+        /// Scientific notation with this many decimal places in the mantissa, e.g. `1.30e-47`
+        /// for 2. Keeps the significant digits of values close to zero or very large.
+        Scientific(usize),
+    }
 
-            // compute new cluster means
-            for (mut cluster, membership) in clusters
-                .axis_iter_mut(Axis(0))
-                .zip(memberships.axis_iter(Axis(1)))
-            {
-                let mem_sums = membership.mapv(|val: f64| val.powf(q)).sum();
-                let fac = membership.mapv(|val| val.powf(q) / mem_sums);
-                cluster.assign(&fac.dot(&data.mapv(|val| f64::from(val))).mapv(|val| T::from(val)));
+    /// A float that is displayed in the given `FloatFormat`, or like a plain `f64` without one
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Formatted {
+        /// The value to display
+        pub value: f64,
+        /// How to display it
+        pub format: Option<FloatFormat>,
+    }
+
+    impl std::fmt::Display for Formatted {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self.format {
+                None => write!(f, "{}", self.value),
+                Some(FloatFormat::Fixed(decimals)) => Rounded {
+                    value: self.value,
+                    decimals: Some(decimals),
+                }
+                .fmt(f),
+                Some(FloatFormat::Scientific(decimals)) => {
+                    write!(f, "{:.*e}", decimals, self.value)
+                }
             }
         }
+    }
 
-        clusters
+    /// Name of cluster `label` in `label_map`, or `cluster_<label>` if it has none
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::cluster_name;
+    /// use std::collections::HashMap;
+    ///
+    /// let names = HashMap::from([(0, "low"), (1, "medium"), (2, "high")]);
+    /// assert_eq!(cluster_name(1, &names), "medium");
+    /// assert_eq!(cluster_name(3, &names), "cluster_3");
+    /// assert_eq!(cluster_name(0, &HashMap::new()), "cluster_0");
+    /// ```
+    pub fn cluster_name(label: usize, label_map: &HashMap<usize, &str>) -> String {
+        match label_map.get(&label) {
+            Some(name) => name.to_string(),
+            None => format!("cluster_{}", label),
+        }
+    }
+
+    /// Wrap every value of an array to be written with at most `decimals` decimal places
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`      - values to wrap
+    /// * `decimals` - largest number of decimal places, `None` to write values unchanged
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{round_values, write_csv};
+    /// use ndarray::array;
+    ///
+    /// let mut out = Vec::new();
+    /// let arr = array![[0.123456, 2.0], [-0.00001, 1.5]];
+    /// write_csv(&round_values(&arr, Some(3)), &mut out, b';', None, None).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), "0.123;2\n0;1.5\n");
+    /// ```
+    pub fn round_values(arr: &Array2<f64>, decimals: Option<usize>) -> Array2<Rounded> {
+        arr.mapv(|value| Rounded { value, decimals })
+    }
+
+    /// Wrap every value of an array to be written in `format`
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`    - values to wrap
+    /// * `format` - how to write the values, `None` to write them unchanged
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{format_values, write_csv, FloatFormat};
+    /// use ndarray::array;
+    ///
+    /// let arr = array![[1.3e-47, 0.5], [1.0, 2.5e12]];
+    /// let mut out = Vec::new();
+    /// let scientific = format_values(&arr, Some(FloatFormat::Scientific(2)));
+    /// write_csv(&scientific, &mut out, b';', None, None).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), "1.30e-47;5.00e-1\n1.00e0;2.50e12\n");
+    ///
+    /// let mut out = Vec::new();
+    /// let fixed = format_values(&arr, Some(FloatFormat::Fixed(3)));
+    /// write_csv(&fixed, &mut out, b';', None, None).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), "0;0.5\n1;2500000000000\n");
+    /// ```
+    pub fn format_values(arr: &Array2<f64>, format: Option<FloatFormat>) -> Array2<Formatted> {
+        arr.mapv(|value| Formatted { value, format })
+    }
+
+    /// Write ndarray::Array2 as csv to any writer, e.g. stdout
+    ///
+    /// If `index_header` is given, rows are preceded by their number `0, 1, 2, ...` in a column
+    /// of that name. If `header` is given, a header line is written first.
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`          - array to write
+    /// * `writer`       - where to write the csv to
+    /// * `delimiter`    - delimiter to use in csv
+    /// * `header`       - column names, one per column of `arr`
+    /// * `index_header` - name of the row index column, or `None` for no row index
+    ///
+    /// # Errors
+    ///
+    /// `ShapeMismatch` if the number of column names doesn't match the number of columns, other
+    /// `CsvError`s if writing fails
+    pub fn write_csv<T, W>(
+        arr: &Array2<T>,
+        writer: W,
+        delimiter: u8,
+        header: Option<&[String]>,
+        index_header: Option<&str>,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+        W: std::io::Write,
+    {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(writer);
+
+        if let Some(header) = header {
+            if header.len() != arr.ncols() {
+                return Err(CsvError::ShapeMismatch {
+                    expected: (arr.nrows(), header.len()),
+                    got: arr.dim(),
+                });
+            }
+            writer.write_record(
+                index_header
+                    .into_iter()
+                    .chain(header.iter().map(String::as_str)),
+            )?;
+        }
+        for (i, row) in arr.outer_iter().enumerate() {
+            let mut record = to_record(&row);
+            if index_header.is_some() {
+                record = std::iter::once(i.to_string().as_str())
+                    .chain(record.iter())
+                    .collect();
+            }
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write ndarray::Array2 to csv file
+    ///
+    /// # Arguments
+    ///
+    /// * `arr` - array to write to file
+    /// * `fname` - target filename
+    /// * `delimiter' - delimiter to use in csv
+    pub fn to_csv<T>(arr: Array2<T>, fname: String, delimiter: u8) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+    {
+        write_csv(&arr, std::fs::File::create(fname)?, delimiter, None, None)
+    }
+
+    /// Write ndarray::Array2 to csv file with every value in scientific notation
+    ///
+    /// Unlike plain decimal formatting, values spanning many orders of magnitude such as
+    /// memberships close to zero keep `precision` significant decimal places.
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`       - array to write to file
+    /// * `fname`     - target filename
+    /// * `delimiter` - delimiter to use in csv
+    /// * `precision` - number of decimal places of the mantissa
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::to_csv_scientific;
+    /// use ndarray::array;
+    ///
+    /// let fname = std::env::temp_dir().join("k_means_rs_scientific_example.csv");
+    /// let fname = fname.to_str().unwrap();
+    /// let arr = array![[1.3e-47, 1.0 - 1.3e-47], [4.2e300, -0.0031]];
+    /// to_csv_scientific(&arr, fname, b';', 3).unwrap();
+    ///
+    /// let written = std::fs::read_to_string(fname).unwrap();
+    /// assert_eq!(written, "1.300e-47;1.000e0\n4.200e300;-3.100e-3\n");
+    /// ```
+    pub fn to_csv_scientific<T>(
+        arr: &Array2<T>,
+        fname: &str,
+        delimiter: u8,
+        precision: usize,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::LowerExp,
+    {
+        write_csv(
+            &arr.map(|val| format!("{:.*e}", precision, val)),
+            std::fs::File::create(fname)?,
+            delimiter,
+            None,
+            None,
+        )
+    }
+
+    /// Write ndarray::Array2 to csv file, preceded by a header line
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`       - array to write to file
+    /// * `header`    - column names, one per column of `arr`
+    /// * `fname`     - target filename
+    /// * `delimiter` - delimiter to use in csv
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of column names doesn't match the number of columns
+    pub fn to_csv_with_header<T>(
+        arr: Array2<T>,
+        header: &[String],
+        fname: String,
+        delimiter: u8,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+    {
+        write_csv(
+            &arr,
+            std::fs::File::create(fname)?,
+            delimiter,
+            Some(header),
+            None,
+        )
+    }
+
+    /// Write ndarray::Array2 to csv file with the row number as first column
+    ///
+    /// Rows are numbered `0, 1, 2, ...` so written results can be matched back to the input
+    /// data. If `header` is given, a header line with `index_header` followed by `header` is
+    /// written first.
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`          - array to write to file
+    /// * `fname`        - target filename
+    /// * `delimiter`    - delimiter to use in csv
+    /// * `index_header` - name of the row index column
+    /// * `header`       - column names, one per column of `arr`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{read_csv, to_csv_with_row_index};
+    /// use ndarray::{array, Array2};
+    ///
+    /// let fname = std::env::temp_dir().join("k_means_rs_row_index_example.csv");
+    /// let fname = fname.to_str().unwrap();
+    /// let header = [String::from("x"), String::from("y")];
+    /// let arr = array![[0.5, 1.5], [2.5, 3.5], [4.5, 5.5]];
+    /// to_csv_with_row_index(&arr, fname, b';', "index", Some(&header)).unwrap();
+    ///
+    /// let read: Array2<f64> = read_csv(fname.to_string()).unwrap();
+    /// assert_eq!(read.column(0), array![0.0, 1.0, 2.0]);
+    /// assert_eq!(read.slice(ndarray::s![.., 1..]), arr);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of column names doesn't match the number of columns
+    pub fn to_csv_with_row_index<T>(
+        arr: &Array2<T>,
+        fname: &str,
+        delimiter: u8,
+        index_header: &str,
+        header: Option<&[String]>,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+    {
+        write_csv(
+            arr,
+            std::fs::File::create(fname)?,
+            delimiter,
+            header,
+            Some(index_header),
+        )
+    }
+
+    /// Write cluster centers to a csv file, one row per cluster with a leading `cluster` column
+    ///
+    /// # Arguments
+    ///
+    /// * `centers`       - cluster centers (rows)
+    /// * `feature_names` - name of every feature column
+    /// * `fname`         - target filename
+    /// * `delimiter`     - delimiter to use in csv
+    pub fn write_centers<T>(
+        centers: &Array2<T>,
+        feature_names: &[String],
+        fname: String,
+        delimiter: u8,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+    {
+        write_centers_to(
+            centers,
+            feature_names,
+            std::fs::File::create(fname)?,
+            delimiter,
+        )
+    }
+
+    /// Like `write_centers`, but to any writer, e.g. stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `centers`       - cluster centers (rows)
+    /// * `feature_names` - name of every feature column
+    /// * `writer`        - where to write the csv to
+    /// * `delimiter`     - delimiter to use in csv
+    pub fn write_centers_to<T, W>(
+        centers: &Array2<T>,
+        feature_names: &[String],
+        writer: W,
+        delimiter: u8,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+        W: std::io::Write,
+    {
+        write_csv(
+            centers,
+            writer,
+            delimiter,
+            Some(feature_names),
+            Some("cluster"),
+        )
+    }
+
+    /// Write a membership matrix to a csv file with a `membership_<j>` column per cluster
+    ///
+    /// # Arguments
+    ///
+    /// * `memberships` - memberships of data points (rows) in clusters (columns)
+    /// * `fname`       - target filename
+    /// * `delimiter`   - delimiter to use in csv
+    pub fn write_memberships(
+        memberships: &Array2<f64>,
+        fname: String,
+        delimiter: u8,
+    ) -> Result<(), CsvError> {
+        write_memberships_to(memberships, std::fs::File::create(fname)?, delimiter)
+    }
+
+    /// Like `write_memberships`, but to any writer, e.g. stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `memberships` - memberships of data points (rows) in clusters (columns)
+    /// * `writer`      - where to write the csv to
+    /// * `delimiter`   - delimiter to use in csv
+    pub fn write_memberships_to<T, W>(
+        memberships: &Array2<T>,
+        writer: W,
+        delimiter: u8,
+    ) -> Result<(), CsvError>
+    where
+        T: std::fmt::Display,
+        W: std::io::Write,
+    {
+        let header: Vec<String> = (0..memberships.ncols())
+            .map(|j| format!("membership_{}", j))
+            .collect();
+        write_csv(memberships, writer, delimiter, Some(&header), None)
+    }
+
+    /// Write the memberships above `threshold` to a csv file in coordinate format
+    ///
+    /// Every entry is written as a `row,col,value` line, all other memberships are left out. For
+    /// large `k` most memberships are close to zero, so this is much smaller than the dense
+    /// output of `write_memberships`.
+    ///
+    /// # Arguments
+    ///
+    /// * `memberships` - memberships of data points (rows) in clusters (columns)
+    /// * `fname`       - target filename
+    /// * `threshold`   - only memberships greater than this are written
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::{read_sparse_memberships, write_sparse_memberships};
+    /// use ndarray::array;
+    ///
+    /// let fname = std::env::temp_dir().join("k_means_rs_sparse_example.csv");
+    /// let fname = fname.to_str().unwrap();
+    /// let memberships = array![[0.995, 0.005, 0.0], [0.2, 0.3, 0.5], [0.001, 0.009, 0.99]];
+    /// write_sparse_memberships(&memberships, fname, 0.01).unwrap();
+    ///
+    /// let read = read_sparse_memberships(fname, 3, 3, 0.01).unwrap();
+    /// assert_eq!(read, array![[0.995, 0.0, 0.0], [0.2, 0.3, 0.5], [0.0, 0.0, 0.99]]);
+    ///
+    /// // entries must lie within the matrix
+    /// let err = read_sparse_memberships(fname, 2, 3, 0.01).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Error trying to parse value in (4, 0): entry (2, 2) lies outside of the 2x3 \
+    ///      membership matrix"
+    /// );
+    /// ```
+    pub fn write_sparse_memberships(
+        memberships: &Array2<f64>,
+        fname: &str,
+        threshold: f64,
+    ) -> Result<(), CsvError> {
+        let mut writer = csv::Writer::from_path(fname)?;
+
+        writer.write_record(["row", "col", "value"])?;
+        for ((i, j), value) in memberships.indexed_iter() {
+            if *value > threshold {
+                writer.write_record(&[i.to_string(), j.to_string(), value.to_string()])?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read memberships written by `write_sparse_memberships` into a dense matrix
+    ///
+    /// Entries missing from the file, or not greater than `threshold`, are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `fname`     - file to read
+    /// * `n_rows`    - number of data points
+    /// * `n_cols`    - number of clusters
+    /// * `threshold` - memberships not greater than this are left at zero
+    ///
+    /// # Errors
+    ///
+    /// `Csv` if the file can't be read, `Parse` if a field isn't a number or an entry lies
+    /// outside of `n_rows` x `n_cols`
+    pub fn read_sparse_memberships(
+        fname: &str,
+        n_rows: usize,
+        n_cols: usize,
+        threshold: f64,
+    ) -> Result<Array2<f64>, CsvError> {
+        let mut reader = csv::Reader::from_path(fname)?;
+        let mut memberships = Array2::<f64>::zeros((n_rows, n_cols));
+        for (row, record) in reader.records().enumerate() {
+            let record = record?;
+            let field = |col: usize| {
+                record
+                    .get(col)
+                    .map(str::trim)
+                    .ok_or_else(|| CsvError::Parse {
+                        row,
+                        col,
+                        message: String::from("missing field"),
+                    })
+            };
+            let parse_err = |col: usize, err: &dyn std::fmt::Display| CsvError::Parse {
+                row,
+                col,
+                message: err.to_string(),
+            };
+            let i: usize = field(0)?.parse().map_err(|err| parse_err(0, &err))?;
+            let j: usize = field(1)?.parse().map_err(|err| parse_err(1, &err))?;
+            let value: f64 = field(2)?.parse().map_err(|err| parse_err(2, &err))?;
+            if i >= n_rows || j >= n_cols {
+                return Err(CsvError::Parse {
+                    row,
+                    col: 0,
+                    message: format!(
+                        "entry ({}, {}) lies outside of the {}x{} membership matrix",
+                        i, j, n_rows, n_cols
+                    ),
+                });
+            }
+            if value > threshold {
+                memberships[[i, j]] = value;
+            }
+        }
+        Ok(memberships)
+    }
+
+    /// Layout of a multi-label csv file
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MultiLabelFormat {
+        /// One row per data point with a `cluster_<j>` column per cluster, holding the membership
+        /// if the cluster qualifies and 0 otherwise
+        Wide,
+        /// One `point_id, cluster, membership` row per qualifying cluster of every data point
+        Long,
+    }
+
+    /// Write multi-labels as returned by `algo::multi_labels` to a csv file
+    ///
+    /// # Arguments
+    ///
+    /// * `labels`    - qualifying clusters and memberships per data point
+    /// * `k`         - number of clusters
+    /// * `fname`     - target filename
+    /// * `delimiter` - delimiter to use in csv
+    /// * `format`    - layout of the file
+    pub fn write_multi_labels(
+        labels: &[Vec<(usize, f64)>],
+        k: usize,
+        fname: String,
+        delimiter: u8,
+        format: MultiLabelFormat,
+    ) -> Result<(), CsvError> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_path(fname)?;
+
+        match format {
+            MultiLabelFormat::Wide => {
+                let header: Vec<String> = (0..k).map(|j| format!("cluster_{}", j)).collect();
+                writer.write_record(&header)?;
+                for point_labels in labels {
+                    let mut row = Array1::<f64>::zeros(k);
+                    for (cluster, membership) in point_labels {
+                        row[*cluster] = *membership;
+                    }
+                    writer.write_record(&to_record(&row.view()))?;
+                }
+            }
+            MultiLabelFormat::Long => {
+                writer.write_record(["point_id", "cluster", "membership"])?;
+                for (point_id, point_labels) in labels.iter().enumerate() {
+                    for (cluster, membership) in point_labels {
+                        writer.write_record([
+                            point_id.to_string(),
+                            cluster.to_string(),
+                            membership.to_string(),
+                        ])?;
+                    }
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write a matrix as a NumPy `.npy` file of little-endian 64-bit floats in row-major order
+    ///
+    /// The file can be loaded with `numpy.load`.
+    ///
+    /// # Arguments
+    ///
+    /// * `arr`    - matrix to write
+    /// * `writer` - destination of the file contents
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::write_npy;
+    /// use ndarray::array;
+    ///
+    /// let mut bytes = Vec::new();
+    /// write_npy(&array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]], &mut bytes).unwrap();
+    /// assert!(bytes.starts_with(b"\x93NUMPY\x01\x00"));
+    /// let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    /// assert_eq!((10 + header_len) % 64, 0);
+    /// let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    /// assert!(header.contains("'shape': (2, 3)"));
+    /// assert_eq!(bytes.len(), 10 + header_len + 6 * 8);
+    /// assert_eq!(bytes[bytes.len() - 8..], 6f64.to_le_bytes());
+    /// ```
+    pub fn write_npy<W>(arr: &Array2<f64>, mut writer: W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        // magic, version 1.0 and the header length come before the header
+        const PREAMBLE_LEN: usize = 10;
+        let mut header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+            arr.nrows(),
+            arr.ncols()
+        );
+        // the data has to start at a multiple of 64 bytes, the header ends with a newline
+        let padded_len = (PREAMBLE_LEN + header.len() + 1).div_ceil(64) * 64 - PREAMBLE_LEN;
+        header.push_str(&" ".repeat(padded_len - header.len() - 1));
+        header.push('\n');
+
+        writer.write_all(b"\x93NUMPY\x01\x00")?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+        for val in arr.iter() {
+            writer.write_all(&val.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Labels, memberships and centers of a clustering as a JSON document
+    ///
+    /// The document is an object with the keys `feature_names`, `centers` (one array per
+    /// cluster), `labels` (one cluster per data point) and `memberships` (one array per data
+    /// point). Callers may add further keys before writing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels`        - cluster per data point
+    /// * `memberships`   - memberships of data points (rows) in clusters (columns)
+    /// * `centers`       - cluster centers (rows)
+    /// * `feature_names` - name of every feature column
+    pub fn results_json(
+        labels: &Array1<usize>,
+        memberships: &Array2<f64>,
+        centers: &Array2<f64>,
+        feature_names: &[String],
+    ) -> serde_json::Value {
+        let rows = |arr: &Array2<f64>| -> Vec<Vec<f64>> {
+            arr.outer_iter().map(|row| row.to_vec()).collect()
+        };
+        serde_json::json!({
+            "feature_names": feature_names,
+            "centers": rows(centers),
+            "labels": labels.to_vec(),
+            "memberships": rows(memberships),
+        })
+    }
+
+    /// Write labels, memberships and centers of a clustering to a single JSON document
+    ///
+    /// The document is that of `results_json`, followed by a newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels`        - cluster per data point
+    /// * `memberships`   - memberships of data points (rows) in clusters (columns)
+    /// * `centers`       - cluster centers (rows)
+    /// * `feature_names` - name of every feature column
+    /// * `writer`        - where to write the JSON to
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::io::write_results_json;
+    /// use ndarray::array;
+    ///
+    /// let mut out = Vec::new();
+    /// let memberships = array![[0.9, 0.1], [0.2, 0.8], [0.7, 0.3]];
+    /// let centers = array![[0.0], [1.0]];
+    /// let names = vec![String::from("x")];
+    /// write_results_json(&array![0, 1, 0], &memberships, &centers, &names, &mut out).unwrap();
+    ///
+    /// let doc: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    /// assert_eq!(doc["labels"], serde_json::json!([0, 1, 0]));
+    /// assert_eq!(doc["memberships"].as_array().unwrap().len(), 3);
+    /// assert_eq!(doc["centers"][1], serde_json::json!([1.0]));
+    /// ```
+    pub fn write_results_json<W>(
+        labels: &Array1<usize>,
+        memberships: &Array2<f64>,
+        centers: &Array2<f64>,
+        feature_names: &[String],
+        mut writer: W,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let doc = results_json(labels, memberships, centers, feature_names);
+        serde_json::to_writer(&mut writer, &doc)?;
+        writeln!(writer)?;
+        writer.flush()
+    }
+}
+
+/// Clustering algorithms, distance metrics and cluster validity measures
+pub mod algo {
+    use ndarray::{self, s, Array1, Array2, ArrayView1, ArrayView2, ArrayViewMut1, Axis};
+    use num_traits::AsPrimitive;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::ops::{Div, Mul, Sub};
+    use std::sync::Arc;
+    use std::time::Duration;
+    // std's clock panics on wasm32-unknown-unknown
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::time::Instant;
+    #[cfg(target_arch = "wasm32")]
+    use web_time::Instant;
+
+    /// Number of data points per chunk in the membership and center update pass.
+    ///
+    /// Partial sums are formed per chunk and combined in chunk order, so the result does not depend
+    /// on the number of threads used.
+    const CHUNK_ROWS: usize = 256;
+
+    /// Center shifts up to this fraction of the largest center norm count as no shift.
+    ///
+    /// Euclidean distances come from a matrix product, which rounds differently for every set of
+    /// centers, so centers can keep jittering by a few ulps around their fixed point.
+    const CENTER_ROUNDING: f64 = 64.0 * f64::EPSILON;
+
+    /// Reasons a clustering function can fail
+    #[derive(Clone, Debug, PartialEq, thiserror::Error)]
+    pub enum AlgoError {
+        /// An array has a different shape than the other inputs require
+        #[error("Expected an array of shape {expected:?}, got {got:?}")]
+        DimensionMismatch {
+            /// Rows and columns the other inputs require
+            expected: (usize, usize),
+            /// Rows and columns of the array
+            got: (usize, usize),
+        },
+        /// The fuzzifier must be finite and greater than 1
+        #[error("Fuzzifier must be finite and greater than 1, got {0}")]
+        InvalidFuzzifier(f64),
+        /// More clusters were requested than there are data points
+        #[error("Can't form {k} clusters from {n} data points")]
+        TooManyClusters {
+            /// Number of clusters
+            k: usize,
+            /// Number of data points
+            n: usize,
+        },
+        /// The computation produced non-finite values
+        #[error("Numerical failure: {0}")]
+        NumericalFailure(&'static str),
+        /// There are no data points to cluster
+        #[error("No data points to cluster")]
+        EmptyData,
+        /// At least one cluster is needed
+        #[error("Number of clusters needs to be at least 1")]
+        NoClusters,
+        /// A kernel bandwidth must be finite and positive
+        #[error("Bandwidth must be finite and positive, got {0}")]
+        InvalidBandwidth(f64),
+        /// Data point weights must be finite and positive
+        #[error("Weights must be finite and positive, got {0}")]
+        InvalidWeight(f64),
+        /// The fraction of data points to trim must be in `[0, 1)`
+        #[error("Trim fraction must be in [0, 1), got {0}")]
+        InvalidTrimFraction(f64),
+        /// A label is not smaller than the number of labels it was declared to have
+        #[error("Label {label} is out of range for {n_labels} labels")]
+        LabelOutOfRange {
+            /// The offending label
+            label: usize,
+            /// Number of labels
+            n_labels: usize,
+        },
+        /// A range of cluster numbers to choose from is empty
+        #[error("No numbers of clusters to choose from")]
+        EmptyClusterRange,
+        /// Stability can't be judged without resampling the data at least once
+        #[error("Number of bootstrap fits needs to be at least 1")]
+        NoBootstrapFits,
+    }
+
+    /// Check that `q` is usable as a fuzzifier
+    fn check_fuzzifier(q: f64) -> Result<(), AlgoError> {
+        if q > 1.0 && q.is_finite() {
+            Ok(())
+        } else {
+            Err(AlgoError::InvalidFuzzifier(q))
+        }
+    }
+
+    /// Check that `bandwidth` is usable as a kernel width
+    fn check_bandwidth(bandwidth: f64) -> Result<(), AlgoError> {
+        if bandwidth > 0.0 && bandwidth.is_finite() {
+            Ok(())
+        } else {
+            Err(AlgoError::InvalidBandwidth(bandwidth))
+        }
+    }
+
+    /// Check that `centers` has one column per feature of `data`
+    fn check_features(n_features: usize, centers: (usize, usize)) -> Result<(), AlgoError> {
+        if centers.1 == n_features {
+            Ok(())
+        } else {
+            Err(AlgoError::DimensionMismatch {
+                expected: (centers.0, n_features),
+                got: centers,
+            })
+        }
+    }
+
+    /// Compute Squared distance between 2 Arrays / Points of Data
+    ///
+    /// # Arguments
+    ///
+    /// * `a`, `b` - Arrays to compute distances between
+    fn dist_sq<T>(a: ArrayView1<T>, b: ArrayView1<T>) -> f64
+    where
+        T: Clone + Copy,
+        f64: From<T>,
+    {
+        // accumulate in f64 so f32 data doesn't lose precision
+        a.iter()
+            .zip(b.iter())
+            .map(|(x, y)| (f64::from(*x) - f64::from(*y)).powi(2))
+            .sum()
+    }
+
+    /// Squared euclidean distances between every data point and every center
+    ///
+    /// Uses `|x - c|^2 = |x|^2 - 2 x.c + |c|^2`, so the bulk of the work is a single matrix
+    /// product. Data points and centers are shifted by the mean of the data points first, which
+    /// leaves the distances unchanged but keeps the norms from dwarfing them when the data lies
+    /// far from the origin. The remaining cancellation is relative to the spread of the data and
+    /// can make distances that should be zero come out slightly positive; negative results are
+    /// clamped to zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::euclidean_dist_sq_matrix;
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.0], [3.0, 4.0]];
+    /// let centers = array![[0.0, 0.0], [3.0, 0.0]];
+    /// let dists = euclidean_dist_sq_matrix(&data, &centers).unwrap();
+    /// assert_eq!(dists, array![[0.0, 9.0], [25.0, 16.0]]);
+    /// ```
+    pub fn euclidean_dist_sq_matrix<T>(
+        data: &Array2<T>,
+        centers: &Array2<T>,
+    ) -> Result<Array2<f64>, AlgoError>
+    where
+        T: Clone + Copy,
+        f64: From<T>,
+    {
+        check_features(data.ncols(), centers.dim())?;
+        Ok(dist_sq_product(
+            data.mapv(f64::from),
+            centers.mapv(f64::from),
+        ))
+    }
+
+    /// Squared euclidean distances by a matrix product, see `euclidean_dist_sq_matrix`
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - data points (rows), centered in place
+    /// * `c` - cluster centers (rows), centered in place
+    fn dist_sq_product(mut x: Array2<f64>, mut c: Array2<f64>) -> Array2<f64> {
+        if let Some(mean) = x.mean_axis(Axis(0)) {
+            x -= &mean;
+            c -= &mean;
+        }
+        let x_sq = x.map_axis(Axis(1), |row| row.dot(&row));
+        let c_sq = c.map_axis(Axis(1), |row| row.dot(&row));
+
+        let mut dists = x.dot(&c.t());
+        for ((i, j), dist) in dists.indexed_iter_mut() {
+            *dist = (x_sq[i] - 2.0 * *dist + c_sq[j]).max(0.0);
+        }
+        dists
+    }
+
+    /// Running sum that compensates for lost low-order bits (Neumaier's variant of Kahan summation)
+    #[derive(Clone, Copy, Debug, Default)]
+    struct CompensatedSum {
+        sum: f64,
+        compensation: f64,
+    }
+
+    impl CompensatedSum {
+        fn add(&mut self, val: f64) {
+            let t = self.sum + val;
+            if self.sum.abs() >= val.abs() {
+                self.compensation += (self.sum - t) + val;
+            } else {
+                self.compensation += (val - t) + self.sum;
+            }
+            self.sum = t;
+        }
+
+        fn value(&self) -> f64 {
+            self.sum + self.compensation
+        }
+    }
+
+    /// Sum values using compensated summation
+    fn compensated_sum<I: IntoIterator<Item = f64>>(values: I) -> f64 {
+        let mut sum = CompensatedSum::default();
+        for val in values {
+            sum.add(val);
+        }
+        sum.value()
+    }
+
+    /// Calculate fuzzy memberships for elements of data to clusters in cluster and write to memberships
+    ///
+    /// With a single cluster every data point belongs to it entirely, even one lying on its center.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`            - fuzzifier
+    /// * `data`         - data to compute memberships for
+    /// * `clusters`     - clusters
+    /// * `memberships`  - write membership information here
+    ///
+    /// # Errors
+    ///
+    /// `InvalidFuzzifier` for `q <= 1`, `DimensionMismatch` if `clusters` or `memberships` don't
+    /// fit `data`
+    pub fn compute_memberships<T>(
+        q: f64,
+        data: &Array2<T>,
+        clusters: &Array2<T>,
+        memberships: &mut Array2<f64>,
+    ) -> Result<(), AlgoError>
+    where
+        T: Clone + Copy,
+        f64: From<T>,
+    {
+        check_fuzzifier(q)?;
+        if memberships.dim() != (data.nrows(), clusters.nrows()) {
+            return Err(AlgoError::DimensionMismatch {
+                expected: (data.nrows(), clusters.nrows()),
+                got: memberships.dim(),
+            });
+        }
+        if clusters.nrows() == 1 {
+            check_features(data.ncols(), clusters.dim())?;
+            memberships.fill(1.0);
+            return Ok(());
+        }
+        // Membrships are distances for now
+        memberships.assign(&euclidean_dist_sq_matrix(data, clusters)?);
+        memberships_from_dissimilarities(q, memberships);
+        Ok(())
+    }
+
+    /// Calculate fuzzy memberships like `compute_memberships` without allocating or bounds checks
+    ///
+    /// Data points lying on one or more centers belong to those centers only.
+    ///
+    /// The squared distances are summed from exact differences instead of the matrix product of
+    /// `compute_memberships`, which would need to allocate. The memberships differ by about the
+    /// product's rounding error, which is relative to the spread of the data, over the distances
+    /// to the other centers. A point on a center always belongs to it entirely here, but may get
+    /// a membership just below 1 from `compute_memberships`.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`               - fuzzifier
+    /// * `data`            - data to compute memberships for
+    /// * `clusters`        - clusters
+    /// * `memberships_out` - write membership information here
+    /// * `dist_scratch`    - receives the normalisation sum of every data point, infinite for
+    ///   points on a center
+    ///
+    /// # Safety
+    ///
+    /// `memberships_out` must have shape `(data.nrows(), clusters.nrows())`, `dist_scratch` must
+    /// have length `data.nrows()` and `clusters` must have as many columns as `data`. Use
+    /// `compute_memberships_with_scratch` to have the shapes checked.
+    pub unsafe fn compute_memberships_unchecked<T>(
+        q: f64,
+        data: &Array2<T>,
+        clusters: &Array2<T>,
+        memberships_out: &mut Array2<f64>,
+        dist_scratch: &mut Array1<f64>,
+    ) where
+        T: Clone + Copy,
+        f64: From<T>,
+    {
+        let (n_data, n_features) = data.dim();
+        let k = clusters.nrows();
+        if k == 1 {
+            memberships_out.fill(1.0);
+            return;
+        }
+        for i in 0..n_data {
+            for j in 0..k {
+                let mut dist = 0.0;
+                for f in 0..n_features {
+                    let diff = f64::from(*data.uget((i, f))) - f64::from(*clusters.uget((j, f)));
+                    dist += diff * diff;
+                }
+                *memberships_out.uget_mut((i, j)) = dist;
+            }
+            *dist_scratch.uget_mut(i) =
+                row_memberships_from_dissimilarities(q, memberships_out.row_mut(i));
+        }
+    }
+
+    /// Calculate fuzzy memberships like `compute_memberships`, reusing caller-provided buffers
+    ///
+    /// # Arguments
+    ///
+    /// * `q`               - fuzzifier
+    /// * `data`            - data to compute memberships for
+    /// * `clusters`        - clusters
+    /// * `memberships_out` - write membership information here, shape `(n_data, k)`
+    /// * `dist_scratch`    - receives the normalisation sum of every data point, length `n_data`
+    ///
+    /// # Errors
+    ///
+    /// `InvalidFuzzifier` for `q <= 1`, `DimensionMismatch` if any of the shapes don't match. The
+    /// scratch buffer is reported as a single column.
+    pub fn compute_memberships_with_scratch<T>(
+        q: f64,
+        data: &Array2<T>,
+        clusters: &Array2<T>,
+        memberships_out: &mut Array2<f64>,
+        dist_scratch: &mut Array1<f64>,
+    ) -> Result<(), AlgoError>
+    where
+        T: Clone + Copy,
+        f64: From<T>,
+    {
+        check_fuzzifier(q)?;
+        check_features(data.ncols(), clusters.dim())?;
+        if memberships_out.dim() != (data.nrows(), clusters.nrows()) {
+            return Err(AlgoError::DimensionMismatch {
+                expected: (data.nrows(), clusters.nrows()),
+                got: memberships_out.dim(),
+            });
+        }
+        if dist_scratch.len() != data.nrows() {
+            return Err(AlgoError::DimensionMismatch {
+                expected: (data.nrows(), 1),
+                got: (dist_scratch.len(), 1),
+            });
+        }
+        // SAFETY: all shapes were checked above
+        unsafe { compute_memberships_unchecked(q, data, clusters, memberships_out, dist_scratch) };
+        Ok(())
+    }
+
+    /// Compute nearest cluster per data point from clusters
+    ///
+    /// # Arguments
+    ///
+    /// `data` - datapoints to compute memberships for.
+    /// `clusters` - Cluster Centers to compute nearest cluster for
+    ///
+    /// # Errors
+    ///
+    /// `EmptyData` if there are no clusters, `DimensionMismatch` if `clusters` doesn't have as
+    /// many columns as `data`
+    pub fn compute_nearest<T>(
+        data: &Array2<T>,
+        clusters: &Array2<T>,
+    ) -> Result<Array2<T>, AlgoError>
+    where
+        T: Clone
+            + Copy
+            + Mul<Output = T>
+            + Sub<Output = T>
+            + PartialOrd
+            + num_traits::Zero
+            + num_traits::Pow<f64, Output = T>
+            + Div<Output = T>
+            + std::convert::From<i32>,
+        f64: From<T>,
+        Array1<T>: Sub<Output = Array1<T>>,
+    {
+        if clusters.nrows() == 0 {
+            return Err(AlgoError::EmptyData);
+        }
+        check_features(data.ncols(), clusters.dim())?;
+        let mut out = data.clone();
+        let mut nearest_clusters = Array1::<T>::zeros(data.dim().0);
+
+        for (val, nearest) in data.outer_iter().zip(nearest_clusters.iter_mut()) {
+            let mut min_cluster: i32 = 0;
+
+            let dists = clusters.map_axis(Axis(1), |cluster| dist_sq(val, cluster));
+            let mut min_dist = dists[0];
+            for (j, cluster) in clusters.outer_iter().enumerate().skip(1) {
+                let cur_dist = dist_sq(val, cluster);
+                if cur_dist < min_dist {
+                    min_dist = cur_dist;
+                    min_cluster = j as i32;
+                }
+            }
+            *nearest = T::from(min_cluster);
+        }
+
+        out.push_column(nearest_clusters.view()).unwrap();
+        Ok(out)
+    }
+
+    /// Reason a fit stopped iterating
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum StopReason {
+        /// The iteration cap was reached
+        MaxIter,
+        /// The largest center shift fell below the tolerance
+        Tolerance,
+        /// The wall-clock budget was used up
+        TimeBudget,
+        /// The per-iteration callback asked to stop
+        Callback,
+    }
+
+    /// Progress information passed to the per-iteration callback
+    #[derive(Clone, Copy, Debug)]
+    pub struct IterationInfo {
+        /// Number of completed iterations
+        pub iteration: usize,
+        /// Objective of the memberships computed in this iteration
+        pub objective: f64,
+        /// Largest distance a cluster center moved in this iteration
+        pub max_shift: f64,
+        /// Wall-clock time since the start of the fit
+        pub elapsed: Duration,
+    }
+
+    /// Result of a clustering run
+    ///
+    /// With the `serde` feature, results are serialized with a `format_version` field holding
+    /// `FORMAT_VERSION`. Results written with another layout version are refused on load.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use k_means_rs::algo::ClusteringResult;
+    /// use k_means_rs::{ClusteringAlgorithm, FuzzyKMeansConfig};
+    /// use ndarray::{array, Array2};
+    ///
+    /// let data = array![[0.1, 0.3], [0.2, 0.1], [5.7, 5.1], [5.3, 4.9], [0.15, 0.2]];
+    /// let result = FuzzyKMeansConfig::new(2, 100, 2.0).fit(&data).unwrap();
+    /// let same_bits = |a: &ClusteringResult<f64>| {
+    ///     let bits = |arr: &Array2<f64>| arr.iter().map(|val| val.to_bits()).collect::<Vec<_>>();
+    ///     a.centers.dim() == result.centers.dim()
+    ///         && bits(&a.centers) == bits(&result.centers)
+    ///         && a.memberships.dim() == result.memberships.dim()
+    ///         && bits(&a.memberships) == bits(&result.memberships)
+    ///         && a.labels == result.labels
+    ///         && a.objective.to_bits() == result.objective.to_bits()
+    /// };
+    ///
+    /// let json = serde_json::to_string(&result).unwrap();
+    /// assert!(same_bits(&serde_json::from_str(&json).unwrap()));
+    /// let bytes = bincode::serialize(&result).unwrap();
+    /// assert!(same_bits(&bincode::deserialize(&bytes).unwrap()));
+    ///
+    /// let newer = json.replace("\"format_version\":2", "\"format_version\":3");
+    /// let err = serde_json::from_str::<ClusteringResult<f64>>(&newer).unwrap_err();
+    /// assert!(err.to_string().starts_with("Unsupported format version 3, expected 2"));
+    /// # }
+    /// ```
+    #[derive(Clone, Debug)]
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(
+            into = "ClusteringResultRepr<T>",
+            try_from = "ClusteringResultRepr<T>",
+            bound(
+                serialize = "T: Clone + serde::Serialize",
+                deserialize = "T: serde::Deserialize<'de>"
+            )
+        )
+    )]
+    pub struct ClusteringResult<T> {
+        /// Cluster centers (rows are clusters)
+        pub centers: Array2<T>,
+        /// Membership of each data point (rows) in each cluster (columns)
+        pub memberships: Array2<f64>,
+        /// Cluster with the highest membership per data point
+        pub labels: Array1<usize>,
+        /// Fuzzy k means objective for the final centers and memberships
+        pub objective: f64,
+        /// Number of iterations performed
+        pub n_iter: usize,
+        /// Whether the center shift fell below the tolerance
+        pub converged: bool,
+        /// Reason the fit stopped iterating
+        pub stopped_by: StopReason,
+        /// Data points that were left out of the last center update
+        pub trimmed: Array1<bool>,
+        /// How far every cluster center moved in each iteration, see `cluster_center_movement`.
+        /// Only recorded if the config asks to track convergence, empty otherwise. Describes the
+        /// clusters as fitted, before any merging.
+        pub center_movement_history: Vec<Array1<f64>>,
+    }
+
+    /// Layout version of serialized `ClusteringResult`s, bumped whenever fields change
+    #[cfg(feature = "serde")]
+    pub const FORMAT_VERSION: u32 = 2;
+
+    /// A serialized `ClusteringResult` has a layout version this crate can't read
+    #[cfg(feature = "serde")]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+    #[error("Unsupported format version {found}, expected {}", FORMAT_VERSION)]
+    pub struct UnsupportedFormatVersion {
+        /// Version found in the serialized data
+        pub found: u32,
+    }
+
+    /// Serialized layout of `ClusteringResult`, the fields prefixed by their version
+    #[cfg(feature = "serde")]
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(rename = "ClusteringResult")]
+    struct ClusteringResultRepr<T> {
+        format_version: u32,
+        centers: Array2<T>,
+        memberships: Array2<f64>,
+        labels: Array1<usize>,
+        objective: f64,
+        n_iter: usize,
+        converged: bool,
+        stopped_by: StopReason,
+        trimmed: Array1<bool>,
+        center_movement_history: Vec<Array1<f64>>,
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T> From<ClusteringResult<T>> for ClusteringResultRepr<T> {
+        fn from(result: ClusteringResult<T>) -> Self {
+            ClusteringResultRepr {
+                format_version: FORMAT_VERSION,
+                centers: result.centers,
+                memberships: result.memberships,
+                labels: result.labels,
+                objective: result.objective,
+                n_iter: result.n_iter,
+                converged: result.converged,
+                stopped_by: result.stopped_by,
+                trimmed: result.trimmed,
+                center_movement_history: result.center_movement_history,
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T> std::convert::TryFrom<ClusteringResultRepr<T>> for ClusteringResult<T> {
+        type Error = UnsupportedFormatVersion;
+
+        fn try_from(repr: ClusteringResultRepr<T>) -> Result<Self, Self::Error> {
+            if repr.format_version != FORMAT_VERSION {
+                return Err(UnsupportedFormatVersion {
+                    found: repr.format_version,
+                });
+            }
+            Ok(ClusteringResult {
+                centers: repr.centers,
+                memberships: repr.memberships,
+                labels: repr.labels,
+                objective: repr.objective,
+                n_iter: repr.n_iter,
+                converged: repr.converged,
+                stopped_by: repr.stopped_by,
+                trimmed: repr.trimmed,
+                center_movement_history: repr.center_movement_history,
+            })
+        }
+    }
+
+    impl std::fmt::Display for ClusteringResult<f64> {
+        /// Summary table with one row per cluster, truncating feature columns that don't fit into
+        /// the terminal
+        ///
+        /// Besides the crisp size, every cluster shows its fuzzy size `Σ_i u_ik` and the mean
+        /// membership of the data points labeled with it.
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            const COL_WIDTH: usize = 12;
+            const FIXED_COLS: usize = 4;
+            const MIN_TERM_WIDTH: usize = (FIXED_COLS + 1) * COL_WIDTH;
+
+            let (k, n_features) = self.centers.dim();
+            let status = if self.converged {
+                String::from("converged")
+            } else {
+                format!("not converged, stopped by {:?}", self.stopped_by)
+            };
+            writeln!(
+                f,
+                "{} clusters after {} iterations ({})",
+                k, self.n_iter, status
+            )?;
+            writeln!(
+                f,
+                "objective: {:.6}, FPC: {:.4}",
+                self.objective,
+                metrics::partition_coefficient(&self.memberships)
+            )?;
+
+            let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
+            let term_width = usize::max(term_width, MIN_TERM_WIDTH);
+            let fit_cols = (term_width - FIXED_COLS * COL_WIDTH) / COL_WIDTH;
+            let shown = if fit_cols >= n_features {
+                n_features
+            } else {
+                fit_cols.saturating_sub(1)
+            };
+
+            let mut sizes = vec![0usize; k];
+            let mut member_sums = vec![0.0; k];
+            for (i, label) in self.labels.iter().enumerate() {
+                sizes[*label] += 1;
+                member_sums[*label] += self.memberships[[i, *label]];
+            }
+            let fuzzy_sizes = self.memberships.sum_axis(Axis(0));
+
+            write!(
+                f,
+                "{: >4$}{: >4$}{: >4$}{: >4$}",
+                "cluster", "size", "fuzzy_size", "mean_u", COL_WIDTH
+            )?;
+            for j in 0..shown {
+                write!(f, "{: >1$}", format!("center_{}", j), COL_WIDTH)?;
+            }
+            if shown < n_features {
+                write!(f, "{: >1$}", "...", COL_WIDTH)?;
+            }
+            writeln!(f)?;
+            for (i, center) in self.centers.outer_iter().enumerate() {
+                let mean_membership = if sizes[i] > 0 {
+                    member_sums[i] / sizes[i] as f64
+                } else {
+                    0.0
+                };
+                write!(
+                    f,
+                    "{: >4$}{: >4$}{: >4$.2}{: >4$.4}",
+                    i, sizes[i], fuzzy_sizes[i], mean_membership, COL_WIDTH
+                )?;
+                for val in center.iter().take(shown) {
+                    write!(f, "{: >1$.4}", val, COL_WIDTH)?;
+                }
+                if shown < n_features {
+                    write!(f, "{: >1$}", "...", COL_WIDTH)?;
+                }
+                writeln!(f)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<T> ClusteringResult<T> {
+        /// Write data points to a csv file with their cluster as a name instead of a number
+        ///
+        /// Clusters missing from `label_map` are called `cluster_<j>`.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`            - clustered data points (rows)
+        /// * `fname`           - target filename
+        /// * `feature_headers` - column names of `data`
+        /// * `label_map`       - name of every cluster
+        /// * `delimiter`       - delimiter to use in csv
+        ///
+        /// # Errors
+        ///
+        /// `ShapeMismatch` if `data` doesn't have one row per label or the number of column names
+        /// doesn't match its columns, other `CsvError`s if writing fails
+        ///
+        /// # Example
+        ///
+        /// ```no_run
+        /// use k_means_rs::algo::{ClusteringAlgorithm, FuzzyKMeansConfig};
+        /// use ndarray::array;
+        /// use std::collections::HashMap;
+        ///
+        /// let data = array![[1.0], [1.1], [5.0], [5.2], [9.0], [9.1]];
+        /// let result = FuzzyKMeansConfig::new(3, 20, 2.0).fit(&data).unwrap();
+        /// let names = HashMap::from([(0, "low"), (1, "medium"), (2, "high")]);
+        /// result
+        ///     .to_labeled_csv(&data, "labeled.csv", &[String::from("x")], &names, b';')
+        ///     .unwrap();
+        /// ```
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn to_labeled_csv(
+            &self,
+            data: &Array2<f64>,
+            fname: &str,
+            feature_headers: &[String],
+            label_map: &std::collections::HashMap<usize, &str>,
+            delimiter: u8,
+        ) -> Result<(), super::io::CsvError> {
+            let (n_data, n_features) = data.dim();
+            if (n_data, n_features) != (self.labels.len(), feature_headers.len()) {
+                return Err(super::io::CsvError::ShapeMismatch {
+                    expected: (self.labels.len(), feature_headers.len()),
+                    got: (n_data, n_features),
+                });
+            }
+            let cells = Array2::from_shape_fn((n_data, n_features + 1), |(i, j)| {
+                if j < n_features {
+                    data[[i, j]].to_string()
+                } else {
+                    super::io::cluster_name(self.labels[i], label_map)
+                }
+            });
+            let header: Vec<String> = feature_headers
+                .iter()
+                .cloned()
+                .chain(std::iter::once(String::from("cluster")))
+                .collect();
+            super::io::write_csv(
+                &cells,
+                std::fs::File::create(fname)?,
+                delimiter,
+                Some(&header),
+                None,
+            )
+        }
+    }
+
+    /// Parameters for fuzzy k means clustering
+    ///
+    /// With the `serde` feature, the metric is serialized by its name, so only built-in metrics
+    /// can be.
+    ///
+    /// ```
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use k_means_rs::algo::{Manhattan, InitStrategy};
+    /// use k_means_rs::FuzzyKMeansConfig;
+    /// use std::sync::Arc;
+    ///
+    /// let config = FuzzyKMeansConfig {
+    ///     metric: Arc::new(Manhattan),
+    ///     init: InitStrategy::KMeansPlusPlus,
+    ///     seed: Some(3),
+    ///     ..FuzzyKMeansConfig::new(4, 50, 1.5)
+    /// };
+    /// let json = serde_json::to_string(&config).unwrap();
+    /// assert!(json.contains("\"metric\":\"manhattan\""));
+    /// let read: FuzzyKMeansConfig = serde_json::from_str(&json).unwrap();
+    /// assert_eq!((read.k, read.q, read.seed), (4, 1.5, Some(3)));
+    /// assert_eq!(read.metric.name(), Some("manhattan"));
+    /// assert_eq!(read.init, InitStrategy::KMeansPlusPlus);
+    /// # }
+    /// ```
+    #[derive(Clone, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct FuzzyKMeansConfig {
+        /// Number of clusters
+        pub k: usize,
+        /// Upper bound of iteration number
+        pub max_iter: usize,
+        /// Fuzzifier
+        pub q: f64,
+        /// Stop once no center moves further than this, or only by rounding error
+        pub tol: f64,
+        /// Stop once this much wall-clock time has passed, checked before every chunk of rows. An
+        /// iteration that runs out of time is abandoned, but the first one always completes.
+        pub max_duration: Option<Duration>,
+        /// Seed for the center initialisation, drawn from entropy if `None`
+        pub seed: Option<u64>,
+        /// Fraction of data points farthest from their nearest center to leave out of every center
+        /// update, in `[0, 1)`. Other values make the fit fail with `InvalidTrimFraction`.
+        pub trim_fraction: f64,
+        /// Distance between data points and centers. Memberships are computed from squared
+        /// distances, centers are always updated as weighted means.
+        #[cfg_attr(feature = "serde", serde(with = "metric_by_name"))]
+        pub metric: Arc<dyn DistanceMetric>,
+        /// How to choose the initial centers
+        pub init: InitStrategy,
+        /// Shuffle the rows before choosing initial centers from them, with the same random
+        /// number generator. The fit itself and the order of the results are unaffected.
+        pub shuffle_before_init: bool,
+        /// Record how far every center moved in each iteration in
+        /// `ClusteringResult::center_movement_history`
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub track_convergence: bool,
+    }
+
+    impl FuzzyKMeansConfig {
+        /// Create a config without tolerance or time budget
+        ///
+        /// # Arguments
+        ///
+        /// * `k`        - number of clusters
+        /// * `max_iter` - upper bound of iteration number
+        /// * `q`        - fuzzifier
+        pub fn new(k: usize, max_iter: usize, q: f64) -> Self {
+            FuzzyKMeansConfig {
+                k,
+                max_iter,
+                q,
+                tol: 0.0,
+                max_duration: None,
+                seed: None,
+                trim_fraction: 0.0,
+                metric: Arc::new(Euclidean),
+                init: InitStrategy::default(),
+                shuffle_before_init: false,
+                track_convergence: false,
+            }
+        }
+    }
+
+    /// Parameters for hard (crisp) k means clustering
+    #[derive(Clone, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct HardKMeansConfig {
+        /// Number of clusters
+        pub k: usize,
+        /// Upper bound of iteration number
+        pub max_iter: usize,
+        /// Stop once no center moves further than this, or only by rounding error
+        pub tol: f64,
+        /// Stop once this much wall-clock time has passed, checked before every chunk of rows. An
+        /// iteration that runs out of time is abandoned, but the first one always completes.
+        pub max_duration: Option<Duration>,
+        /// Seed for the center initialisation, drawn from entropy if `None`
+        pub seed: Option<u64>,
+        /// How to choose the initial centers
+        pub init: InitStrategy,
+        /// Record how far every center moved in each iteration in
+        /// `ClusteringResult::center_movement_history`
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub track_convergence: bool,
+    }
+
+    impl HardKMeansConfig {
+        /// Create a config without tolerance or time budget
+        ///
+        /// # Arguments
+        ///
+        /// * `k`        - number of clusters
+        /// * `max_iter` - upper bound of iteration number
+        pub fn new(k: usize, max_iter: usize) -> Self {
+            HardKMeansConfig {
+                k,
+                max_iter,
+                tol: 0.0,
+                max_duration: None,
+                seed: None,
+                init: InitStrategy::default(),
+                track_convergence: false,
+            }
+        }
+    }
+
+    /// How to choose the initial cluster centers
+    #[derive(Clone, Debug, Default, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum InitStrategy {
+        /// Uniformly random within the range of every feature in the data
+        #[default]
+        RandomBounds,
+        /// k means++: the first center is a random data point, every further one a data point
+        /// drawn with probability proportional to its squared distance to the nearest center
+        KMeansPlusPlus,
+        /// Distinct data points drawn uniformly at random
+        RandomDataPoints,
+        /// Fixed centers (rows), must have `k` rows and one column per feature
+        Provided(Array2<f64>),
+        /// Fixed centers like `Provided`, of which those marked in `frozen` (one entry per
+        /// center) are never updated, e.g. because their position is known. Memberships are still
+        /// computed against all centers.
+        ///
+        /// ```
+        /// use k_means_rs::algo::{ClusteringAlgorithm, FuzzyKMeansConfig, InitStrategy};
+        /// use ndarray::{array, Array2};
+        ///
+        /// // three blobs around (0, 0), (5, 0) and (0, 5)
+        /// let means = [[0.0, 0.0], [5.0, 0.0], [0.0, 5.0]];
+        /// let data = Array2::from_shape_fn((90, 2), |(i, j)| {
+        ///     means[i / 30][j] + ((i * 7 + j * 3) % 5) as f64 * 0.1 - 0.2
+        /// });
+        /// let centers = array![[0.0, 0.0], [5.0, 0.0], [2.0, 2.0]];
+        /// let config = FuzzyKMeansConfig {
+        ///     init: InitStrategy::ProvidedWithMask {
+        ///         centers: centers.clone(),
+        ///         frozen: vec![true, true, false],
+        ///     },
+        ///     tol: 1e-9,
+        ///     ..FuzzyKMeansConfig::new(3, 100, 2.0)
+        /// };
+        /// let mut objectives = Vec::new();
+        /// let result = config
+        ///     .fit_with_callback(&data, &mut |info| {
+        ///         objectives.push(info.objective);
+        ///         true
+        ///     })
+        ///     .unwrap();
+        /// assert!(objectives.windows(2).all(|pair| pair[1] <= pair[0] + 1e-9));
+        /// assert_eq!(result.centers.row(0), centers.row(0));
+        /// assert_eq!(result.centers.row(1), centers.row(1));
+        /// assert!((result.centers[[2, 0]] - 0.0).abs() < 0.1);
+        /// assert!((result.centers[[2, 1]] - 5.0).abs() < 0.1);
+        /// ```
+        ProvidedWithMask {
+            /// Initial centers (rows)
+            centers: Array2<f64>,
+            /// Whether each center stays where it is
+            frozen: Vec<bool>,
+        },
+        /// Deterministic: center `j` of `k` is the `(j + 1) / (k + 1)` quantile of every feature,
+        /// interpolated linearly between data points
+        ///
+        /// ```
+        /// use k_means_rs::algo::{ClusteringAlgorithm, FuzzyKMeansConfig, InitStrategy};
+        /// use ndarray::Array2;
+        ///
+        /// let data = Array2::from_shape_fn((101, 1), |(i, _)| i as f64 / 100.0);
+        /// let config = FuzzyKMeansConfig {
+        ///     init: InitStrategy::Quantile,
+        ///     ..FuzzyKMeansConfig::new(3, 0, 2.0)
+        /// };
+        /// let centers = config.fit(&data).unwrap().centers;
+        /// for (center, expected) in centers.iter().zip([0.25, 0.5, 0.75]) {
+        ///     assert!((center - expected).abs() < 1e-12);
+        /// }
+        /// ```
+        Quantile,
+    }
+
+    /// Choose initial centers according to `init`
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - initialisation strategy
+    /// * `x`    - data points (rows)
+    /// * `k`    - number of clusters
+    /// * `rng`  - random number generator
+    ///
+    /// # Panics
+    ///
+    /// Panics if provided centers have the wrong shape or more distinct data points than exist
+    /// are requested
+    fn initial_centers<R: Rng>(
+        init: &InitStrategy,
+        x: &Array2<f64>,
+        k: usize,
+        rng: &mut R,
+    ) -> Array2<f64> {
+        let (n_data, n_features) = x.dim();
+        match init {
+            InitStrategy::RandomBounds => {
+                let min = x.fold_axis(Axis(0), f64::INFINITY, |a, b| a.min(*b));
+                let max = x.fold_axis(Axis(0), f64::NEG_INFINITY, |a, b| a.max(*b));
+                let mut centers = Array2::<f64>::zeros((k, n_features));
+                for mut center in centers.outer_iter_mut() {
+                    for ((val, lo), hi) in center.iter_mut().zip(min.iter()).zip(max.iter()) {
+                        *val = lo + rng.gen::<f64>() * (hi - lo);
+                    }
+                }
+                centers
+            }
+            InitStrategy::KMeansPlusPlus => {
+                assert!(n_data > 0, "Can't pick initial centers from empty data!");
+                let mut centers = Array2::<f64>::zeros((k, n_features));
+                centers.row_mut(0).assign(&x.row(rng.gen_range(0..n_data)));
+                let mut min_dists = Array1::<f64>::from_elem(n_data, f64::INFINITY);
+                for c in 1..k {
+                    let prev = centers.slice(s![c - 1..c, ..]).to_owned();
+                    for (min_dist, dist) in min_dists
+                        .iter_mut()
+                        .zip(dist_sq_matrix(x.view(), &prev).iter())
+                    {
+                        *min_dist = min_dist.min(*dist);
+                    }
+                    let total = min_dists.sum();
+                    let chosen = if total > 0.0 {
+                        let mut target = rng.gen::<f64>() * total;
+                        let mut chosen = n_data - 1;
+                        for (i, dist) in min_dists.iter().enumerate() {
+                            if target < *dist {
+                                chosen = i;
+                                break;
+                            }
+                            target -= dist;
+                        }
+                        chosen
+                    } else {
+                        // all data points coincide with a center already
+                        rng.gen_range(0..n_data)
+                    };
+                    centers.row_mut(c).assign(&x.row(chosen));
+                }
+                centers
+            }
+            InitStrategy::RandomDataPoints => {
+                assert!(
+                    k <= n_data,
+                    "Can't pick {} distinct data points out of {}!",
+                    k,
+                    n_data
+                );
+                let chosen = rand::seq::index::sample(rng, n_data, k).into_vec();
+                x.select(Axis(0), &chosen)
+            }
+            InitStrategy::Quantile => {
+                let mut centers = Array2::<f64>::zeros((k, n_features));
+                for (column, mut center_column) in
+                    x.columns().into_iter().zip(centers.columns_mut())
+                {
+                    let mut sorted = column.to_vec();
+                    sorted.sort_by(f64::total_cmp);
+                    for (j, center) in center_column.iter_mut().enumerate() {
+                        let pos = (j + 1) as f64 / (k + 1) as f64 * (n_data - 1) as f64;
+                        let (lo, frac) = (pos.floor() as usize, pos.fract());
+                        let hi = (lo + 1).min(n_data - 1);
+                        *center = sorted[lo] + frac * (sorted[hi] - sorted[lo]);
+                    }
+                }
+                centers
+            }
+            InitStrategy::Provided(centers) | InitStrategy::ProvidedWithMask { centers, .. } => {
+                assert_eq!(
+                    centers.dim(),
+                    (k, n_features),
+                    "Provided centers must have shape ({}, {})!",
+                    k,
+                    n_features
+                );
+                centers.clone()
+            }
+        }
+    }
+
+    /// A clustering algorithm that can be fit to data
+    ///
+    /// Allows switching between algorithms at runtime:
+    ///
+    /// ```
+    /// use k_means_rs::algo::{ClusteringAlgorithm, FuzzyKMeansConfig, HardKMeansConfig};
+    /// use ndarray::array;
+    ///
+    /// let fuzzy = true;
+    /// let algo: Box<dyn ClusteringAlgorithm<f64>> = if fuzzy {
+    ///     Box::new(FuzzyKMeansConfig::new(2, 10, 2.0))
+    /// } else {
+    ///     Box::new(HardKMeansConfig::new(2, 10))
+    /// };
+    /// let result = algo
+    ///     .fit(&array![[0.0, 0.0], [0.1, 0.0], [1.0, 1.0], [0.9, 1.0]])
+    ///     .unwrap();
+    /// assert_eq!(result.centers.dim(), (2, 2));
+    /// ```
+    pub trait ClusteringAlgorithm<T> {
+        /// Cluster data, calling `callback` after every iteration
+        ///
+        /// The fit stops with `StopReason::Callback` as soon as `callback` returns `false`.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`     - data to cluster (rows are data points)
+        /// * `callback` - receives progress information, returns whether to keep iterating
+        ///
+        /// # Errors
+        ///
+        /// `EmptyData` without data points, `TooManyClusters` for more clusters than data points,
+        /// `InvalidFuzzifier` for a fuzzifier `<= 1`, `DimensionMismatch` for provided initial
+        /// centers of the wrong shape and `NumericalFailure` if the objective isn't finite, e.g.
+        /// because the data contains NaN.
+        fn fit_with_callback(
+            &self,
+            data: &Array2<T>,
+            callback: &mut dyn FnMut(&IterationInfo) -> bool,
+        ) -> Result<ClusteringResult<T>, AlgoError>;
+
+        /// Cluster data
+        ///
+        /// # Arguments
+        ///
+        /// * `data` - data to cluster (rows are data points)
+        fn fit(&self, data: &Array2<T>) -> Result<ClusteringResult<T>, AlgoError> {
+            self.fit_with_callback(data, &mut |_| true)
+        }
+    }
+
+    impl<T> ClusteringAlgorithm<T> for FuzzyKMeansConfig
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        fn fit_with_callback(
+            &self,
+            data: &Array2<T>,
+            callback: &mut dyn FnMut(&IterationInfo) -> bool,
+        ) -> Result<ClusteringResult<T>, AlgoError> {
+            check_fuzzifier(self.q)?;
+            if !(0.0..1.0).contains(&self.trim_fraction) {
+                return Err(AlgoError::InvalidTrimFraction(self.trim_fraction));
+            }
+
+            let q = self.q;
+            let criteria = StopCriteria {
+                max_iter: self.max_iter,
+                tol: self.tol,
+                max_duration: self.max_duration,
+                track_convergence: self.track_convergence,
+            };
+            if self.trim_fraction == 0.0 {
+                return fit_centers(
+                    data,
+                    self.k,
+                    &self.init,
+                    self.shuffle_before_init,
+                    &mut seeded_rng(self.seed),
+                    &criteria,
+                    callback,
+                    |x, centers, memberships, deadline| {
+                        membership_step(
+                            x,
+                            None,
+                            centers,
+                            self.metric.as_ref(),
+                            memberships,
+                            deadline,
+                            |dists| fuzzy_weights(q, dists),
+                        )
+                    },
+                );
+            }
+
+            let mut trimmed = Array1::<bool>::from_elem(data.nrows(), false);
+            let mut result = fit_centers(
+                data,
+                self.k,
+                &self.init,
+                self.shuffle_before_init,
+                &mut seeded_rng(self.seed),
+                &criteria,
+                callback,
+                |x, centers, memberships, deadline| {
+                    trimmed_step(
+                        self.trim_fraction,
+                        x,
+                        centers,
+                        self.metric.as_ref(),
+                        memberships,
+                        &mut trimmed,
+                        deadline,
+                        |dists| fuzzy_weights(q, dists),
+                    )
+                },
+            )?;
+            result.trimmed = trimmed;
+            Ok(result)
+        }
+    }
+
+    impl<T> ClusteringAlgorithm<T> for HardKMeansConfig
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        fn fit_with_callback(
+            &self,
+            data: &Array2<T>,
+            callback: &mut dyn FnMut(&IterationInfo) -> bool,
+        ) -> Result<ClusteringResult<T>, AlgoError> {
+            let criteria = StopCriteria {
+                max_iter: self.max_iter,
+                tol: self.tol,
+                max_duration: self.max_duration,
+                track_convergence: self.track_convergence,
+            };
+            fit_centers(
+                data,
+                self.k,
+                &self.init,
+                false,
+                &mut seeded_rng(self.seed),
+                &criteria,
+                callback,
+                |x, centers, memberships, deadline| {
+                    membership_step(
+                        x,
+                        None,
+                        centers,
+                        &Euclidean,
+                        memberships,
+                        deadline,
+                        hard_weights,
+                    )
+                },
+            )
+        }
+    }
+
+    /// Fit fuzzy k means `n_init` times from different initialisations
+    ///
+    /// Restart `i` uses the seed `derive_seed(master, i)`, where `master` is the seed of `config`
+    /// or drawn from entropy, so results don't depend on how restarts are scheduled. Restarts run
+    /// concurrently when the `parallel` feature is enabled. Results are returned in restart order.
+    /// Fails with the first error of any restart.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parameters shared by all restarts
+    /// * `data`   - data to cluster (rows are data points)
+    /// * `n_init` - number of restarts
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{fit_restarts, FuzzyKMeansConfig};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.1], [0.2, 0.0], [0.9, 1.0], [1.0, 0.8]];
+    /// let mut config = FuzzyKMeansConfig::new(2, 20, 2.0);
+    /// config.seed = Some(42);
+    /// let results = fit_restarts(&config, &data, 3).unwrap();
+    /// let best = results
+    ///     .iter()
+    ///     .min_by(|a, b| a.objective.total_cmp(&b.objective))
+    ///     .unwrap();
+    /// assert!(results.iter().all(|result| best.objective <= result.objective));
+    /// ```
+    pub fn fit_restarts<T>(
+        config: &FuzzyKMeansConfig,
+        data: &Array2<T>,
+        n_init: usize,
+    ) -> Result<Vec<ClusteringResult<T>>, AlgoError>
+    where
+        T: Clone + Copy + Send + Sync + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        fit_restarts_with_callback(config, data, n_init, &|_, _| true)
+    }
+
+    /// Fit fuzzy k means `n_init` times like `fit_restarts`, calling `callback` after every
+    /// iteration of every restart
+    ///
+    /// `callback` receives the index of the restart and its progress and returns whether that
+    /// restart should keep iterating. With the `parallel` feature it is called from several
+    /// threads at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `config`   - parameters shared by all restarts
+    /// * `data`     - data to cluster (rows are data points)
+    /// * `n_init`   - number of restarts
+    /// * `callback` - receives the restart index and progress information
+    pub fn fit_restarts_with_callback<T>(
+        config: &FuzzyKMeansConfig,
+        data: &Array2<T>,
+        n_init: usize,
+        callback: &(dyn Fn(usize, &IterationInfo) -> bool + Sync),
+    ) -> Result<Vec<ClusteringResult<T>>, AlgoError>
+    where
+        T: Clone + Copy + Send + Sync + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        let master = config.seed.unwrap_or_else(rand::random);
+        let restart = |i: usize| {
+            let mut restart_config = config.clone();
+            restart_config.seed = Some(derive_seed(master, i as u64));
+            ClusteringAlgorithm::<T>::fit_with_callback(&restart_config, data, &mut |info| {
+                callback(i, info)
+            })
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..n_init).into_par_iter().map(restart).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            (0..n_init).map(restart).collect()
+        }
+    }
+
+    /// Fit fuzzy k means for every number of clusters in `ks`
+    ///
+    /// Every `k` is fitted `n_init` times with `fit_restarts` (at least once) and the fit with
+    /// the lowest objective is kept. All other parameters, including the seed, are taken from
+    /// `config`. Results are returned in the order of `ks`, so they can be compared with the
+    /// validity indices in `metrics`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - parameters shared by all fits, its `k` is ignored
+    /// * `data`   - data to cluster (rows are data points)
+    /// * `ks`     - numbers of clusters to try
+    /// * `n_init` - number of restarts per number of clusters
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{scan_k, FuzzyKMeansConfig};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0], [0.1], [5.0], [5.1], [9.0], [9.1]];
+    /// let config = FuzzyKMeansConfig {
+    ///     seed: Some(1),
+    ///     ..FuzzyKMeansConfig::new(0, 50, 2.0)
+    /// };
+    /// let results = scan_k(&config, &data, &[2, 3], 2).unwrap();
+    /// assert_eq!(results[0].centers.nrows(), 2);
+    /// assert!(results[1].objective < results[0].objective);
+    /// ```
+    pub fn scan_k<T>(
+        config: &FuzzyKMeansConfig,
+        data: &Array2<T>,
+        ks: &[usize],
+        n_init: usize,
+    ) -> Result<Vec<ClusteringResult<T>>, AlgoError>
+    where
+        T: Clone + Copy + Send + Sync + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        ks.iter()
+            .map(|&k| {
+                let k_config = FuzzyKMeansConfig {
+                    k,
+                    ..config.clone()
+                };
+                let results = fit_restarts(&k_config, data, n_init.max(1))?;
+                Ok(results
+                    .into_iter()
+                    .min_by(|a, b| a.objective.total_cmp(&b.objective))
+                    .unwrap())
+            })
+            .collect()
+    }
+
+    /// Cluster validity index to choose the number of clusters by, see `select_k_by_validity`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ValidityMeasure {
+        /// Fuzzy partition coefficient, see `metrics::partition_coefficient`
+        Fpc,
+        /// See `metrics::xie_beni_index`
+        XieBeni,
+        /// See `metrics::davies_bouldin_index`
+        DaviesBouldin,
+        /// See `metrics::silhouette_score`
+        Silhouette,
+        /// See `metrics::calinski_harabasz_index`
+        CalinskiHarabasz,
+    }
+
+    impl ValidityMeasure {
+        /// All measures, in the order they are documented
+        pub const ALL: [ValidityMeasure; 5] = [
+            ValidityMeasure::Fpc,
+            ValidityMeasure::XieBeni,
+            ValidityMeasure::DaviesBouldin,
+            ValidityMeasure::Silhouette,
+            ValidityMeasure::CalinskiHarabasz,
+        ];
+
+        /// Name of the measure, e.g. `"xie-beni"`
+        pub fn name(self) -> &'static str {
+            match self {
+                ValidityMeasure::Fpc => "fpc",
+                ValidityMeasure::XieBeni => "xie-beni",
+                ValidityMeasure::DaviesBouldin => "davies-bouldin",
+                ValidityMeasure::Silhouette => "silhouette",
+                ValidityMeasure::CalinskiHarabasz => "calinski-harabasz",
+            }
+        }
+
+        /// Measure of the given name, see `name`
+        pub fn from_name(name: &str) -> Option<ValidityMeasure> {
+            ValidityMeasure::ALL
+                .iter()
+                .copied()
+                .find(|measure| measure.name() == name)
+        }
+
+        /// Whether higher values mean a better clustering
+        pub fn higher_is_better(self) -> bool {
+            match self {
+                ValidityMeasure::Fpc
+                | ValidityMeasure::Silhouette
+                | ValidityMeasure::CalinskiHarabasz => true,
+                ValidityMeasure::XieBeni | ValidityMeasure::DaviesBouldin => false,
+            }
+        }
+
+        /// Value of the measure for a fuzzy clustering
+        ///
+        /// The measures of hard clusterings use the labels of the result.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`   - data the result was fit on
+        /// * `result` - result of the fit
+        /// * `q`      - fuzzifier of the fit
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` if a label of the result isn't one of its clusters
+        pub fn score<T>(
+            self,
+            data: &Array2<f64>,
+            result: &ClusteringResult<T>,
+            q: f64,
+        ) -> Result<f64, AlgoError>
+        where
+            T: Copy,
+            f64: From<T>,
+        {
+            let centers = result.centers.mapv(f64::from);
+            Ok(match self {
+                ValidityMeasure::Fpc => metrics::partition_coefficient(&result.memberships),
+                ValidityMeasure::XieBeni => {
+                    metrics::xie_beni_index(data, &centers, &result.memberships, q)
+                }
+                ValidityMeasure::DaviesBouldin => {
+                    metrics::davies_bouldin_index(data, &centers, &result.labels)?
+                }
+                ValidityMeasure::Silhouette => {
+                    metrics::silhouette_score(data, &result.labels, centers.nrows())?
+                }
+                ValidityMeasure::CalinskiHarabasz => {
+                    metrics::calinski_harabasz_index(data, &centers, &result.labels)?
+                }
+            })
+        }
+
+        /// Index of the best of `scores`, ignoring NaN scores
+        ///
+        /// Ties go to the earlier score. Returns `None` if all scores are NaN.
+        ///
+        /// # Arguments
+        ///
+        /// * `scores` - value of the measure for every candidate
+        pub fn best(self, scores: &[f64]) -> Option<usize> {
+            let key = |i: &usize| {
+                if self.higher_is_better() {
+                    -scores[*i]
+                } else {
+                    scores[*i]
+                }
+            };
+            (0..scores.len())
+                .filter(|&i| !scores[i].is_nan())
+                .min_by(|a, b| key(a).total_cmp(&key(b)))
+        }
+    }
+
+    /// Fit fuzzy k means for every number of clusters in `k_range` and keep the best by `validity`
+    ///
+    /// Every `k` is fitted once with the same seed, see `scan_k`. Measures that are undefined
+    /// for a `k`, like the Davies–Bouldin index for a single cluster, don't count; if none is defined
+    /// the smallest `k` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`     - data to cluster (rows are data points)
+    /// * `k_range`  - numbers of clusters to try
+    /// * `validity` - measure to choose by
+    /// * `n_iter`   - upper bound of iteration number
+    /// * `q`        - fuzzifier
+    /// * `seed`     - seed for the center initialisation of every fit
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{select_k_by_validity, ValidityMeasure};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0], [0.1], [0.2], [5.0], [5.1], [5.2], [9.0], [9.1], [9.2]];
+    /// for validity in [ValidityMeasure::XieBeni, ValidityMeasure::CalinskiHarabasz] {
+    ///     let (k, result) = select_k_by_validity(&data, 2..6, validity, 50, 2.0, 1).unwrap();
+    ///     assert_eq!(k, 3);
+    ///     assert_eq!(result.centers.nrows(), 3);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `AlgoError::EmptyClusterRange` if `k_range` is empty
+    pub fn select_k_by_validity<T>(
+        data: &Array2<T>,
+        k_range: std::ops::Range<usize>,
+        validity: ValidityMeasure,
+        n_iter: usize,
+        q: f64,
+        seed: u64,
+    ) -> Result<(usize, ClusteringResult<T>), AlgoError>
+    where
+        T: Clone + Copy + Send + Sync + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        if k_range.is_empty() {
+            return Err(AlgoError::EmptyClusterRange);
+        }
+        let config = FuzzyKMeansConfig {
+            seed: Some(seed),
+            ..FuzzyKMeansConfig::new(0, n_iter, q)
+        };
+        let ks: Vec<usize> = k_range.collect();
+        let mut results = scan_k(&config, data, &ks, 1)?;
+        let x = data.mapv(f64::from);
+        let scores = results
+            .iter()
+            .map(|result| validity.score(&x, result, q))
+            .collect::<Result<Vec<f64>, _>>()?;
+        let best = validity.best(&scores).unwrap_or(0);
+        Ok((ks[best], results.swap_remove(best)))
+    }
+
+    /// What to do with a cluster according to `per_cluster_stability`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum StabilityRecommendation {
+        /// The cluster is recovered reliably
+        Keep,
+        /// The cluster is unstable and shares many data points with another cluster
+        Merge,
+        /// The cluster is unstable but distinct from the others, it likely covers several groups
+        Split,
+    }
+
+    /// Stability of every cluster of a fit, see `per_cluster_stability`
+    #[derive(Clone, Debug)]
+    pub struct ClusterStabilityReport {
+        /// Mean soft Jaccard similarity of every cluster to its counterpart in the bootstrap
+        /// fits, in `[0, 1]`
+        pub scores: Array1<f64>,
+        /// Recommendation per cluster
+        pub recommendations: Vec<StabilityRecommendation>,
+    }
+
+    /// Clusters with at least this mean Jaccard similarity across bootstrap fits are stable
+    const STABLE_JACCARD: f64 = 0.75;
+
+    /// Unstable clusters overlapping another cluster by at least this much should be merged
+    const MERGE_OVERLAP: f64 = 0.25;
+
+    /// Measure how reliably every cluster is recovered when the data is resampled
+    ///
+    /// A reference fit on all data is compared with fits on `n_bootstrap` bootstrap samples
+    /// (drawn with replacement). The centers of every bootstrap fit give memberships for all data
+    /// points, whose clusters are matched greedily to the reference clusters by their soft
+    /// Jaccard similarity `Σ_i min(u_ij, v_il) / Σ_i max(u_ij, v_il)`. The score of a cluster is
+    /// its mean similarity over all bootstrap fits.
+    ///
+    /// Clusters scoring at least 0.75 are kept. Unstable clusters are recommended for merging if
+    /// their soft overlap with another reference cluster (see `metrics::cluster_overlap_matrix`)
+    /// is at least 0.25 and for splitting otherwise. These thresholds are rules of thumb.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`        - data to cluster (rows are data points)
+    /// * `k`           - number of clusters
+    /// * `n_bootstrap` - number of bootstrap fits
+    /// * `n_iter`      - upper bound of iteration number of every fit
+    /// * `q`           - fuzzifier
+    /// * `seed`        - seed for the reference fit and the resampling, random if `None`
+    ///
+    /// # Errors
+    ///
+    /// `NoBootstrapFits` if `n_bootstrap` is 0, otherwise whatever the fits return
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{per_cluster_stability, StabilityRecommendation};
+    /// use ndarray::array;
+    ///
+    /// let data = array![
+    ///     [0.0, 0.0], [0.1, 0.0], [0.0, 0.1], [0.1, 0.1],
+    ///     [5.0, 5.0], [5.1, 5.0], [5.0, 5.1], [5.1, 5.1],
+    ///     [0.0, 5.0], [0.1, 5.0], [0.0, 5.1], [0.1, 5.1],
+    /// ];
+    /// let report = per_cluster_stability(&data, 3, 10, 50, 2.0, Some(7)).unwrap();
+    /// assert!(report.scores.iter().all(|&score| score > 0.9));
+    /// assert!(report.recommendations.iter().all(|&r| r == StabilityRecommendation::Keep));
+    /// ```
+    pub fn per_cluster_stability<T>(
+        data: &Array2<T>,
+        k: usize,
+        n_bootstrap: usize,
+        n_iter: usize,
+        q: f64,
+        seed: Option<u64>,
+    ) -> Result<ClusterStabilityReport, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        if n_bootstrap == 0 {
+            return Err(AlgoError::NoBootstrapFits);
+        }
+        let x = data.mapv(f64::from);
+        let n_data = x.nrows();
+        let master = seed.unwrap_or_else(rand::random);
+        let config = FuzzyKMeansConfig {
+            seed: Some(master),
+            ..FuzzyKMeansConfig::new(k, n_iter, q)
+        };
+        let reference = ClusteringAlgorithm::<f64>::fit(&config, &x)?;
+
+        let mut score_sums = Array1::<f64>::zeros(k);
+        for b in 0..n_bootstrap {
+            let seed = derive_seed(master, b as u64);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let sample: Vec<usize> = (0..n_data).map(|_| rng.gen_range(0..n_data)).collect();
+            let boot_config = FuzzyKMeansConfig {
+                seed: Some(seed),
+                ..config.clone()
+            };
+            let boot = ClusteringAlgorithm::<f64>::fit(&boot_config, &x.select(Axis(0), &sample))?;
+            let mut memberships = Array2::<f64>::zeros((n_data, k));
+            compute_memberships::<f64>(q, &x, &boot.centers, &mut memberships)?;
+
+            let mut similarity = Array2::<f64>::zeros((k, k));
+            for ((j, l), sim) in similarity.indexed_iter_mut() {
+                let (mut min_sum, mut max_sum) = (0.0, 0.0);
+                for (u, v) in reference
+                    .memberships
+                    .column(j)
+                    .iter()
+                    .zip(memberships.column(l))
+                {
+                    min_sum += u.min(*v);
+                    max_sum += u.max(*v);
+                }
+                *sim = if max_sum > 0.0 {
+                    min_sum / max_sum
+                } else {
+                    0.0
+                };
+            }
+            // match the most similar pairs first, every cluster is used once
+            let mut pairs: Vec<(usize, usize)> =
+                (0..k).flat_map(|j| (0..k).map(move |l| (j, l))).collect();
+            pairs.sort_by(|a, b| similarity[*b].total_cmp(&similarity[*a]));
+            let mut ref_done = vec![false; k];
+            let mut boot_done = vec![false; k];
+            for (j, l) in pairs {
+                if !ref_done[j] && !boot_done[l] {
+                    ref_done[j] = true;
+                    boot_done[l] = true;
+                    score_sums[j] += similarity[[j, l]];
+                }
+            }
+        }
+        let scores = score_sums / n_bootstrap.max(1) as f64;
+
+        let overlap = metrics::cluster_overlap_matrix(&reference.memberships);
+        let recommendations = scores
+            .iter()
+            .enumerate()
+            .map(|(j, &score)| {
+                let max_overlap = (0..k)
+                    .filter(|&l| l != j)
+                    .map(|l| overlap[[j, l]])
+                    .fold(0.0, f64::max);
+                if score >= STABLE_JACCARD {
+                    StabilityRecommendation::Keep
+                } else if max_overlap >= MERGE_OVERLAP {
+                    StabilityRecommendation::Merge
+                } else {
+                    StabilityRecommendation::Split
+                }
+            })
+            .collect();
+        Ok(ClusterStabilityReport {
+            scores,
+            recommendations,
+        })
+    }
+
+    // CALIBRATION_NS, written by build.rs
+    include!(concat!(env!("OUT_DIR"), "/calibration.rs"));
+
+    /// Cost of turning a distance into a membership, in units of one feature's share of the
+    /// distance
+    const MEMBERSHIP_COST: usize = 40;
+
+    /// Rough wall time of a fuzzy k means fit
+    ///
+    /// Every iteration computes `n * k` distances over `d` features plus a membership for each,
+    /// and one extra pass computes the final memberships. The time per unit of work is
+    /// `CALIBRATION_NS`, fixed at build time. The estimate assumes all `n_iter` iterations run.
+    ///
+    /// # Arguments
+    ///
+    /// * `n`      - number of data points
+    /// * `d`      - number of features
+    /// * `k`      - number of clusters
+    /// * `n_iter` - upper bound of iteration number
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::estimate_runtime;
+    /// use std::time::Duration;
+    ///
+    /// assert!(estimate_runtime(1_000_000, 100, 50, 300) > Duration::from_secs(60));
+    /// assert!(estimate_runtime(100, 2, 3, 10) < estimate_runtime(1000, 2, 3, 10));
+    /// ```
+    pub fn estimate_runtime(n: usize, d: usize, k: usize, n_iter: usize) -> Duration {
+        let work = n as f64 * k as f64 * (d + MEMBERSHIP_COST) as f64 * (n_iter + 1) as f64;
+        Duration::try_from_secs_f64(work * CALIBRATION_NS * 1e-9).unwrap_or(Duration::MAX)
+    }
+
+    /// Quantiles of the euclidean distance between random pairs of data points
+    ///
+    /// `sample_size` pairs of distinct data points are drawn, so the cost doesn't grow with the
+    /// square of the number of data points. Quantiles are interpolated linearly between the
+    /// sorted distances. All quantiles are NaN for fewer than two data points or no pairs.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`        - data points (rows)
+    /// * `quantiles`   - quantiles to return, each in `[0, 1]`
+    /// * `sample_size` - number of pairs to draw
+    /// * `seed`        - seed for drawing the pairs
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::distance_quantiles;
+    /// use ndarray::Array2;
+    ///
+    /// let data = Array2::from_shape_fn((100, 3), |(i, j)| ((i * 13 + j * 7) % 17) as f64);
+    /// let quantiles = [0.0, 0.1, 0.25, 0.5, 0.75, 0.9, 1.0];
+    /// let dists = distance_quantiles(&data, &quantiles, 2000, 5);
+    /// assert_eq!(dists.len(), quantiles.len());
+    /// assert!(dists.windows(2).into_iter().all(|pair| pair[0] <= pair[1]));
+    /// assert!(dists[0] >= 0.0);
+    /// ```
+    pub fn distance_quantiles(
+        data: &Array2<f64>,
+        quantiles: &[f64],
+        sample_size: usize,
+        seed: u64,
+    ) -> Array1<f64> {
+        let n = data.nrows();
+        if n < 2 || sample_size == 0 {
+            return Array1::from_elem(quantiles.len(), f64::NAN);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut dists: Vec<f64> = (0..sample_size)
+            .map(|_| {
+                let a = rng.gen_range(0..n);
+                // any other data point, uniformly
+                let b = (a + rng.gen_range(1..n)) % n;
+                Euclidean.distance(data.row(a), data.row(b))
+            })
+            .collect();
+        dists.sort_by(f64::total_cmp);
+        quantiles
+            .iter()
+            .map(|quantile| {
+                let pos = quantile.clamp(0.0, 1.0) * (dists.len() - 1) as f64;
+                let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+                dists[lo] + (dists[hi] - dists[lo]) * (pos - lo as f64)
+            })
+            .collect()
+    }
+
+    /// Heuristic fuzzifier for the data, from how spread out the distances between data points are
+    ///
+    /// When the 75th and 25th percentile of the pairwise distances are close, e.g. in many
+    /// dimensions, fuzzy memberships drift towards `1/k` for the usual `q = 2` and a smaller
+    /// fuzzifier keeps the clusters apart. The suggestion is `1 + ln(d75 / d25)`, limited to
+    /// `[1.1, 2]`, so clearly structured data gets the usual 2. Distances are sampled with a
+    /// fixed seed, so the same data always gets the same suggestion.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data points (rows)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::suggest_fuzzifier;
+    /// use ndarray::Array2;
+    ///
+    /// // two tight, distant groups: distances are either tiny or large
+    /// let groups = Array2::from_shape_fn((100, 2), |(i, j)| {
+    ///     (i % 2) as f64 * 100.0 + ((i * 7 + j) % 5) as f64 * 0.1
+    /// });
+    /// assert_eq!(suggest_fuzzifier(&groups), 2.0);
+    /// // evenly spread points in many dimensions: distances are all alike
+    /// let spread = Array2::from_shape_fn((100, 50), |(i, j)| ((i * 31 + j * 17) % 23) as f64);
+    /// let q = suggest_fuzzifier(&spread);
+    /// assert!(q > 1.0 && q < 2.0);
+    /// ```
+    pub fn suggest_fuzzifier(data: &Array2<f64>) -> f64 {
+        const SAMPLE_SIZE: usize = 10_000;
+        const MIN_Q: f64 = 1.1;
+        const MAX_Q: f64 = 2.0;
+
+        let dists = distance_quantiles(data, &[0.25, 0.75], SAMPLE_SIZE, 0);
+        let ratio = dists[1] / dists[0];
+        if !ratio.is_finite() {
+            // a quarter of the pairs coincide, as structured as data gets, or there are no pairs
+            return MAX_Q;
+        }
+        (1.0 + ratio.ln()).clamp(MIN_Q, MAX_Q)
+    }
+
+    /// Stopping criteria shared by all iterative fits
+    struct StopCriteria {
+        max_iter: usize,
+        tol: f64,
+        max_duration: Option<Duration>,
+        /// Keep the movement of every center in every iteration
+        track_convergence: bool,
+    }
+
+    /// Seed a random number generator, drawing the seed from entropy if `None`
+    fn seeded_rng(seed: Option<u64>) -> StdRng {
+        match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    /// Initialise centers and run `step` until one of the stopping criteria is met
+    ///
+    /// `step` recomputes the memberships for the given centers and returns the objective and the
+    /// updated centers, or `None` if the deadline it is given passed before it finished. The first
+    /// iteration always runs to completion, later ones are abandoned when the time budget runs
+    /// out mid-iteration. The data, `k` and `init` are validated before the first step.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`     - data to cluster (rows are data points)
+    /// * `k`        - number of clusters
+    /// * `init`     - how to choose the initial centers
+    /// * `shuffle`  - shuffle the rows with `rng` before choosing initial centers from them
+    /// * `rng`      - random number generator for the center initialisation
+    /// * `criteria` - when to stop iterating
+    /// * `callback` - receives progress information, returns whether to keep iterating
+    /// * `step`     - one iteration of the algorithm
+    #[allow(clippy::too_many_arguments)]
+    fn fit_centers<T, R, S>(
+        data: &Array2<T>,
+        k: usize,
+        init: &InitStrategy,
+        shuffle: bool,
+        rng: &mut R,
+        criteria: &StopCriteria,
+        callback: &mut dyn FnMut(&IterationInfo) -> bool,
+        mut step: S,
+    ) -> Result<ClusteringResult<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+        R: Rng,
+        S: FnMut(
+            &Array2<f64>,
+            &Array2<f64>,
+            &mut Array2<f64>,
+            Option<Instant>,
+        ) -> Option<(f64, Array2<f64>)>,
+    {
+        let start = Instant::now();
+        let deadline = criteria.max_duration.map(|budget| start + budget);
+        let (n_data, n_features) = data.dim();
+        if n_data == 0 {
+            return Err(AlgoError::EmptyData);
+        }
+        if k == 0 {
+            return Err(AlgoError::NoClusters);
+        }
+        if k > n_data {
+            return Err(AlgoError::TooManyClusters { k, n: n_data });
+        }
+        if let InitStrategy::Provided(centers) | InitStrategy::ProvidedWithMask { centers, .. } =
+            init
+        {
+            if centers.dim() != (k, n_features) {
+                return Err(AlgoError::DimensionMismatch {
+                    expected: (k, n_features),
+                    got: centers.dim(),
+                });
+            }
+        }
+        let frozen = match init {
+            InitStrategy::ProvidedWithMask { frozen, .. } if frozen.len() != k => {
+                return Err(AlgoError::DimensionMismatch {
+                    expected: (k, 1),
+                    got: (frozen.len(), 1),
+                });
+            }
+            InitStrategy::ProvidedWithMask { frozen, .. } => frozen.clone(),
+            _ => vec![false; k],
+        };
+        let x = data.mapv(f64::from);
+
+        let mut centers = if shuffle {
+            // only the choice of initial centers sees the shuffled order
+            let shuffled = super::preprocess::shuffle_rows(&x, rng);
+            initial_centers(init, &shuffled, k, rng)
+        } else {
+            initial_centers(init, &x, k, rng)
+        };
+
+        let mut memberships = Array2::<f64>::zeros((n_data, k));
+        let mut stopped_by = StopReason::MaxIter;
+        let mut n_iter = 0;
+        let mut history = Vec::new();
+        while n_iter < criteria.max_iter {
+            let step_deadline = if n_iter == 0 { None } else { deadline };
+            let (objective, mut new_centers) =
+                match step(&x, &centers, &mut memberships, step_deadline) {
+                    Some(step) => step,
+                    None => {
+                        stopped_by = StopReason::TimeBudget;
+                        break;
+                    }
+                };
+            // every center is updated independently of the others, so keeping some in place
+            // still never increases the objective
+            for (j, _) in frozen.iter().enumerate().filter(|(_, frozen)| **frozen) {
+                new_centers.row_mut(j).assign(&centers.row(j));
+            }
+            let max_shift = if criteria.track_convergence {
+                let movement = cluster_center_movement(&centers, &new_centers);
+                let max_shift = movement.fold(0.0, |a: f64, &b| a.max(b));
+                history.push(movement);
+                max_shift
+            } else {
+                max_center_movement(&centers, &new_centers)
+            };
+            let rounding = CENTER_ROUNDING
+                * new_centers
+                    .outer_iter()
+                    .map(|center| center.dot(&center).sqrt())
+                    .fold(0.0, f64::max);
+            centers = new_centers;
+            n_iter += 1;
+            log::trace!("centers after iteration {}:\n{}", n_iter, centers);
+
+            let info = IterationInfo {
+                iteration: n_iter,
+                objective,
+                max_shift,
+                elapsed: start.elapsed(),
+            };
+            // the callback sees every iteration, including the one that converged
+            let keep_going = callback(&info);
+            if max_shift <= criteria.tol.max(rounding) {
+                stopped_by = StopReason::Tolerance;
+                break;
+            }
+            if !keep_going {
+                stopped_by = StopReason::Callback;
+                break;
+            }
+            if let Some(budget) = criteria.max_duration {
+                if info.elapsed >= budget {
+                    stopped_by = StopReason::TimeBudget;
+                    break;
+                }
+            }
+        }
+
+        // memberships always belong to the centers that are returned
+        let (objective, _) = step(&x, &centers, &mut memberships, None)
+            .expect("a step without deadline always finishes");
+        if !objective.is_finite() {
+            return Err(AlgoError::NumericalFailure("objective is not finite"));
+        }
+        Ok(ClusteringResult {
+            centers: centers.mapv(|val| val.as_()),
+            labels: argmax_rows(&memberships),
+            memberships,
+            objective,
+            n_iter,
+            converged: stopped_by == StopReason::Tolerance,
+            stopped_by,
+            trimmed: Array1::from_elem(n_data, false),
+            center_movement_history: history,
+        })
+    }
+
+    /// A distance between two data points
+    pub trait DistanceMetric: Sync + Send + std::fmt::Debug {
+        /// Distance between `a` and `b`
+        fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64;
+
+        /// Name of a built-in metric as accepted by `metric_from_name`, `None` for other metrics
+        fn name(&self) -> Option<&'static str> {
+            None
+        }
+
+        /// Squared distances between every data point and every center
+        ///
+        /// Override this if squared distances can be computed more cheaply than by squaring
+        /// `distance`.
+        ///
+        /// # Arguments
+        ///
+        /// * `x`       - data points (rows)
+        /// * `centers` - cluster centers (rows)
+        fn squared_distances(&self, x: ArrayView2<f64>, centers: &Array2<f64>) -> Array2<f64> {
+            let mut dists = metric_dist_matrix(x, centers, self);
+            dists.mapv_inplace(|dist| dist * dist);
+            dists
+        }
+    }
+
+    /// Euclidean (L2) distance
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Euclidean;
+
+    impl DistanceMetric for Euclidean {
+        fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+            a.iter()
+                .zip(b.iter())
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f64>()
+                .sqrt()
+        }
+
+        fn name(&self) -> Option<&'static str> {
+            Some("euclidean")
+        }
+
+        fn squared_distances(&self, x: ArrayView2<f64>, centers: &Array2<f64>) -> Array2<f64> {
+            // the fit's hot path, see `euclidean_dist_sq_matrix`
+            dist_sq_product(x.to_owned(), centers.clone())
+        }
+    }
+
+    /// Manhattan (L1) distance
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Manhattan;
+
+    impl DistanceMetric for Manhattan {
+        fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+            a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum()
+        }
+
+        fn name(&self) -> Option<&'static str> {
+            Some("manhattan")
+        }
+    }
+
+    /// Cosine distance `1 - cos(a, b)`, which only depends on the direction of the vectors
+    ///
+    /// A zero vector has no direction and is at distance 1 from everything.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Cosine;
+
+    impl DistanceMetric for Cosine {
+        fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+            let norms = a.dot(&a).sqrt() * b.dot(&b).sqrt();
+            if norms == 0.0 {
+                return 1.0;
+            }
+            // clamp rounding errors, the distance of parallel vectors must not be negative
+            (1.0 - a.dot(&b) / norms).max(0.0)
+        }
+
+        fn name(&self) -> Option<&'static str> {
+            Some("cosine")
+        }
+    }
+
+    /// Chebyshev (L∞) distance, the largest difference in any feature
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Chebyshev;
+
+    impl DistanceMetric for Chebyshev {
+        fn distance(&self, a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+            a.iter()
+                .zip(b.iter())
+                .map(|(x, y)| (x - y).abs())
+                .fold(0.0, f64::max)
+        }
+
+        fn name(&self) -> Option<&'static str> {
+            Some("chebyshev")
+        }
+    }
+
+    /// The built-in metric called `name`: "euclidean", "manhattan", "cosine" or "chebyshev"
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::metric_from_name;
+    ///
+    /// let metric = metric_from_name("manhattan").unwrap();
+    /// assert_eq!(metric.name(), Some("manhattan"));
+    /// assert!(metric_from_name("hamming").is_none());
+    /// ```
+    pub fn metric_from_name(name: &str) -> Option<Arc<dyn DistanceMetric>> {
+        match name {
+            "euclidean" => Some(Arc::new(Euclidean)),
+            "manhattan" => Some(Arc::new(Manhattan)),
+            "cosine" => Some(Arc::new(Cosine)),
+            "chebyshev" => Some(Arc::new(Chebyshev)),
+            _ => None,
+        }
+    }
+
+    /// (De)serialize a metric by its name, see `DistanceMetric::name`
+    #[cfg(feature = "serde")]
+    mod metric_by_name {
+        use super::{metric_from_name, DistanceMetric};
+        use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+        use std::sync::Arc;
+
+        pub fn serialize<S: Serializer>(
+            metric: &Arc<dyn DistanceMetric>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match metric.name() {
+                Some(name) => serializer.serialize_str(name),
+                None => Err(S::Error::custom(format!(
+                    "Only built-in metrics can be serialized, got {:?}",
+                    metric
+                ))),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Arc<dyn DistanceMetric>, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            metric_from_name(&name)
+                .ok_or_else(|| D::Error::custom(format!("Unknown metric \"{}\"", name)))
+        }
+    }
+
+    /// Compute distances between every data point and every center
+    ///
+    /// # Arguments
+    ///
+    /// * `x`       - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    /// * `metric`  - distance to use
+    fn metric_dist_matrix<M: DistanceMetric + ?Sized>(
+        x: ArrayView2<f64>,
+        centers: &Array2<f64>,
+        metric: &M,
+    ) -> Array2<f64> {
+        let mut dists = Array2::<f64>::zeros((x.nrows(), centers.nrows()));
+        for (point, mut row) in x.outer_iter().zip(dists.outer_iter_mut()) {
+            for (center, dist) in centers.outer_iter().zip(row.iter_mut()) {
+                *dist = metric.distance(point, center);
+            }
+        }
+        dists
+    }
+
+    /// Compute squared euclidean distances between every data point and every center
+    ///
+    /// # Arguments
+    ///
+    /// * `x`       - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    fn dist_sq_matrix(x: ArrayView2<f64>, centers: &Array2<f64>) -> Array2<f64> {
+        let mut dists = Array2::<f64>::zeros((x.nrows(), centers.nrows()));
+        for (point, mut row) in x.outer_iter().zip(dists.outer_iter_mut()) {
+            for (center, dist) in centers.outer_iter().zip(row.iter_mut()) {
+                *dist = point
+                    .iter()
+                    .zip(center.iter())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum();
+            }
+        }
+        dists
+    }
+
+    /// Turn dissimilarities into fuzzy memberships in place
+    ///
+    /// Dissimilarities are whatever the objective weighs with `u^q`, i.e. squared euclidean
+    /// distances for fuzzy k means. Points that coincide with one or more centers belong to those
+    /// centers only.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`     - fuzzifier
+    /// * `dists` - dissimilarities (rows are data points), overwritten with memberships
+    fn memberships_from_dissimilarities(q: f64, dists: &mut Array2<f64>) {
+        for row in dists.outer_iter_mut() {
+            row_memberships_from_dissimilarities(q, row);
+        }
+    }
+
+    /// Turn the dissimilarities of a single data point into its fuzzy memberships in place
+    ///
+    /// Returns the normalisation sum `Σ_k d_k^(1/(1-q))`, which is infinite if the point
+    /// coincides with a center. Doesn't allocate.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`   - fuzzifier
+    /// * `row` - dissimilarities to every center, overwritten with memberships
+    fn row_memberships_from_dissimilarities(q: f64, mut row: ArrayViewMut1<f64>) -> f64 {
+        let n_zero = row.iter().filter(|&&d| d == 0.0).count();
+        if n_zero > 0 {
+            let share = 1.0 / n_zero as f64;
+            row.mapv_inplace(|d| if d == 0.0 { share } else { 0.0 });
+            return f64::INFINITY;
+        }
+        row.mapv_inplace(|d| d.powf(1.0 / (1.0 - q)));
+        let sum = row.sum();
+        row.mapv_inplace(|val| val / sum);
+        sum
+    }
+
+    /// Derive the seed of a sub-computation (restart, chunk, ...) from a master seed
+    ///
+    /// Seeds depend only on the master seed and the index, never on thread scheduling.
+    ///
+    /// # Arguments
+    ///
+    /// * `master` - seed of the whole run
+    /// * `index`  - index of the sub-computation
+    pub fn derive_seed(master: u64, index: u64) -> u64 {
+        // splitmix64 finaliser
+        let mut z = master.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Map `f` over consecutive chunks of `CHUNK_ROWS` rows of `x`, returning results in chunk order
+    ///
+    /// Chunks are processed on the rayon thread pool when the `parallel` feature is enabled.
+    /// Returns `None` if `deadline` passes before all chunks were started.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`        - data points (rows)
+    /// * `deadline` - give up instead of starting another chunk after this point in time
+    /// * `f`        - receives the index of the first row and the chunk
+    fn map_row_chunks<R, F>(x: &Array2<f64>, deadline: Option<Instant>, f: F) -> Option<Vec<R>>
+    where
+        R: Send,
+        F: Fn(usize, ArrayView2<f64>) -> R + Sync + Send,
+    {
+        let n_chunks = x.nrows().div_ceil(CHUNK_ROWS);
+        let chunk = |c: usize| {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return None;
+            }
+            let start = c * CHUNK_ROWS;
+            let end = usize::min(start + CHUNK_ROWS, x.nrows());
+            Some(f(start, x.slice(s![start..end, ..])))
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..n_chunks).into_par_iter().map(chunk).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            (0..n_chunks).map(chunk).collect()
+        }
+    }
+
+    /// Partial sums of one chunk of data points
+    struct ChunkPartial {
+        memberships: Array2<f64>,
+        objective: f64,
+        weighted_sum: Array2<f64>,
+        weight_sum: Array1<f64>,
+    }
+
+    /// Fuzzy memberships and center update weights `u^q` for squared distances
+    ///
+    /// # Arguments
+    ///
+    /// * `q`     - fuzzifier
+    /// * `dists` - squared distances (rows are data points)
+    fn fuzzy_weights(q: f64, dists: &Array2<f64>) -> (Array2<f64>, Array2<f64>) {
+        let mut memberships = dists.clone();
+        memberships_from_dissimilarities(q, &mut memberships);
+        let weights = memberships.mapv(|val| val.powf(q));
+        (memberships, weights)
+    }
+
+    /// One-hot memberships in the nearest cluster for squared distances, used as weights as well
+    ///
+    /// # Arguments
+    ///
+    /// * `dists` - squared distances (rows are data points)
+    fn hard_weights(dists: &Array2<f64>) -> (Array2<f64>, Array2<f64>) {
+        let mut memberships = Array2::<f64>::zeros(dists.dim());
+        for (row, mut out) in dists.outer_iter().zip(memberships.outer_iter_mut()) {
+            let mut nearest = 0;
+            for (j, dist) in row.iter().enumerate().skip(1) {
+                if *dist < row[nearest] {
+                    nearest = j;
+                }
+            }
+            out[nearest] = 1.0;
+        }
+        (memberships.clone(), memberships)
+    }
+
+    /// Recompute memberships for the given centers and the weighted cluster means
+    ///
+    /// Returns the objective `Σ w_ik d_ik²` of the new memberships and the updated centers, or
+    /// `None` if `deadline` passed before the step finished. Clusters without any weight keep
+    /// their previous center.
+    ///
+    /// # Arguments
+    ///
+    /// * `x`           - data points (rows)
+    /// * `row_weights` - weight of every data point in the objective and center update, or `None`
+    /// * `centers`     - cluster centers (rows)
+    /// * `metric`      - distance between data points and centers
+    /// * `memberships` - write membership information here
+    /// * `deadline`    - give up after this point in time
+    /// * `weigh`       - turns squared distances into memberships and center update weights
+    fn membership_step<W>(
+        x: &Array2<f64>,
+        row_weights: Option<&Array1<f64>>,
+        centers: &Array2<f64>,
+        metric: &dyn DistanceMetric,
+        memberships: &mut Array2<f64>,
+        deadline: Option<Instant>,
+        weigh: W,
+    ) -> Option<(f64, Array2<f64>)>
+    where
+        W: Fn(&Array2<f64>) -> (Array2<f64>, Array2<f64>) + Sync + Send,
+    {
+        let partials = map_row_chunks(x, deadline, |start, chunk| {
+            let dists = metric.squared_distances(chunk, centers);
+            let (chunk_memberships, mut weights) = weigh(&dists);
+            if let Some(row_weights) = row_weights {
+                let chunk_row_weights = row_weights.slice(s![start..start + chunk.nrows()]);
+                weights *= &chunk_row_weights.insert_axis(Axis(1));
+            }
+            ChunkPartial {
+                objective: compensated_sum(weights.iter().zip(dists.iter()).map(|(w, d)| w * d)),
+                weighted_sum: weights.t().dot(&chunk),
+                weight_sum: weights.sum_axis(Axis(0)),
+                memberships: chunk_memberships,
+            }
+        })?;
+
+        // combine in chunk order so the sums don't depend on thread scheduling
+        let mut objective = CompensatedSum::default();
+        let mut weighted_sum = Array2::<f64>::zeros(centers.dim());
+        let mut weight_sum = Array1::<f64>::zeros(centers.nrows());
+        let mut row = 0;
+        for partial in partials {
+            let rows = partial.memberships.nrows();
+            memberships
+                .slice_mut(s![row..row + rows, ..])
+                .assign(&partial.memberships);
+            row += rows;
+            objective.add(partial.objective);
+            weighted_sum += &partial.weighted_sum;
+            weight_sum += &partial.weight_sum;
+        }
+        Some((
+            objective.value(),
+            weighted_means(weighted_sum, &weight_sum, centers),
+        ))
+    }
+
+    /// Like `membership_step`, but leaves the `trim_fraction` of data points farthest from their
+    /// nearest center out of the center update and the objective
+    ///
+    /// # Arguments
+    ///
+    /// * `trim_fraction` - fraction of data points to leave out
+    /// * `x`             - data points (rows)
+    /// * `centers`       - cluster centers (rows)
+    /// * `metric`        - distance between data points and centers
+    /// * `memberships`   - write membership information here
+    /// * `trimmed`       - write which data points were left out here
+    /// * `deadline`      - give up after this point in time
+    /// * `weigh`         - turns squared distances into memberships and center update weights
+    #[allow(clippy::too_many_arguments)]
+    fn trimmed_step<W>(
+        trim_fraction: f64,
+        x: &Array2<f64>,
+        centers: &Array2<f64>,
+        metric: &dyn DistanceMetric,
+        memberships: &mut Array2<f64>,
+        trimmed: &mut Array1<bool>,
+        deadline: Option<Instant>,
+        weigh: W,
+    ) -> Option<(f64, Array2<f64>)>
+    where
+        W: Fn(&Array2<f64>) -> (Array2<f64>, Array2<f64>) + Sync + Send,
+    {
+        let partials = map_row_chunks(x, deadline, |_, chunk| {
+            let dists = metric.squared_distances(chunk, centers);
+            let (chunk_memberships, weights) = weigh(&dists);
+            (dists, chunk_memberships, weights)
+        })?;
+
+        let mut dists = Array2::<f64>::zeros(memberships.dim());
+        let mut weights = Array2::<f64>::zeros(memberships.dim());
+        let mut row = 0;
+        for (chunk_dists, chunk_memberships, chunk_weights) in partials {
+            let rows = chunk_dists.nrows();
+            let chunk_rows = s![row..row + rows, ..];
+            dists.slice_mut(chunk_rows).assign(&chunk_dists);
+            memberships.slice_mut(chunk_rows).assign(&chunk_memberships);
+            weights.slice_mut(chunk_rows).assign(&chunk_weights);
+            row += rows;
+        }
+
+        // stable sort, ties are trimmed in data order
+        let min_dists = dists.map_axis(Axis(1), |row| row.fold(f64::INFINITY, |a, b| a.min(*b)));
+        let mut order: Vec<usize> = (0..x.nrows()).collect();
+        order.sort_by(|&a, &b| min_dists[b].total_cmp(&min_dists[a]));
+        let n_trim = (trim_fraction * x.nrows() as f64).floor() as usize;
+        trimmed.fill(false);
+        for &i in order.iter().take(n_trim) {
+            trimmed[i] = true;
+            weights.row_mut(i).fill(0.0);
+        }
+
+        let partials = map_row_chunks(x, deadline, |start, chunk| {
+            let chunk_rows = s![start..start + chunk.nrows(), ..];
+            let chunk_weights = weights.slice(chunk_rows);
+            (
+                compensated_sum(
+                    chunk_weights
+                        .iter()
+                        .zip(dists.slice(chunk_rows).iter())
+                        .map(|(w, d)| w * d),
+                ),
+                chunk_weights.t().dot(&chunk),
+                chunk_weights.sum_axis(Axis(0)),
+            )
+        })?;
+
+        let mut objective = CompensatedSum::default();
+        let mut weighted_sum = Array2::<f64>::zeros(centers.dim());
+        let mut weight_sum = Array1::<f64>::zeros(centers.nrows());
+        for (chunk_objective, chunk_weighted_sum, chunk_weight_sum) in partials {
+            objective.add(chunk_objective);
+            weighted_sum += &chunk_weighted_sum;
+            weight_sum += &chunk_weight_sum;
+        }
+        Some((
+            objective.value(),
+            weighted_means(weighted_sum, &weight_sum, centers),
+        ))
+    }
+
+    /// Divide weighted sums of data points by the sum of their weights
+    ///
+    /// Clusters without any weight keep their previous center.
+    ///
+    /// # Arguments
+    ///
+    /// * `weighted_sum` - weighted sum of data points per cluster (rows)
+    /// * `weight_sum`   - sum of weights per cluster
+    /// * `prev`         - previous cluster centers
+    fn weighted_means(
+        mut weighted_sum: Array2<f64>,
+        weight_sum: &Array1<f64>,
+        prev: &Array2<f64>,
+    ) -> Array2<f64> {
+        for ((mut center, weight), prev_center) in weighted_sum
+            .outer_iter_mut()
+            .zip(weight_sum.iter())
+            .zip(prev.outer_iter())
+        {
+            if *weight > 0.0 {
+                center.mapv_inplace(|val| val / weight);
+            } else {
+                center.assign(&prev_center);
+            }
+        }
+        weighted_sum
+    }
+
+    /// Weighted median of `values`
+    ///
+    /// The smallest value at which the cumulative weight reaches half of the total weight. If the
+    /// cumulative weight equals exactly half the total weight there, the median lies anywhere
+    /// between that value and the next one and the midpoint of both is returned, like the median
+    /// of an even number of unweighted values. Returns NaN if the total weight is zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `values`  - values to take the median of
+    /// * `weights` - non-negative weight per value
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::weighted_median;
+    ///
+    /// assert_eq!(weighted_median(&[1.0, 2.0, 10.0], &[1.0, 1.0, 1.0]), 2.0);
+    /// assert_eq!(weighted_median(&[1.0, 2.0, 10.0], &[3.0, 1.0, 1.0]), 1.0);
+    /// assert_eq!(weighted_median(&[1.0, 2.0, 3.0, 4.0], &[1.0, 1.0, 1.0, 1.0]), 2.5);
+    /// ```
+    pub fn weighted_median(values: &[f64], weights: &[f64]) -> f64 {
+        let mut order: Vec<usize> = (0..values.len()).filter(|&i| weights[i] > 0.0).collect();
+        order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+        let total: f64 = order.iter().map(|&i| weights[i]).sum();
+        if total <= 0.0 {
+            return f64::NAN;
+        }
+
+        let half = total / 2.0;
+        let mut cum = 0.0;
+        for (pos, &i) in order.iter().enumerate() {
+            cum += weights[i];
+            if (cum - half).abs() <= f64::EPSILON * total && pos + 1 < order.len() {
+                return (values[i] + values[order[pos + 1]]) / 2.0;
+            }
+            if cum >= half {
+                return values[i];
+            }
+        }
+        values[*order.last().unwrap()]
+    }
+
+    /// One iteration of fuzzy c-medians
+    ///
+    /// Memberships are computed from manhattan distances, centers are the `u^q` weighted medians
+    /// per feature. Returns the objective `Σ u_ik^q d_ik` of the new memberships and the updated
+    /// centers, or `None` if `deadline` passed before the step finished. Clusters without any
+    /// membership keep their previous center.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`           - fuzzifier
+    /// * `x`           - data points (rows)
+    /// * `centers`     - cluster centers (rows)
+    /// * `memberships` - write membership information here
+    /// * `deadline`    - give up after this point in time
+    fn median_step(
+        q: f64,
+        x: &Array2<f64>,
+        centers: &Array2<f64>,
+        memberships: &mut Array2<f64>,
+        deadline: Option<Instant>,
+    ) -> Option<(f64, Array2<f64>)> {
+        let partials = map_row_chunks(x, deadline, |_, chunk| {
+            let dists = metric_dist_matrix(chunk, centers, &Manhattan);
+            let mut chunk_memberships = dists.clone();
+            memberships_from_dissimilarities(q, &mut chunk_memberships);
+            let objective = compensated_sum(
+                chunk_memberships
+                    .iter()
+                    .zip(dists.iter())
+                    .map(|(u, d)| u.powf(q) * d),
+            );
+            (chunk_memberships, objective)
+        })?;
+
+        let mut objective = CompensatedSum::default();
+        let mut row = 0;
+        for (chunk_memberships, chunk_objective) in partials {
+            let rows = chunk_memberships.nrows();
+            memberships
+                .slice_mut(s![row..row + rows, ..])
+                .assign(&chunk_memberships);
+            row += rows;
+            objective.add(chunk_objective);
+        }
+        let objective = objective.value();
+
+        let mut new_centers = centers.clone();
+        for (mut center, membership) in new_centers
+            .outer_iter_mut()
+            .zip(memberships.axis_iter(Axis(1)))
+        {
+            let weights = membership.mapv(|val| val.powf(q)).to_vec();
+            if weights.iter().sum::<f64>() <= 0.0 {
+                continue;
+            }
+            for (feature, value) in x.axis_iter(Axis(1)).zip(center.iter_mut()) {
+                *value = weighted_median(&feature.to_vec(), &weights);
+            }
+        }
+        Some((objective, new_centers))
+    }
+
+    /// How far every cluster center moved between two iterations, the euclidean norm of the
+    /// difference of corresponding rows
+    ///
+    /// # Arguments
+    ///
+    /// * `prev_centers` - cluster centers (rows) before the iteration
+    /// * `next_centers` - cluster centers (rows) after the iteration
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{cluster_center_movement, max_center_movement};
+    /// use ndarray::array;
+    ///
+    /// let prev = array![[0.0, 0.0], [1.0, 1.0]];
+    /// let next = array![[3.0, 4.0], [1.0, 1.0]];
+    /// assert_eq!(cluster_center_movement(&prev, &next), array![5.0, 0.0]);
+    /// assert_eq!(max_center_movement(&prev, &next), 5.0);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the center arrays differ in shape
+    pub fn cluster_center_movement(
+        prev_centers: &Array2<f64>,
+        next_centers: &Array2<f64>,
+    ) -> Array1<f64> {
+        assert_eq!(
+            prev_centers.dim(),
+            next_centers.dim(),
+            "Center arrays differ in shape!"
+        );
+        prev_centers
+            .outer_iter()
+            .zip(next_centers.outer_iter())
+            .map(|(a, b)| (&a - &b).mapv(|val| val * val).sum().sqrt())
+            .collect()
+    }
+
+    /// Largest distance any cluster center moved between two iterations, which fits stop on
+    /// once it falls below their tolerance
+    ///
+    /// # Arguments
+    ///
+    /// * `prev_centers` - cluster centers (rows) before the iteration
+    /// * `next_centers` - cluster centers (rows) after the iteration
+    ///
+    /// # Panics
+    ///
+    /// Panics if the center arrays differ in shape
+    pub fn max_center_movement(prev_centers: &Array2<f64>, next_centers: &Array2<f64>) -> f64 {
+        cluster_center_movement(prev_centers, next_centers).fold(0.0, |a: f64, &b| a.max(b))
+    }
+
+    /// Merge clusters whose centers lie within `threshold` of each other
+    ///
+    /// Close clusters are grouped transitively, so a chain of close centers ends up as a single
+    /// cluster. The merged center is the average of the group's centers, weighted by the total
+    /// membership of each cluster. Memberships, labels and the objective are then computed once
+    /// for the merged centers, which needs the data and fuzzifier the result was fitted with.
+    /// Merged clusters are numbered in order of their first original cluster.
+    ///
+    /// # Arguments
+    ///
+    /// * `result`    - clustering result to merge clusters of
+    /// * `data`      - data the result was fitted to (rows are data points)
+    /// * `q`         - fuzzifier
+    /// * `threshold` - largest center distance at which two clusters are merged
+    /// * `metric`    - distance between cluster centers and data points
+    ///
+    /// # Errors
+    ///
+    /// `InvalidFuzzifier` for `q <= 1`, `DimensionMismatch` if `data` doesn't match the result
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{merge_close_clusters, ClusteringAlgorithm, Euclidean, FuzzyKMeansConfig, InitStrategy};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.0], [0.1, 0.0], [0.0, 0.1], [5.0, 5.0], [5.1, 5.0]];
+    /// let config = FuzzyKMeansConfig {
+    ///     init: InitStrategy::Provided(array![[0.0, 0.0], [0.1, 0.1], [5.0, 5.0]]),
+    ///     ..FuzzyKMeansConfig::new(3, 1, 2.0)
+    /// };
+    /// let result = config.fit(&data).unwrap();
+    /// let merged = merge_close_clusters(&result, &data, 2.0, 1.0, &Euclidean).unwrap();
+    /// assert_eq!(merged.centers.nrows(), 2);
+    /// assert_eq!(merged.labels.to_vec(), vec![0, 0, 0, 1, 1]);
+    /// ```
+    pub fn merge_close_clusters<T>(
+        result: &ClusteringResult<T>,
+        data: &Array2<T>,
+        q: f64,
+        threshold: f64,
+        metric: &dyn DistanceMetric,
+    ) -> Result<ClusteringResult<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        check_fuzzifier(q)?;
+        check_features(data.ncols(), result.centers.dim())?;
+        if data.nrows() != result.memberships.nrows() {
+            return Err(AlgoError::DimensionMismatch {
+                expected: result.memberships.dim(),
+                got: (data.nrows(), result.memberships.ncols()),
+            });
+        }
+        let centers = result.centers.mapv(f64::from);
+        let k = centers.nrows();
+
+        // group[j] is the lowest cluster index cluster j is connected to
+        let mut group: Vec<usize> = (0..k).collect();
+        for i in 0..k {
+            for j in i + 1..k {
+                if group[i] != group[j]
+                    && metric.distance(centers.row(i), centers.row(j)) <= threshold
+                {
+                    let (keep, drop) = (group[i].min(group[j]), group[i].max(group[j]));
+                    group
+                        .iter_mut()
+                        .filter(|g| **g == drop)
+                        .for_each(|g| *g = keep);
+                }
+            }
+        }
+        let first_members: Vec<usize> = (0..k).filter(|&j| group[j] == j).collect();
+        let new_index: Vec<usize> = group
+            .iter()
+            .map(|g| first_members.iter().position(|first| first == g).unwrap())
+            .collect();
+
+        let n_merged = first_members.len();
+        let cluster_mass = metrics::soft_counts(&result.memberships);
+        let mut weighted_sum = Array2::<f64>::zeros((n_merged, centers.ncols()));
+        let mut weight_sum = Array1::<f64>::zeros(n_merged);
+        for (j, center) in centers.outer_iter().enumerate() {
+            weighted_sum
+                .row_mut(new_index[j])
+                .scaled_add(cluster_mass[j], &center);
+            weight_sum[new_index[j]] += cluster_mass[j];
+        }
+        let merged_centers = weighted_means(
+            weighted_sum,
+            &weight_sum,
+            &centers.select(Axis(0), &first_members),
+        );
+
+        let x = data.mapv(f64::from);
+        let mut memberships = Array2::<f64>::zeros((x.nrows(), n_merged));
+        let (objective, _) = membership_step(
+            &x,
+            None,
+            &merged_centers,
+            metric,
+            &mut memberships,
+            None,
+            |dists| fuzzy_weights(q, dists),
+        )
+        .expect("a step without deadline always finishes");
+        Ok(ClusteringResult {
+            centers: merged_centers.mapv(|val| val.as_()),
+            labels: argmax_rows(&memberships),
+            memberships,
+            objective,
+            n_iter: result.n_iter,
+            converged: result.converged,
+            stopped_by: result.stopped_by,
+            trimmed: Array1::from_elem(x.nrows(), false),
+            center_movement_history: result.center_movement_history.clone(),
+        })
+    }
+
+    /// Renumber clusters from the largest to the smallest
+    ///
+    /// The size of a cluster is its soft count, the sum of the memberships of all data points in
+    /// it. Centers, membership columns and labels are permuted so cluster 0 is the largest.
+    /// Clusters of equal size keep their relative order.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - clustering result to renumber
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{relabel_by_size, ClusteringAlgorithm, FuzzyKMeansConfig};
+    /// use ndarray::{array, Axis};
+    ///
+    /// let data = array![[0.0], [10.0], [10.1], [10.2], [20.0], [20.1]];
+    /// let config = FuzzyKMeansConfig {
+    ///     seed: Some(3),
+    ///     ..FuzzyKMeansConfig::new(3, 50, 2.0)
+    /// };
+    /// let result = relabel_by_size(config.fit(&data).unwrap());
+    /// let sizes = result.memberships.sum_axis(Axis(0));
+    /// assert!(sizes[0] >= sizes[1] && sizes[1] >= sizes[2]);
+    /// assert_eq!(result.labels, array![2, 0, 0, 0, 1, 1]);
+    /// ```
+    pub fn relabel_by_size(result: ClusteringResult<f64>) -> ClusteringResult<f64> {
+        let sizes = metrics::soft_counts(&result.memberships);
+        let mut order: Vec<usize> = (0..sizes.len()).collect();
+        order.sort_by(|&a, &b| sizes[b].total_cmp(&sizes[a]));
+        let mut new_label = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_label[old] = new;
+        }
+        ClusteringResult {
+            centers: result.centers.select(Axis(0), &order),
+            memberships: result.memberships.select(Axis(1), &order),
+            labels: result.labels.mapv(|label| new_label[label]),
+            center_movement_history: result
+                .center_movement_history
+                .iter()
+                .map(|movement| movement.select(Axis(0), &order))
+                .collect(),
+            ..result
+        }
+    }
+
+    /// Cluster integer data, e.g. counts
+    ///
+    /// The data is converted to `f64` once and clustered like floating point data, so centers and
+    /// memberships are `f64`.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - clustering algorithm to fit
+    /// * `data`      - data to cluster (rows are data points)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{assign_labels, fit_ints, FuzzyKMeansConfig};
+    /// use ndarray::array;
+    ///
+    /// let counts = array![[0i64, 1], [1, 0], [0, 0], [40, 41], [41, 40], [40, 40]];
+    /// let config = FuzzyKMeansConfig {
+    ///     seed: Some(1),
+    ///     ..FuzzyKMeansConfig::new(2, 50, 2.0)
+    /// };
+    /// let result = fit_ints(&config, &counts).unwrap();
+    /// let labels = assign_labels(&counts, &result.centers).unwrap();
+    /// assert_eq!(labels, result.labels);
+    /// assert_eq!(labels[0], labels[2]);
+    /// assert_ne!(labels[0], labels[3]);
+    /// ```
+    pub fn fit_ints(
+        algorithm: &dyn ClusteringAlgorithm<f64>,
+        data: &Array2<i64>,
+    ) -> Result<ClusteringResult<f64>, AlgoError> {
+        algorithm.fit(&data.mapv(|val| val as f64))
+    }
+
+    /// Assign every data point to its nearest cluster center (euclidean distance)
+    ///
+    /// Works for any primitive numeric type, integers included.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`
+    pub fn assign_labels<T, C>(
+        data: &Array2<T>,
+        centers: &Array2<C>,
+    ) -> Result<Array1<usize>, AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+        C: num_traits::AsPrimitive<f64>,
+    {
+        Ok(assign_labels_with_dist(data, centers, &Euclidean)?.0)
+    }
+
+    /// Assign every data point to its nearest cluster center and return the distance to it
+    ///
+    /// Like `assign_labels`, but for any metric. Distances are the metric's distances, not
+    /// squared ones, so points lying exactly on their center get 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    /// * `metric`  - distance between data points and centers
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{assign_labels_with_dist, Euclidean};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.0], [3.0, 4.0], [10.0, 1.0]];
+    /// let centers = array![[0.0, 0.0], [10.0, 0.0]];
+    /// let (labels, dists) = assign_labels_with_dist(&data, &centers, &Euclidean).unwrap();
+    /// assert_eq!(labels, array![0, 0, 1]);
+    /// assert_eq!(dists, array![0.0, 5.0, 1.0]);
+    /// ```
+    pub fn assign_labels_with_dist<T, C>(
+        data: &Array2<T>,
+        centers: &Array2<C>,
+        metric: &dyn DistanceMetric,
+    ) -> Result<(Array1<usize>, Array1<f64>), AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+        C: num_traits::AsPrimitive<f64>,
+    {
+        check_features(data.ncols(), centers.dim())?;
+        let centers = centers.mapv(|val| val.as_());
+        let mut labels = Array1::<usize>::zeros(data.nrows());
+        let mut dists = Array1::<f64>::zeros(data.nrows());
+        for ((point, label), min_dist) in data
+            .outer_iter()
+            .zip(labels.iter_mut())
+            .zip(dists.iter_mut())
+        {
+            let point = point.mapv(|val| val.as_());
+            *min_dist = f64::INFINITY;
+            for (j, center) in centers.outer_iter().enumerate() {
+                let dist = metric.distance(point.view(), center);
+                if dist < *min_dist {
+                    *min_dist = dist;
+                    *label = j;
+                }
+            }
+        }
+        Ok((labels, dists))
+    }
+
+    /// Assign every data point to its nearest cluster center, for predicting many points fast
+    ///
+    /// Same labels as `assign_labels_with_dist`, but only the labels are returned. The
+    /// distances aren't stored, only the nearest center seen so far per point, and data points
+    /// are converted to `f64` in a buffer that is reused for every row.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    /// * `metric`  - distance between data points and centers
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{assign_crisp, assign_labels_with_dist, Manhattan};
+    /// use ndarray::{array, Array2};
+    ///
+    /// let centers = array![[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]];
+    /// let data = Array2::from_shape_fn((100, 2), |(i, j)| ((i * 7 + j * 13) % 12) as f64);
+    /// let labels = assign_crisp(&data, &centers, &Manhattan).unwrap();
+    /// assert_eq!(labels, assign_labels_with_dist(&data, &centers, &Manhattan).unwrap().0);
+    ///
+    /// let points = array![[1, 1], [9, 2], [2, 8]];
+    /// let centers = array![[0, 0], [10, 0], [0, 10]];
+    /// assert_eq!(assign_crisp(&points, &centers, &Manhattan).unwrap(), array![0, 1, 2]);
+    /// ```
+    pub fn assign_crisp<T>(
+        data: &Array2<T>,
+        centers: &Array2<T>,
+        metric: &dyn DistanceMetric,
+    ) -> Result<Array1<usize>, AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+    {
+        check_features(data.ncols(), centers.dim())?;
+        let centers = centers.mapv(|val| val.as_());
+        let mut point = Array1::<f64>::zeros(data.ncols());
+        Ok(data
+            .outer_iter()
+            .map(|row| {
+                point.zip_mut_with(&row, |dst, src| *dst = src.as_());
+                let mut nearest = 0;
+                let mut min_dist = f64::INFINITY;
+                for (j, center) in centers.outer_iter().enumerate() {
+                    let dist = metric.distance(point.view(), center);
+                    if dist < min_dist {
+                        min_dist = dist;
+                        nearest = j;
+                    }
+                }
+                nearest
+            })
+            .collect())
+    }
+
+    /// Distances between every data point and every cluster center
+    ///
+    /// Unlike the memberships, which only depend on how distances compare, these are the
+    /// metric's raw distances, e.g. for spotting points far from all centers.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    /// * `metric`  - distance between data points and centers
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{compute_distance_matrix, Euclidean};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.0], [3.0, 4.0], [10.0, 0.0]];
+    /// let centers = array![[0.0, 0.0], [10.0, 0.0]];
+    /// let dists = compute_distance_matrix(&data, &centers, &Euclidean).unwrap();
+    /// assert_eq!(dists.dim(), (3, 2));
+    /// assert!(dists.iter().all(|&dist| dist >= 0.0));
+    /// // points lying on a center are at distance 0 from it
+    /// assert_eq!(dists[[0, 0]], 0.0);
+    /// assert_eq!(dists[[2, 1]], 0.0);
+    /// assert_eq!(dists[[1, 0]], 5.0);
+    /// ```
+    pub fn compute_distance_matrix<T>(
+        data: &Array2<T>,
+        centers: &Array2<T>,
+        metric: &dyn DistanceMetric,
+    ) -> Result<Array2<f64>, AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+    {
+        check_features(data.ncols(), centers.dim())?;
+        Ok(metric_dist_matrix(
+            data.mapv(|val| val.as_()).view(),
+            &centers.mapv(|val| val.as_()),
+            metric,
+        ))
+    }
+
+    /// Fuzzy c-means objective `J = Σ_i Σ_j u_ij^q d(x_i, v_j)²` of a clustering
+    ///
+    /// The quantity a fit minimises, so it compares clusterings of the same data with the same
+    /// fuzzifier and metric, e.g. two initialisations or a model loaded from a file. For the
+    /// result of a fit it equals `ClusteringResult::objective` up to rounding.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`        - data points (rows)
+    /// * `centers`     - cluster centers (rows)
+    /// * `memberships` - membership of every data point (rows) in every cluster (columns)
+    /// * `q`           - fuzzifier
+    /// * `metric`      - distance between data points and centers
+    ///
+    /// # Errors
+    ///
+    /// `DimensionMismatch` if `centers` doesn't have as many columns as `data`, or `memberships`
+    /// isn't of shape `(data.nrows(), centers.nrows())`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{fcm_objective, ClusteringAlgorithm, Euclidean, FuzzyKMeansConfig};
+    /// use ndarray::Array2;
+    ///
+    /// let data = Array2::from_shape_fn((60, 2), |(i, j)| {
+    ///     (i / 20) as f64 * 4.0 + ((i * 5 + j * 3) % 7) as f64 * 0.2
+    /// });
+    /// // the objective never increases from one iteration to the next
+    /// let mut previous = f64::INFINITY;
+    /// for n_iter in 1..6 {
+    ///     let config = FuzzyKMeansConfig {
+    ///         seed: Some(3),
+    ///         ..FuzzyKMeansConfig::new(3, n_iter, 2.0)
+    ///     };
+    ///     let result = config.fit(&data).unwrap();
+    ///     let objective =
+    ///         fcm_objective(&data, &result.centers, &result.memberships, 2.0, &Euclidean)
+    ///             .unwrap();
+    ///     assert!((objective - result.objective).abs() <= 1e-9 * result.objective);
+    ///     assert!(objective <= previous + 1e-9);
+    ///     previous = objective;
+    /// }
+    /// ```
+    pub fn fcm_objective<T>(
+        data: &Array2<T>,
+        centers: &Array2<T>,
+        memberships: &Array2<f64>,
+        q: f64,
+        metric: &dyn DistanceMetric,
+    ) -> Result<f64, AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+    {
+        check_features(data.ncols(), centers.dim())?;
+        if memberships.dim() != (data.nrows(), centers.nrows()) {
+            return Err(AlgoError::DimensionMismatch {
+                expected: (data.nrows(), centers.nrows()),
+                got: memberships.dim(),
+            });
+        }
+        let dists = metric.squared_distances(
+            data.mapv(|val| val.as_()).view(),
+            &centers.mapv(|val| val.as_()),
+        );
+        Ok(compensated_sum(
+            memberships
+                .iter()
+                .zip(dists.iter())
+                .map(|(u, dist)| u.powf(q) * dist),
+        ))
+    }
+
+    /// Fuzzy memberships of data points in fixed clusters under any metric
+    ///
+    /// Memberships are computed from squared distances like in a fit, so predicting the data a
+    /// model was fit on reproduces the memberships of the fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`    - data points (rows)
+    /// * `centers` - cluster centers (rows)
+    /// * `q`       - fuzzifier
+    /// * `metric`  - distance between data points and centers
+    ///
+    /// # Errors
+    ///
+    /// `InvalidFuzzifier` for `q <= 1`, `DimensionMismatch` if `centers` doesn't have as many
+    /// columns as `data`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{predict_memberships, Manhattan};
+    /// use ndarray::array;
+    ///
+    /// let centers = array![[0.0, 0.0], [4.0, 0.0]];
+    /// let memberships =
+    ///     predict_memberships(&array![[1.0, 0.0], [4.0, 0.0]], &centers, 2.0, &Manhattan).unwrap();
+    /// assert!((memberships[[0, 0]] - 0.9).abs() < 1e-12);
+    /// assert_eq!(memberships.row(1).to_vec(), vec![0.0, 1.0]);
+    /// ```
+    pub fn predict_memberships(
+        data: &Array2<f64>,
+        centers: &Array2<f64>,
+        q: f64,
+        metric: &dyn DistanceMetric,
+    ) -> Result<Array2<f64>, AlgoError> {
+        check_fuzzifier(q)?;
+        check_features(data.ncols(), centers.dim())?;
+        let mut memberships = metric.squared_distances(data.view(), centers);
+        memberships_from_dissimilarities(q, &mut memberships);
+        Ok(memberships)
+    }
+
+    /// Gradient of the fuzzy membership of one point in one cluster with respect to the point
+    ///
+    /// Entry `f` is `du_k / dx_f`: how fast the membership in cluster `cluster` changes as the
+    /// point moves along feature `f`, with all centers fixed. Memberships are those of fuzzy
+    /// k means on squared euclidean distances `D_j`, so with `p = 1 / (q - 1)` the chain rule
+    /// gives `du_k / dx = 2 p u_k (sum_j u_j (x - c_j) / D_j - (x - c_k) / D_k)`. A point lying
+    /// exactly on a center gets zero, the membership is only differentiable there for `q < 3`.
+    ///
+    /// The whole set of centers is needed, moving the point changes its distance to all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `point`   - data point
+    /// * `centers` - cluster centers (rows)
+    /// * `cluster` - index of the cluster whose membership is differentiated
+    /// * `q`       - fuzzifier
+    ///
+    /// # Errors
+    ///
+    /// `InvalidFuzzifier` for `q <= 1`, `DimensionMismatch` if `centers` doesn't have as many
+    /// columns as `point` has features
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cluster` is not a row of `centers`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{membership_gradient, predict_memberships, Euclidean};
+    /// use ndarray::{array, Array2};
+    ///
+    /// let centers = array![[0.0, 0.0], [3.0, 1.0], [1.0, 4.0]];
+    /// let point = array![1.2, 0.7];
+    /// let gradient = membership_gradient(point.view(), &centers, 1, 2.5).unwrap();
+    ///
+    /// // central finite differences of the membership in cluster 1
+    /// let membership = |x: &Array2<f64>| {
+    ///     predict_memberships(x, &centers, 2.5, &Euclidean).unwrap()[[0, 1]]
+    /// };
+    /// let h = 1e-6;
+    /// for f in 0..2 {
+    ///     let mut plus = point.clone().insert_axis(ndarray::Axis(0));
+    ///     let mut minus = plus.clone();
+    ///     plus[[0, f]] += h;
+    ///     minus[[0, f]] -= h;
+    ///     let numerical = (membership(&plus) - membership(&minus)) / (2.0 * h);
+    ///     assert!((gradient[f] - numerical).abs() < 1e-6);
+    /// }
+    /// ```
+    pub fn membership_gradient<T>(
+        point: ArrayView1<T>,
+        centers: &Array2<T>,
+        cluster: usize,
+        q: f64,
+    ) -> Result<Array1<f64>, AlgoError>
+    where
+        T: num_traits::AsPrimitive<f64>,
+    {
+        check_fuzzifier(q)?;
+        check_features(point.len(), centers.dim())?;
+        let x = point.mapv(|val| val.as_());
+        let centers = centers.mapv(|val| val.as_());
+        // offsets from every center and the squared distances to them
+        let offsets = &x.view().insert_axis(Axis(0)) - &centers;
+        let dists = offsets.map_axis(Axis(1), |offset| offset.dot(&offset));
+        let mut gradient = Array1::<f64>::zeros(x.len());
+        if dists.iter().any(|&dist| dist == 0.0) {
+            return Ok(gradient);
+        }
+        let p = 1.0 / (q - 1.0);
+        let mut memberships = dists.mapv(|dist| dist.powf(-p));
+        memberships /= memberships.sum();
+        for (j, offset) in offsets.outer_iter().enumerate() {
+            gradient.scaled_add(memberships[j] / dists[j], &offset);
+        }
+        gradient.scaled_add(-1.0 / dists[cluster], &offsets.row(cluster));
+        gradient *= 2.0 * p * memberships[cluster];
+        Ok(gradient)
+    }
+
+    /// All clusters a data point belongs to with at least `cutoff` membership
+    ///
+    /// Clusters are sorted by descending membership. Points without any membership of at least
+    /// `cutoff` get an empty list.
+    ///
+    /// # Arguments
+    ///
+    /// * `memberships` - memberships of data points (rows) in clusters (columns)
+    /// * `cutoff`      - minimum membership for a cluster to be reported
+    pub fn multi_labels(memberships: &Array2<f64>, cutoff: f64) -> Vec<Vec<(usize, f64)>> {
+        memberships
+            .outer_iter()
+            .map(|row| {
+                let mut labels: Vec<(usize, f64)> = row
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, u)| **u >= cutoff)
+                    .map(|(j, u)| (j, *u))
+                    .collect();
+                labels.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+                labels
+            })
+            .collect()
+    }
+
+    /// Index of the largest value in every row
+    fn argmax_rows(values: &Array2<f64>) -> Array1<usize> {
+        values.map_axis(Axis(1), |row| {
+            let mut best = 0;
+            for (j, val) in row.iter().enumerate().skip(1) {
+                if *val > row[best] {
+                    best = j;
+                }
+            }
+            best
+        })
+    }
+
+    /// Cluster data using fuzzy c-medians
+    ///
+    /// Like fuzzy k means but with manhattan distances and weighted medians as centers, which makes
+    /// the centers robust against outliers.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`      - number of clusters
+    /// * `n_iter` - upper bound of iteration number
+    /// * `q`      - fuzzifier
+    /// * `data`   - data to cluster (rows are data points)
+    /// * `seed`   - seed for the center initialisation, drawn from entropy if `None`
+    pub fn cluster_fuzzy_c_medians<T>(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &Array2<T>,
+        seed: Option<u64>,
+    ) -> Result<ClusteringResult<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        check_fuzzifier(q)?;
+        let criteria = StopCriteria {
+            max_iter: n_iter,
+            tol: 0.0,
+            max_duration: None,
+            track_convergence: false,
+        };
+        fit_centers(
+            data,
+            k,
+            &InitStrategy::default(),
+            false,
+            &mut seeded_rng(seed),
+            &criteria,
+            &mut |_| true,
+            |x, centers, memberships, deadline| median_step(q, x, centers, memberships, deadline),
+        )
+    }
+
+    /// Result of fuzzy c-shells clustering, see `cluster_fuzzy_c_shells`
+    #[cfg(feature = "advanced-algo")]
+    #[derive(Clone, Debug)]
+    pub struct FCShellsResult<T> {
+        /// Center of every shell (rows)
+        pub centers: Array2<T>,
+        /// Radius of every shell
+        pub radii: Array1<T>,
+        /// Memberships of data points (rows) in shells (columns)
+        pub memberships: Array2<f64>,
+        /// Shell with the largest membership for every data point
+        pub labels: Array1<usize>,
+        /// Sum of the squared distances of all data points to every shell, weighted with their
+        /// memberships to the power of the fuzzifier
+        pub objective: f64,
+        /// Number of iterations run
+        pub n_iter: usize,
+    }
+
+    /// Distance of a point to the shell of a circle or sphere, `|d(x, v) - r|`
+    ///
+    /// # Arguments
+    ///
+    /// * `point`  - the point
+    /// * `center` - center of the shell
+    /// * `radius` - radius of the shell
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::dist_to_shell;
+    /// use ndarray::array;
+    ///
+    /// let center = array![1.0, 1.0];
+    /// assert_eq!(dist_to_shell(array![4.0, 5.0].view(), center.view(), 2.0), 3.0);
+    /// assert_eq!(dist_to_shell(center.view(), center.view(), 2.0), 2.0);
+    /// ```
+    #[cfg(feature = "advanced-algo")]
+    pub fn dist_to_shell(point: ArrayView1<f64>, center: ArrayView1<f64>, radius: f64) -> f64 {
+        (Euclidean.distance(point, center) - radius).abs()
+    }
+
+    /// Cluster data into rings or spherical shells using fuzzy c-shells
+    ///
+    /// Every cluster is a shell with a center `v_k` and a radius `r_k` instead of a point, so
+    /// clusters can be rings around a common center that fuzzy k means can't tell apart. The
+    /// memberships follow from the squared distances `(d(x_i, v_k) - r_k)²` of the data points
+    /// to the shells like in fuzzy k means. Every iteration then sets each radius to the
+    /// weighted mean distance of the data points to the center and moves the center to
+    /// `Σ_i u_ik^q (x_i - r_k (x_i - v_k) / d(x_i, v_k)) / Σ_i u_ik^q`, the fixed point
+    /// condition of the objective.
+    ///
+    /// The centers start at random data points and the radii at the mean distance of the data
+    /// points to them, weighted with fuzzy k means memberships. Like all shell clustering, the
+    /// result depends on this start; keep the result with the lowest objective over several
+    /// seeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`      - number of shells
+    /// * `n_iter` - upper bound of iteration number
+    /// * `q`      - fuzzifier
+    /// * `data`   - data to cluster (rows are data points)
+    /// * `seed`   - seed for the center initialisation, drawn from entropy if `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::cluster_fuzzy_c_shells;
+    /// use ndarray::Array2;
+    ///
+    /// // two rings around the origin with radii 1 and 4
+    /// let data = Array2::from_shape_fn((80, 2), |(i, j)| {
+    ///     let radius = if i < 40 { 1.0 } else { 4.0 };
+    ///     let angle = i as f64 * std::f64::consts::PI / 20.0;
+    ///     radius * if j == 0 { angle.cos() } else { angle.sin() }
+    /// });
+    /// // keep the best of a few starts
+    /// let result = (0..8)
+    ///     .map(|seed| cluster_fuzzy_c_shells(2, 200, 2.0, &data, Some(seed)).unwrap())
+    ///     .min_by(|a, b| a.objective.partial_cmp(&b.objective).unwrap())
+    ///     .unwrap();
+    /// let mut radii = result.radii.to_vec();
+    /// radii.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    /// assert!((radii[0] - 1.0).abs() < 1e-3 && (radii[1] - 4.0).abs() < 1e-3);
+    /// assert!(result.centers.iter().all(|coord| coord.abs() < 1e-3));
+    /// ```
+    #[cfg(feature = "advanced-algo")]
+    pub fn cluster_fuzzy_c_shells<T>(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &Array2<T>,
+        seed: Option<u64>,
+    ) -> Result<FCShellsResult<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        check_fuzzifier(q)?;
+        let (n_data, n_features) = data.dim();
+        if n_data == 0 {
+            return Err(AlgoError::EmptyData);
+        }
+        if k == 0 {
+            return Err(AlgoError::NoClusters);
+        }
+        if k > n_data {
+            return Err(AlgoError::TooManyClusters { k, n: n_data });
+        }
+        let x = data.mapv(f64::from);
+
+        let mut centers = initial_centers(&InitStrategy::default(), &x, k, &mut seeded_rng(seed));
+        let mut center_dists = metric_dist_matrix(x.view(), &centers, &Euclidean);
+        let mut memberships = center_dists.mapv(|d| d * d);
+        memberships_from_dissimilarities(q, &mut memberships);
+        // a shell without weight, whose memberships all vanish to the power of q, keeps its
+        // previous radius
+        let weighted_mean_dists =
+            |memberships: &Array2<f64>, center_dists: &Array2<f64>, previous: &Array1<f64>| {
+                let weights = memberships.mapv(|u| u.powf(q));
+                let weight_sums = weights.sum_axis(Axis(0));
+                let mut radii = (&weights * center_dists).sum_axis(Axis(0));
+                ndarray::Zip::from(&mut radii)
+                    .and(&weight_sums)
+                    .and(previous)
+                    .for_each(|radius, &weight_sum, &prev| {
+                        *radius = if weight_sum > 0.0 {
+                            *radius / weight_sum
+                        } else {
+                            prev
+                        };
+                    });
+                radii
+            };
+        let mut radii = weighted_mean_dists(
+            &memberships,
+            &center_dists,
+            &center_dists.mean_axis(Axis(0)).unwrap(),
+        );
+
+        let mut objective = f64::INFINITY;
+        let mut iterations = 0;
+        while iterations < n_iter {
+            let shell_dists = (&center_dists - &radii).mapv(|d| d * d);
+            memberships.assign(&shell_dists);
+            memberships_from_dissimilarities(q, &mut memberships);
+            let weights = memberships.mapv(|u| u.powf(q));
+            objective = (&weights * &shell_dists).sum();
+            if !objective.is_finite() {
+                return Err(AlgoError::NumericalFailure("objective is not finite"));
+            }
+
+            radii = weighted_mean_dists(&memberships, &center_dists, &radii);
+            let mut new_centers = Array2::<f64>::zeros((k, n_features));
+            for (j, mut center) in new_centers.outer_iter_mut().enumerate() {
+                for (i, point) in x.outer_iter().enumerate() {
+                    let dist = center_dists[[i, j]];
+                    // a point on the center pulls in no particular direction
+                    let pull = if dist > 0.0 { radii[j] / dist } else { 0.0 };
+                    let target = &point - &((&point - &centers.row(j)) * pull);
+                    center.scaled_add(weights[[i, j]], &target);
+                }
+                // a shell without weight keeps its previous center
+                let weight_sum = weights.column(j).sum();
+                if weight_sum > 0.0 {
+                    center /= weight_sum;
+                } else {
+                    center.assign(&centers.row(j));
+                }
+            }
+            iterations += 1;
+            let max_shift = max_center_movement(&centers, &new_centers);
+            centers = new_centers;
+            center_dists = metric_dist_matrix(x.view(), &centers, &Euclidean);
+            if max_shift == 0.0 {
+                break;
+            }
+        }
+        Ok(FCShellsResult {
+            centers: centers.mapv(|val| val.as_()),
+            radii: radii.mapv(|val| val.as_()),
+            labels: argmax_rows(&memberships),
+            memberships,
+            objective,
+            n_iter: iterations,
+        })
+    }
+
+    /// Data points with a positive weight each
+    ///
+    /// A data point with weight 2 counts as much as two copies of it, e.g. for data where
+    /// duplicates were compressed into a single row.
+    #[derive(Clone, Debug)]
+    pub struct WeightedData<T> {
+        /// Data points (rows)
+        pub data: Array2<T>,
+        /// Weight of every data point
+        pub weights: Array1<f64>,
+    }
+
+    impl<T> WeightedData<T> {
+        /// Pair data points with their weights
+        ///
+        /// # Arguments
+        ///
+        /// * `data`    - data points (rows)
+        /// * `weights` - weight of every data point
+        ///
+        /// # Errors
+        ///
+        /// `DimensionMismatch` if there isn't exactly one weight per data point, `InvalidWeight`
+        /// for the first weight that is not finite and positive
+        pub fn new(data: Array2<T>, weights: Array1<f64>) -> Result<Self, AlgoError> {
+            if weights.len() != data.nrows() {
+                return Err(AlgoError::DimensionMismatch {
+                    expected: (data.nrows(), 1),
+                    got: (weights.len(), 1),
+                });
+            }
+            if let Some(&weight) = weights.iter().find(|&&w| !(w > 0.0 && w.is_finite())) {
+                return Err(AlgoError::InvalidWeight(weight));
+            }
+            Ok(WeightedData { data, weights })
+        }
+
+        /// Give every data point weight 1
+        ///
+        /// # Arguments
+        ///
+        /// * `data` - data points (rows)
+        pub fn uniform(data: Array2<T>) -> Self {
+            let weights = Array1::ones(data.nrows());
+            WeightedData { data, weights }
+        }
+    }
+
+    /// Cluster weighted data using fuzzy k means
+    ///
+    /// Centers are the means `v_k = Σ_i w_i u_ik^q x_i / Σ_i w_i u_ik^q` and the objective is
+    /// weighted the same way. Memberships don't depend on the weights. With all weights 1 this is
+    /// the same as the unweighted fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`      - number of clusters
+    /// * `n_iter` - upper bound of iteration number
+    /// * `q`      - fuzzifier
+    /// * `data`   - weighted data to cluster
+    /// * `seed`   - seed for the center initialisation, drawn from entropy if `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::{
+    ///     cluster_k_means_fuzzy_weighted, ClusteringAlgorithm, FuzzyKMeansConfig, WeightedData,
+    /// };
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.1], [0.2, 0.0], [0.9, 1.0], [1.0, 0.8], [0.1, 0.1]];
+    /// let weighted =
+    ///     cluster_k_means_fuzzy_weighted(2, 20, 2.0, &WeightedData::uniform(data.clone()), Some(3))
+    ///         .unwrap();
+    ///
+    /// let mut config = FuzzyKMeansConfig::new(2, 20, 2.0);
+    /// config.seed = Some(3);
+    /// let unweighted = ClusteringAlgorithm::<f64>::fit(&config, &data).unwrap();
+    /// assert_eq!(weighted.centers, unweighted.centers);
+    /// assert_eq!(weighted.memberships, unweighted.memberships);
+    /// ```
+    pub fn cluster_k_means_fuzzy_weighted<T>(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &WeightedData<T>,
+        seed: Option<u64>,
+    ) -> Result<ClusteringResult<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        check_fuzzifier(q)?;
+        let criteria = StopCriteria {
+            max_iter: n_iter,
+            tol: 0.0,
+            max_duration: None,
+            track_convergence: false,
+        };
+        fit_centers(
+            &data.data,
+            k,
+            &InitStrategy::default(),
+            false,
+            &mut seeded_rng(seed),
+            &criteria,
+            &mut |_| true,
+            |x, centers, memberships, deadline| {
+                membership_step(
+                    x,
+                    Some(&data.weights),
+                    centers,
+                    &Euclidean,
+                    memberships,
+                    deadline,
+                    |dists| fuzzy_weights(q, dists),
+                )
+            },
+        )
+    }
+
+    /// Cluster data using fuzzy k means, weighing every data point by its local density
+    ///
+    /// Data points in dense regions pull harder on the centers than isolated ones, which keeps
+    /// outliers from dragging centers away. The weights are the Gaussian kernel density estimate
+    /// at every data point, see `kernel::gaussian_density_estimate`, scaled to average 1. Takes
+    /// `O(n²)` time for the density estimate.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`         - number of clusters
+    /// * `n_iter`    - upper bound of iteration number
+    /// * `q`         - fuzzifier
+    /// * `data`      - data to cluster (rows are data points)
+    /// * `bandwidth` - kernel width for the density estimate, if `None` the mean of
+    ///   `kernel::silverman_bandwidth` over all features
+    /// * `seed`      - seed for the center initialisation, drawn from entropy if `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::cluster_k_means_fuzzy_density_weighted;
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 0.1], [0.2, 0.0], [0.1, 0.1], [0.9, 1.0], [1.0, 0.8], [4.0, 4.0]];
+    /// let result = cluster_k_means_fuzzy_density_weighted(2, 20, 2.0, &data, None, Some(3)).unwrap();
+    /// assert_eq!(result.labels[0], result.labels[1]);
+    /// assert_ne!(result.labels[0], result.labels[3]);
+    /// ```
+    pub fn cluster_k_means_fuzzy_density_weighted(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &Array2<f64>,
+        bandwidth: Option<f64>,
+        seed: Option<u64>,
+    ) -> Result<ClusteringResult<f64>, AlgoError> {
+        check_fuzzifier(q)?;
+        if data.nrows() == 0 {
+            return Err(AlgoError::EmptyData);
+        }
+        let bandwidth =
+            bandwidth.unwrap_or_else(|| kernel::silverman_bandwidth(data).mean().unwrap());
+        let density = kernel::gaussian_density_estimate(data, bandwidth)?;
+        let mean_density = density.mean().unwrap();
+        let weights = density / mean_density;
+        if !weights.iter().all(|&w| w > 0.0 && w.is_finite()) {
+            return Err(AlgoError::NumericalFailure(
+                "density estimate vanished, the bandwidth is too small",
+            ));
+        }
+        cluster_k_means_fuzzy_weighted(
+            k,
+            n_iter,
+            q,
+            &WeightedData::new(data.clone(), weights)?,
+            seed,
+        )
+    }
+
+    /// Compute cluster means using fuzzy k means clustering
+    ///
+    /// A single cluster holds every data point entirely, so its center is the mean of the data
+    /// and is returned without iterating.
+    ///
+    /// # Arguments
+    /// `k` - number of clusters
+    /// `n_iter` - number of iterations to perform
+    /// `q` - fuzzifier
+    /// `data` - data to cluster (rows are data points)
+    /// `rng` - random number generator for the center initialisation
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::algo::cluster_k_means_fuzzy;
+    /// use ndarray::{array, Axis};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let data = array![[0.0, 1.0], [2.0, 3.0], [4.0, 8.0], [1.0, 1.0]];
+    /// let centers = cluster_k_means_fuzzy(1, 10, 2.0, &data, &mut StdRng::seed_from_u64(1)).unwrap();
+    /// assert_eq!(centers.row(0), data.mean_axis(Axis(0)).unwrap());
+    /// ```
+    pub fn cluster_k_means_fuzzy<T>(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &Array2<T>,
+        rng: &mut impl Rng,
+    ) -> Result<Array2<T>, AlgoError>
+    where
+        T: Clone + Copy + 'static,
+        f64: From<T> + AsPrimitive<T>,
+    {
+        check_fuzzifier(q)?;
+        if k == 1 {
+            let mean = data
+                .mapv(f64::from)
+                .mean_axis(Axis(0))
+                .ok_or(AlgoError::EmptyData)?;
+            return Ok(mean.mapv(|val| val.as_()).insert_axis(Axis(0)));
+        }
+        let criteria = StopCriteria {
+            max_iter: n_iter,
+            tol: 0.0,
+            max_duration: None,
+            track_convergence: false,
+        };
+        fit_centers(
+            data,
+            k,
+            &InitStrategy::default(),
+            false,
+            rng,
+            &criteria,
+            &mut |_| true,
+            |x, centers, memberships, deadline| {
+                membership_step(
+                    x,
+                    None,
+                    centers,
+                    &Euclidean,
+                    memberships,
+                    deadline,
+                    |dists| fuzzy_weights(q, dists),
+                )
+            },
+        )
+        .map(|result| result.centers)
+    }
+
+    /// Compute fuzzy k means on `f32` data with all arithmetic in `f32`
+    ///
+    /// Same algorithm as `cluster_k_means_fuzzy`, but distances, memberships and sums are never
+    /// widened to `f64`, which halves the memory traffic at the cost of precision. Only the
+    /// memberships and objective of the result are converted to `f64` at the end. Runs serially.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`      - number of clusters
+    /// * `n_iter` - upper bound of iteration number
+    /// * `q`      - fuzzifier
+    /// * `data`   - data to cluster (rows are data points)
+    /// * `rng`    - random number generator for the center initialisation
+    pub fn cluster_k_means_fuzzy_f32(
+        k: usize,
+        n_iter: usize,
+        q: f64,
+        data: &Array2<f32>,
+        rng: &mut impl Rng,
+    ) -> Result<ClusteringResult<f32>, AlgoError> {
+        check_fuzzifier(q)?;
+        let q = q as f32;
+        let (n_data, n_features) = data.dim();
+        if n_data == 0 {
+            return Err(AlgoError::EmptyData);
+        }
+        if k == 0 {
+            return Err(AlgoError::NoClusters);
+        }
+        if k > n_data {
+            return Err(AlgoError::TooManyClusters { k, n: n_data });
+        }
+
+        // draw like the generic path does, so the same rng state gives the same initial centers
+        let min = data.fold_axis(Axis(0), f32::INFINITY, |a, b| a.min(*b));
+        let max = data.fold_axis(Axis(0), f32::NEG_INFINITY, |a, b| a.max(*b));
+        let mut centers = Array2::<f32>::zeros((k, n_features));
+        for mut center in centers.outer_iter_mut() {
+            for ((val, lo), hi) in center.iter_mut().zip(min.iter()).zip(max.iter()) {
+                *val = lo + rng.gen::<f64>() as f32 * (hi - lo);
+            }
+        }
+
+        let mut memberships = Array2::<f32>::zeros((n_data, k));
+        let mut stopped_by = StopReason::MaxIter;
+        let mut iterations = 0;
+        while iterations < n_iter {
+            let (_, new_centers) = fuzzy_step_f32(q, data, &centers, &mut memberships);
+            let max_shift = centers
+                .outer_iter()
+                .zip(new_centers.outer_iter())
+                .map(|(a, b)| (&a - &b).mapv(|val| val * val).sum().sqrt())
+                .fold(0.0, f32::max);
+            centers = new_centers;
+            iterations += 1;
+            if max_shift <= 0.0 {
+                stopped_by = StopReason::Tolerance;
+                break;
+            }
+        }
+
+        let (objective, _) = fuzzy_step_f32(q, data, &centers, &mut memberships);
+        if !objective.is_finite() {
+            return Err(AlgoError::NumericalFailure("objective is not finite"));
+        }
+        let memberships = memberships.mapv(f64::from);
+        Ok(ClusteringResult {
+            centers,
+            labels: argmax_rows(&memberships),
+            memberships,
+            objective: f64::from(objective),
+            n_iter: iterations,
+            converged: stopped_by == StopReason::Tolerance,
+            stopped_by,
+            trimmed: Array1::from_elem(n_data, false),
+            center_movement_history: Vec::new(),
+        })
+    }
+
+    /// One fuzzy k means iteration in `f32`, returning the objective and the updated centers
+    ///
+    /// Sums are accumulated per chunk of `CHUNK_ROWS` data points and the chunk sums added up
+    /// afterwards, which keeps the rounding error from growing with the number of data points as
+    /// fast as a single running sum would.
+    ///
+    /// # Arguments
+    ///
+    /// * `q`           - fuzzifier
+    /// * `x`           - data points (rows)
+    /// * `centers`     - current cluster centers (rows)
+    /// * `memberships` - overwritten with the memberships for `centers`
+    fn fuzzy_step_f32(
+        q: f32,
+        x: &Array2<f32>,
+        centers: &Array2<f32>,
+        memberships: &mut Array2<f32>,
+    ) -> (f32, Array2<f32>) {
+        let mut objective = 0.0;
+        let mut weighted_sum = Array2::<f32>::zeros(centers.dim());
+        let mut weight_sum = Array1::<f32>::zeros(centers.nrows());
+        let mut chunk_objective = 0.0;
+        let mut chunk_weighted_sum = Array2::<f32>::zeros(centers.dim());
+        let mut chunk_weight_sum = Array1::<f32>::zeros(centers.nrows());
+        let mut dists = Array1::<f32>::zeros(centers.nrows());
+        for (i, (point, mut point_memberships)) in
+            x.outer_iter().zip(memberships.outer_iter_mut()).enumerate()
+        {
+            for (dist, center) in dists.iter_mut().zip(centers.outer_iter()) {
+                *dist = point
+                    .iter()
+                    .zip(center.iter())
+                    .map(|(a, b)| (a - b).powi(2))
+                    .sum();
+            }
+
+            let n_zero = dists.iter().filter(|&&d| d == 0.0).count();
+            if n_zero > 0 {
+                let share = 1.0 / n_zero as f32;
+                point_memberships
+                    .zip_mut_with(&dists, |u, &d| *u = if d == 0.0 { share } else { 0.0 });
+            } else {
+                point_memberships.zip_mut_with(&dists, |u, &d| *u = d.powf(1.0 / (1.0 - q)));
+                let sum = point_memberships.sum();
+                point_memberships.mapv_inplace(|val| val / sum);
+            }
+
+            for (c, (u, dist)) in point_memberships.iter().zip(dists.iter()).enumerate() {
+                let weight = u.powf(q);
+                chunk_objective += weight * dist;
+                chunk_weight_sum[c] += weight;
+                chunk_weighted_sum.row_mut(c).scaled_add(weight, &point);
+            }
+
+            if (i + 1) % CHUNK_ROWS == 0 || i + 1 == x.nrows() {
+                objective += chunk_objective;
+                weight_sum += &chunk_weight_sum;
+                weighted_sum += &chunk_weighted_sum;
+                chunk_objective = 0.0;
+                chunk_weight_sum.fill(0.0);
+                chunk_weighted_sum.fill(0.0);
+            }
+        }
+
+        // clusters without any weight keep their previous center
+        for ((mut center, weight), prev_center) in weighted_sum
+            .outer_iter_mut()
+            .zip(weight_sum.iter())
+            .zip(centers.outer_iter())
+        {
+            if *weight > 0.0 {
+                center.mapv_inplace(|val| val / weight);
+            } else {
+                center.assign(&prev_center);
+            }
+        }
+        (objective, weighted_sum)
+    }
+
+    /// Kernel functions for clustering in an implicit feature space
+    pub mod kernel {
+        use super::{check_bandwidth, AlgoError};
+        use ndarray::{Array1, Array2, Axis};
+
+        /// Gaussian (RBF) kernel matrix `K[i, j] = exp(-||x_i - x_j||² / (2σ²))`
+        ///
+        /// # Arguments
+        ///
+        /// * `data`  - data points (rows)
+        /// * `sigma` - kernel width
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::kernel::rbf_kernel_matrix;
+        /// use ndarray::array;
+        ///
+        /// let kernel = rbf_kernel_matrix(&array![[0.0, 0.0], [1.0, 2.0], [-3.0, 0.5]], 1.5).unwrap();
+        /// assert!(kernel.diag().iter().all(|&val| val == 1.0));
+        /// assert_eq!(kernel, kernel.t());
+        /// ```
+        ///
+        /// # Errors
+        ///
+        /// `InvalidBandwidth` if `sigma` is not finite and positive
+        pub fn rbf_kernel_matrix(data: &Array2<f64>, sigma: f64) -> Result<Array2<f64>, AlgoError> {
+            check_bandwidth(sigma)?;
+            let n = data.nrows();
+            let scale = 2.0 * sigma * sigma;
+            let mut out = Array2::<f64>::zeros((n, n));
+            for i in 0..n {
+                out[[i, i]] = 1.0;
+                for j in i + 1..n {
+                    let dist_sq: f64 = data
+                        .row(i)
+                        .iter()
+                        .zip(data.row(j).iter())
+                        .map(|(a, b)| (a - b) * (a - b))
+                        .sum();
+                    let val = (-dist_sq / scale).exp();
+                    out[[i, j]] = val;
+                    out[[j, i]] = val;
+                }
+            }
+            Ok(out)
+        }
+
+        /// Polynomial kernel matrix `K[i, j] = (x_i · x_j + coef)^degree`
+        ///
+        /// # Arguments
+        ///
+        /// * `data`   - data points (rows)
+        /// * `degree` - degree of the polynomial
+        /// * `coef`   - constant added to the dot product
+        pub fn polynomial_kernel_matrix(data: &Array2<f64>, degree: u32, coef: f64) -> Array2<f64> {
+            data.dot(&data.t())
+                .mapv(|dot| (dot + coef).powi(degree as i32))
+        }
+
+        /// Gaussian kernel density estimate at every data point
+        ///
+        /// `f(x_i) = 1 / (n (2πh²)^(d/2)) Σ_j exp(-||x_i - x_j||² / (2h²))`, where the sum
+        /// includes the data point itself. Takes `O(n²)` time.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`      - data points (rows)
+        /// * `bandwidth` - kernel width `h`, shared by all features
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::kernel::gaussian_density_estimate;
+        /// use ndarray::array;
+        ///
+        /// let data = array![[0.0], [0.1], [0.2], [5.0]];
+        /// let density = gaussian_density_estimate(&data, 0.5).unwrap();
+        /// assert!(density[1] > density[0] && density[0] > density[3]);
+        /// ```
+        ///
+        /// # Errors
+        ///
+        /// `InvalidBandwidth` if `bandwidth` is not finite and positive
+        pub fn gaussian_density_estimate(
+            data: &Array2<f64>,
+            bandwidth: f64,
+        ) -> Result<Array1<f64>, AlgoError> {
+            check_bandwidth(bandwidth)?;
+            let n = data.nrows();
+            let scale = 2.0 * bandwidth * bandwidth;
+            let mut sums = Array1::<f64>::ones(n);
+            for i in 0..n {
+                for j in i + 1..n {
+                    let dist_sq: f64 = data
+                        .row(i)
+                        .iter()
+                        .zip(data.row(j).iter())
+                        .map(|(a, b)| (a - b) * (a - b))
+                        .sum();
+                    let val = (-dist_sq / scale).exp();
+                    sums[i] += val;
+                    sums[j] += val;
+                }
+            }
+            let norm = n as f64 * (std::f64::consts::PI * scale).powf(data.ncols() as f64 / 2.0);
+            Ok(sums / norm)
+        }
+
+        /// Kernel bandwidth per feature by Silverman's rule of thumb `h = 1.06 σ n^(-1/5)`
+        ///
+        /// Uses the sample standard deviation `σ` of every feature, constant features get
+        /// bandwidth 0.
+        ///
+        /// # Arguments
+        ///
+        /// * `data` - data points (rows)
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::kernel::silverman_bandwidth;
+        /// use ndarray::array;
+        ///
+        /// let bandwidth = silverman_bandwidth(&array![[0.0, 1.0], [2.0, 1.0]]);
+        /// assert_eq!(bandwidth[1], 0.0);
+        /// assert!((bandwidth[0] - 1.06 * 2f64.sqrt() * 2f64.powf(-0.2)).abs() < 1e-12);
+        /// ```
+        pub fn silverman_bandwidth(data: &Array2<f64>) -> Array1<f64> {
+            let n = data.nrows();
+            let ddof = if n > 1 { 1.0 } else { 0.0 };
+            data.std_axis(Axis(0), ddof) * 1.06 * (n as f64).powf(-0.2)
+        }
+    }
+
+    /// Measures for judging the quality of a clustering
+    pub mod metrics {
+        use super::{AlgoError, DistanceMetric, Euclidean};
+        use ndarray::{Array1, Array2, Axis};
+
+        /// Count how often each ground-truth class was assigned to each cluster
+        ///
+        /// Rows of the result are ground-truth classes, columns are predicted clusters.
+        ///
+        /// # Arguments
+        ///
+        /// * `labels_true` - ground-truth class per data point
+        /// * `labels_pred` - predicted cluster per data point
+        /// * `n_true`      - number of ground-truth classes
+        /// * `n_pred`      - number of predicted clusters
+        ///
+        /// # Errors
+        ///
+        /// `DimensionMismatch` if the label arrays differ in length, `LabelOutOfRange` for a
+        /// ground-truth label `>= n_true` or a predicted label `>= n_pred`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::confusion_matrix;
+        /// use ndarray::array;
+        ///
+        /// let cm = confusion_matrix(&array![0, 0, 1], &array![1, 1, 0], 2, 2).unwrap();
+        /// assert_eq!(cm, array![[0, 2], [1, 0]]);
+        /// assert!(confusion_matrix(&array![0, 2], &array![0, 1], 2, 2).is_err());
+        /// ```
+        pub fn confusion_matrix(
+            labels_true: &Array1<usize>,
+            labels_pred: &Array1<usize>,
+            n_true: usize,
+            n_pred: usize,
+        ) -> Result<Array2<usize>, AlgoError> {
+            if labels_true.len() != labels_pred.len() {
+                return Err(AlgoError::DimensionMismatch {
+                    expected: (labels_true.len(), 1),
+                    got: (labels_pred.len(), 1),
+                });
+            }
+            let mut cm = Array2::<usize>::zeros((n_true, n_pred));
+            for (&t, &p) in labels_true.iter().zip(labels_pred.iter()) {
+                if t >= n_true {
+                    return Err(AlgoError::LabelOutOfRange {
+                        label: t,
+                        n_labels: n_true,
+                    });
+                }
+                if p >= n_pred {
+                    return Err(AlgoError::LabelOutOfRange {
+                        label: p,
+                        n_labels: n_pred,
+                    });
+                }
+                cm[[t, p]] += 1;
+            }
+            Ok(cm)
+        }
+
+        /// Fuzzy partition coefficient `FPC = (1/n) Σ_i Σ_k u_ik²`
+        ///
+        /// Ranges from `1/k` (all memberships equal) to 1 (crisp partition).
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        pub fn partition_coefficient(memberships: &Array2<f64>) -> f64 {
+            memberships.mapv(|u| u * u).sum() / memberships.nrows() as f64
+        }
+
+        /// Bezdek's partition coefficient `PC = (1/n) Σ_i Σ_k u_ik²`
+        ///
+        /// The same quantity as `partition_coefficient` (FPC) under the name used in Bezdek and
+        /// Pal's work on cluster validity. Some texts normalize it by `k`, see
+        /// `modified_partition_coefficient` for that variant.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::{bezdek_partition_coefficient, partition_coefficient};
+        /// use ndarray::array;
+        ///
+        /// let memberships = array![[1.0, 0.0], [0.5, 0.5]];
+        /// assert_eq!(bezdek_partition_coefficient(&memberships), 0.75);
+        /// assert_eq!(
+        ///     bezdek_partition_coefficient(&memberships),
+        ///     partition_coefficient(&memberships)
+        /// );
+        /// ```
+        pub fn bezdek_partition_coefficient(memberships: &Array2<f64>) -> f64 {
+            partition_coefficient(memberships)
+        }
+
+        /// Modified partition coefficient `MPC = 1 - k/(k-1) (1 - PC)`
+        ///
+        /// The partition coefficient drops towards `1/k` as `k` grows, even for good partitions.
+        /// MPC rescales it to range from 0 (all memberships equal) to 1 (crisp partition) for
+        /// every `k`, so values for different numbers of clusters can be compared. A single
+        /// cluster holds every point fully, so MPC is 1 for fewer than 2 clusters.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        /// * `k`           - number of clusters
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::modified_partition_coefficient;
+        /// use ndarray::array;
+        ///
+        /// let crisp = array![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+        /// assert_eq!(modified_partition_coefficient(&crisp, 3), 1.0);
+        /// let uniform = array![[0.25, 0.25, 0.25, 0.25], [0.25, 0.25, 0.25, 0.25]];
+        /// assert_eq!(modified_partition_coefficient(&uniform, 4), 0.0);
+        /// ```
+        pub fn modified_partition_coefficient(memberships: &Array2<f64>, k: usize) -> f64 {
+            // k/(k-1) is undefined, the partition is crisp
+            if k < 2 {
+                return 1.0;
+            }
+            let k = k as f64;
+            1.0 - k / (k - 1.0) * (1.0 - partition_coefficient(memberships))
+        }
+
+        /// Effective size of every cluster, the sum of the memberships of all data points in it
+        ///
+        /// Unlike counting hard assignments, a point with memberships 0.51 and 0.49 adds about
+        /// half a point to both clusters.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::soft_counts;
+        /// use ndarray::array;
+        ///
+        /// let memberships = array![[0.51, 0.49], [0.9, 0.1], [0.2, 0.8]];
+        /// let counts = soft_counts(&memberships);
+        /// assert!((counts[0] - 1.61).abs() < 1e-12);
+        /// assert!((counts.sum() - memberships.nrows() as f64).abs() < 1e-12);
+        /// ```
+        pub fn soft_counts(memberships: &Array2<f64>) -> Array1<f64> {
+            memberships.sum_axis(Axis(0))
+        }
+
+        /// Entropy of how every cluster's membership is spread over the data points
+        ///
+        /// `H_k = -Σ_i p_ik ln p_ik` with `p_ik = u_ik / Σ_j u_jk`. It is 0 if a single data point
+        /// holds all of the cluster's membership and `ln(n)` if all `n` points hold the same, so
+        /// high values mark diffuse clusters. Clusters without any membership get 0.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::cluster_membership_entropy;
+        /// use ndarray::array;
+        ///
+        /// let memberships = array![[0.5, 1.0], [0.5, 0.0], [0.5, 0.0], [0.5, 0.0]];
+        /// let entropy = cluster_membership_entropy(&memberships);
+        /// assert!((entropy[0] - 4f64.ln()).abs() < 1e-12);
+        /// assert_eq!(entropy[1], 0.0);
+        /// ```
+        pub fn cluster_membership_entropy(memberships: &Array2<f64>) -> Array1<f64> {
+            let totals = soft_counts(memberships);
+            Array1::from_iter(memberships.axis_iter(Axis(1)).zip(totals.iter()).map(
+                |(column, &total)| {
+                    if total <= 0.0 {
+                        return 0.0;
+                    }
+                    -column
+                        .iter()
+                        .map(|&u| u / total)
+                        .filter(|&p| p > 0.0)
+                        .map(|p| p * p.ln())
+                        .sum::<f64>()
+                },
+            ))
+        }
+
+        /// Number of data points assigned to every cluster
+        ///
+        /// # Arguments
+        ///
+        /// * `labels` - cluster of every data point
+        /// * `k`      - number of clusters
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` for a label `>= k`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::cluster_sizes;
+        /// use ndarray::array;
+        ///
+        /// assert_eq!(cluster_sizes(&array![0, 2, 2, 0, 2], 4).unwrap(), array![2, 0, 3, 0]);
+        /// assert!(cluster_sizes(&array![0, 4], 4).is_err());
+        /// ```
+        pub fn cluster_sizes(labels: &Array1<usize>, k: usize) -> Result<Array1<usize>, AlgoError> {
+            let mut sizes = Array1::zeros(k);
+            for &label in labels.iter() {
+                if label >= k {
+                    return Err(AlgoError::LabelOutOfRange { label, n_labels: k });
+                }
+                sizes[label] += 1;
+            }
+            Ok(sizes)
+        }
+
+        /// Mean distance of the data points assigned to every cluster to its center
+        ///
+        /// Clusters without data points get NaN.
+        ///
+        /// # Arguments
+        ///
+        /// * `dists`  - distance of every data point to the center of its cluster, see
+        ///   `assign_labels_with_dist`
+        /// * `labels` - cluster of every data point
+        /// * `k`      - number of clusters
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` for a label `>= k`
+        pub fn mean_distance_to_center(
+            dists: &Array1<f64>,
+            labels: &Array1<usize>,
+            k: usize,
+        ) -> Result<Array1<f64>, AlgoError> {
+            let sizes = cluster_sizes(labels, k)?;
+            let mut sums = Array1::<f64>::zeros(k);
+            for (&label, &dist) in labels.iter().zip(dists.iter()) {
+                sums[label] += dist;
+            }
+            Ok(Array1::from_iter(
+                sums.iter()
+                    .zip(sizes.iter())
+                    .map(|(&sum, &size)| sum / size as f64),
+            ))
+        }
+
+        /// Number of clusters whose soft count exceeds `threshold`
+        ///
+        /// Clusters that hardly hold any membership don't describe any data, so this is the
+        /// number of clusters the data actually uses.
+        ///
+        /// # Arguments
+        ///
+        /// * `soft_counts` - effective size per cluster, see `soft_counts`
+        /// * `threshold`   - smallest soft count a cluster needs to be counted
+        pub fn effective_k(soft_counts: &Array1<f64>, threshold: f64) -> usize {
+            soft_counts
+                .iter()
+                .filter(|&&count| count > threshold)
+                .count()
+        }
+
+        /// Soft overlap between every pair of fuzzy clusters
+        ///
+        /// Entry `(j, l)` is `Σ_i min(u_ij, u_il) / Σ_i max(u_ij, u_il)`, a Jaccard index of the
+        /// membership columns. Values near 1 mean the two clusters cover the same data points,
+        /// which hints at too many clusters. The matrix is symmetric with ones on the diagonal;
+        /// pairs of clusters without any membership get 0.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::{compute_memberships, metrics::max_cluster_overlap};
+        /// use ndarray::{array, Array2};
+        ///
+        /// let data = array![[0.0], [0.5], [1.0], [4.0], [4.5], [5.0]];
+        /// let centers = array![[0.5], [4.5]];
+        /// let overlap = |q| {
+        ///     let mut memberships = Array2::<f64>::zeros((6, 2));
+        ///     compute_memberships(q, &data, &centers, &mut memberships).unwrap();
+        ///     max_cluster_overlap(&memberships).2
+        /// };
+        /// assert!(overlap(4.0) > overlap(1.5));
+        /// ```
+        pub fn cluster_overlap_matrix(memberships: &Array2<f64>) -> Array2<f64> {
+            let k = memberships.ncols();
+            let mut overlap = Array2::<f64>::zeros((k, k));
+            for j in 0..k {
+                for l in j..k {
+                    let (mut min_sum, mut max_sum) = (0.0, 0.0);
+                    for (u_j, u_l) in memberships.column(j).iter().zip(memberships.column(l)) {
+                        min_sum += u_j.min(*u_l);
+                        max_sum += u_j.max(*u_l);
+                    }
+                    let val = if max_sum > 0.0 {
+                        min_sum / max_sum
+                    } else {
+                        0.0
+                    };
+                    overlap[[j, l]] = val;
+                    overlap[[l, j]] = val;
+                }
+            }
+            overlap
+        }
+
+        /// The two distinct clusters with the largest soft overlap and their overlap
+        ///
+        /// See `cluster_overlap_matrix`. Fewer than two clusters give `(0, 0, NaN)`.
+        ///
+        /// # Arguments
+        ///
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        pub fn max_cluster_overlap(memberships: &Array2<f64>) -> (usize, usize, f64) {
+            let overlap = cluster_overlap_matrix(memberships);
+            let mut best = (0, 0, f64::NAN);
+            for ((j, l), &val) in overlap.indexed_iter() {
+                if j < l && (best.2.is_nan() || val > best.2) {
+                    best = (j, l, val);
+                }
+            }
+            best
+        }
+
+        /// Fuzzy Dunn index of a fuzzy clustering, higher is better
+        ///
+        /// The classical Dunn index divides the smallest distance between two clusters by the
+        /// largest cluster diameter, both taken over hard assignments, so a single outlier can
+        /// dominate it. This version uses the memberships instead: the distance between two
+        /// clusters is the distance between their centers, which are membership-weighted means,
+        /// and the diameter of cluster `k` is the weighted mean distance of the data points to
+        /// its center, `Σ_i u_ik^q d(x_i, v_k) / Σ_i u_ik^q`. Normalizing by the weights keeps
+        /// the index comparable between data sets of different size. Returns NaN for fewer than
+        /// two clusters.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `centers`     - cluster centers (rows)
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        /// * `q`           - fuzzifier the memberships were computed with
+        /// * `metric`      - distance between data points and centers
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::{metrics::fuzzy_dunn_index, Euclidean};
+        /// use ndarray::array;
+        ///
+        /// let memberships = array![[0.9, 0.1], [0.9, 0.1], [0.1, 0.9], [0.1, 0.9]];
+        /// let centers = array![[0.0], [10.0]];
+        /// let tight = array![[-1.0], [1.0], [9.0], [11.0]];
+        /// let loose = array![[-4.0], [4.0], [6.0], [14.0]];
+        /// let dunn = |data| fuzzy_dunn_index(data, &centers, &memberships, 2.0, &Euclidean);
+        /// assert!(dunn(&tight) > dunn(&loose));
+        /// ```
+        pub fn fuzzy_dunn_index<T>(
+            data: &Array2<T>,
+            centers: &Array2<T>,
+            memberships: &Array2<f64>,
+            q: f64,
+            metric: &dyn DistanceMetric,
+        ) -> f64
+        where
+            T: Clone + Copy,
+            f64: From<T>,
+        {
+            let k = centers.nrows();
+            if k < 2 {
+                return f64::NAN;
+            }
+            let x = data.mapv(f64::from);
+            let centers = centers.mapv(f64::from);
+
+            let mut max_diameter: f64 = 0.0;
+            for (center, cluster_memberships) in centers.outer_iter().zip(memberships.columns()) {
+                let (mut weighted_dist, mut weight_sum) = (0.0, 0.0);
+                for (point, u) in x.outer_iter().zip(cluster_memberships.iter()) {
+                    let weight = u.powf(q);
+                    weighted_dist += weight * metric.distance(point, center);
+                    weight_sum += weight;
+                }
+                if weight_sum > 0.0 {
+                    max_diameter = max_diameter.max(weighted_dist / weight_sum);
+                }
+            }
+
+            let mut min_separation = f64::INFINITY;
+            for i in 0..k {
+                for j in i + 1..k {
+                    min_separation =
+                        min_separation.min(metric.distance(centers.row(i), centers.row(j)));
+                }
+            }
+            min_separation / max_diameter
+        }
+
+        /// Xie–Beni index of a fuzzy clustering, lower is better
+        ///
+        /// The membership-weighted squared distance of the data points to the centers,
+        /// `Σ_i Σ_j u_ij^q |x_i - v_j|²`, divided by `n` times the smallest squared distance
+        /// between two centers. Compact, well separated clusters give small values. A single
+        /// cluster has no separation, so fewer than two clusters give infinity, the worst value.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `centers`     - cluster centers (rows)
+        /// * `memberships` - memberships of data points (rows) in clusters (columns)
+        /// * `q`           - fuzzifier the memberships were computed with
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::xie_beni_index;
+        /// use ndarray::array;
+        ///
+        /// let data = array![[0.0], [2.0], [10.0]];
+        /// let centers = array![[1.0], [10.0]];
+        /// let memberships = array![[1.0, 0.0], [1.0, 0.0], [0.0, 1.0]];
+        /// // (1 + 1 + 0) / (3 * 81)
+        /// assert_eq!(xie_beni_index(&data, &centers, &memberships, 2.0), 2.0 / 243.0);
+        /// ```
+        pub fn xie_beni_index(
+            data: &Array2<f64>,
+            centers: &Array2<f64>,
+            memberships: &Array2<f64>,
+            q: f64,
+        ) -> f64 {
+            let k = centers.nrows();
+            if k < 2 {
+                return f64::INFINITY;
+            }
+            let mut compactness = 0.0;
+            for (point, point_memberships) in data.outer_iter().zip(memberships.outer_iter()) {
+                for (center, u) in centers.outer_iter().zip(point_memberships.iter()) {
+                    let diff = &point - &center;
+                    compactness += u.powf(q) * diff.dot(&diff);
+                }
+            }
+            let mut min_separation = f64::INFINITY;
+            for i in 0..k {
+                for j in i + 1..k {
+                    let diff = &centers.row(i) - &centers.row(j);
+                    min_separation = min_separation.min(diff.dot(&diff));
+                }
+            }
+            compactness / (data.nrows() as f64 * min_separation)
+        }
+
+        /// Davies–Bouldin index of a hard clustering using euclidean distance, lower is better
+        ///
+        /// For every cluster, the largest ratio `(s_i + s_j) / d(v_i, v_j)` to any other cluster,
+        /// where `s_i` is the mean distance of the points assigned to cluster `i` to its center,
+        /// averaged over all clusters. Empty clusters are left out. Returns NaN if fewer than two
+        /// clusters are populated.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`    - data points (rows)
+        /// * `centers` - cluster centers (rows)
+        /// * `labels`  - cluster per data point
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` for a label that isn't a row of `centers`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::davies_bouldin_index;
+        /// use ndarray::array;
+        ///
+        /// let data = array![[0.0], [2.0], [9.0], [11.0]];
+        /// let centers = array![[1.0], [10.0]];
+        /// // both clusters have a spread of 1 and are 9 apart
+        /// let index = davies_bouldin_index(&data, &centers, &array![0, 0, 1, 1]).unwrap();
+        /// assert!((index - 2.0 / 9.0).abs() < 1e-12);
+        /// ```
+        pub fn davies_bouldin_index(
+            data: &Array2<f64>,
+            centers: &Array2<f64>,
+            labels: &Array1<usize>,
+        ) -> Result<f64, AlgoError> {
+            let k = centers.nrows();
+            let mut spread = vec![0.0; k];
+            let sizes = cluster_sizes(labels, k)?;
+            for (point, &label) in data.outer_iter().zip(labels.iter()) {
+                spread[label] += Euclidean.distance(point, centers.row(label));
+            }
+            let populated: Vec<usize> = (0..k).filter(|&j| sizes[j] > 0).collect();
+            if populated.len() < 2 {
+                return Ok(f64::NAN);
+            }
+            for &j in &populated {
+                spread[j] /= sizes[j] as f64;
+            }
+            let total: f64 = populated
+                .iter()
+                .map(|&i| {
+                    populated
+                        .iter()
+                        .filter(|&&j| j != i)
+                        .map(|&j| {
+                            (spread[i] + spread[j])
+                                / Euclidean.distance(centers.row(i), centers.row(j))
+                        })
+                        .fold(f64::NEG_INFINITY, f64::max)
+                })
+                .sum();
+            Ok(total / populated.len() as f64)
+        }
+
+        /// Calinski–Harabasz index of a hard clustering, higher is better
+        ///
+        /// The ratio of the spread between clusters to the spread within clusters, each divided
+        /// by its degrees of freedom: `(B / (k - 1)) / (W / (n - k))` with
+        /// `B = Σ_j n_j |v_j - m|²` for the data mean `m` and `W = Σ_i |x_i - v_(l_i)|²`. The
+        /// given centers are used as they are, for fuzzy centers this differs slightly from the
+        /// textbook index on cluster means. Returns NaN for fewer than two clusters or no more
+        /// data points than clusters.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`    - data points (rows)
+        /// * `centers` - cluster centers (rows)
+        /// * `labels`  - cluster per data point
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` for a label that isn't a row of `centers`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::metrics::calinski_harabasz_index;
+        /// use ndarray::array;
+        ///
+        /// let data = array![[0.0], [2.0], [9.0], [11.0]];
+        /// let centers = array![[1.0], [10.0]];
+        /// // B = 2 * 4.5² + 2 * 4.5², W = 4, with 1 and 2 degrees of freedom
+        /// let index = calinski_harabasz_index(&data, &centers, &array![0, 0, 1, 1]).unwrap();
+        /// assert!((index - 40.5).abs() < 1e-12);
+        /// ```
+        pub fn calinski_harabasz_index(
+            data: &Array2<f64>,
+            centers: &Array2<f64>,
+            labels: &Array1<usize>,
+        ) -> Result<f64, AlgoError> {
+            let (n, k) = (data.nrows(), centers.nrows());
+            let sizes = cluster_sizes(labels, k)?;
+            if k < 2 || n <= k {
+                return Ok(f64::NAN);
+            }
+            let mean = data.mean_axis(Axis(0)).unwrap();
+            let between: f64 = centers
+                .outer_iter()
+                .zip(sizes.iter())
+                .map(|(center, &size)| size as f64 * (&center - &mean).mapv(|d| d * d).sum())
+                .sum();
+            let within: f64 = data
+                .outer_iter()
+                .zip(labels.iter())
+                .map(|(point, &label)| (&point - &centers.row(label)).mapv(|d| d * d).sum())
+                .sum();
+            Ok((between / (k - 1) as f64) / (within / (n - k) as f64))
+        }
+
+        /// Mean silhouette coefficient of a hard clustering using euclidean distance
+        ///
+        /// Points in singleton clusters contribute 0. Returns NaN if fewer than two clusters are
+        /// populated. This is O(n²) in the number of data points.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`   - data points (rows)
+        /// * `labels` - cluster per data point
+        /// * `k`      - number of clusters
+        ///
+        /// # Errors
+        ///
+        /// `LabelOutOfRange` for a label `>= k`
+        pub fn silhouette_score(
+            data: &Array2<f64>,
+            labels: &Array1<usize>,
+            k: usize,
+        ) -> Result<f64, AlgoError> {
+            let sizes = cluster_sizes(labels, k)?;
+            if sizes.iter().filter(|&&size| size > 0).count() < 2 {
+                return Ok(f64::NAN);
+            }
+
+            let mut total = 0.0;
+            let mut dist_sums = vec![0.0; k];
+            for (i, point) in data.outer_iter().enumerate() {
+                dist_sums.iter_mut().for_each(|sum| *sum = 0.0);
+                for (other, label) in data.outer_iter().zip(labels.iter()) {
+                    dist_sums[*label] += (&point - &other).mapv(|val| val * val).sum().sqrt();
+                }
+
+                let own = labels[i];
+                if sizes[own] < 2 {
+                    continue;
+                }
+                let a = dist_sums[own] / (sizes[own] - 1) as f64;
+                let b = (0..k)
+                    .filter(|&c| c != own && sizes[c] > 0)
+                    .map(|c| dist_sums[c] / sizes[c] as f64)
+                    .fold(f64::INFINITY, f64::min);
+                total += (b - a) / f64::max(a, b);
+            }
+            Ok(total / data.nrows() as f64)
+        }
+
+        /// Mean distance between all points of cluster `ci` and all points of cluster `cj`
+        ///
+        /// For `ci == cj` this is the mean distance between distinct points of the cluster.
+        /// Returns NaN if there are no such pairs. This is O(n²) in the number of data points.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `assignments` - cluster per data point
+        /// * `ci`          - first cluster
+        /// * `cj`          - second cluster
+        /// * `metric`      - distance between two data points
+        pub fn average_linkage_distance<T>(
+            data: &Array2<T>,
+            assignments: &Array1<usize>,
+            ci: usize,
+            cj: usize,
+            metric: &dyn DistanceMetric,
+        ) -> f64
+        where
+            T: Clone + Copy,
+            f64: From<T>,
+        {
+            let x = data.mapv(f64::from);
+            let members = cluster_members(assignments, ci.max(cj) + 1);
+            linkage(&x, &members[ci], &members[cj], ci == cj, metric)
+        }
+
+        /// Average linkage distances between all pairs of clusters
+        ///
+        /// Entry `(i, j)` is `average_linkage_distance(data, assignments, i, j, metric)`, so the
+        /// matrix is symmetric.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `assignments` - cluster per data point
+        /// * `k`           - number of clusters
+        /// * `metric`      - distance between two data points
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::{metrics::average_linkage_matrix, Euclidean};
+        /// use ndarray::{array, Array2};
+        ///
+        /// let data: Array2<f64> = array![[0.0, 0.0], [0.0, 1.0], [4.0, 0.0], [5.0, 0.0]];
+        /// let linkage = average_linkage_matrix(&data, &array![0, 0, 1, 1], 2, &Euclidean);
+        /// assert_eq!(linkage, linkage.t());
+        /// assert_eq!(linkage[[0, 0]], 1.0);
+        /// ```
+        pub fn average_linkage_matrix<T>(
+            data: &Array2<T>,
+            assignments: &Array1<usize>,
+            k: usize,
+            metric: &dyn DistanceMetric,
+        ) -> Array2<f64>
+        where
+            T: Clone + Copy,
+            f64: From<T>,
+        {
+            let x = data.mapv(f64::from);
+            let members = cluster_members(assignments, k);
+            let mut out = Array2::<f64>::zeros((k, k));
+            for i in 0..k {
+                for j in i..k {
+                    let dist = linkage(&x, &members[i], &members[j], i == j, metric);
+                    out[[i, j]] = dist;
+                    out[[j, i]] = dist;
+                }
+            }
+            out
+        }
+
+        /// Distances between all pairs of data points within each cluster
+        ///
+        /// Element `c` of the result is an `n_c × n_c` matrix, where `n_c` is the number of data
+        /// points assigned to cluster `c`. Rows and columns follow the order of the points in
+        /// `data`. Points assigned to a cluster `>= k` are ignored.
+        ///
+        /// Computing and storing the matrices is O(n_c²) per cluster in time and memory, so this
+        /// is only feasible for small data sets.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `assignments` - cluster per data point
+        /// * `k`           - number of clusters
+        /// * `metric`      - distance between two data points
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::algo::{metrics::intra_cluster_pairwise_distances, Euclidean};
+        /// use ndarray::{array, Array2};
+        ///
+        /// let data: Array2<f64> = array![[0.0, 0.0], [9.0, 9.0], [3.0, 4.0], [0.0, 4.0]];
+        /// let dists = intra_cluster_pairwise_distances(&data, &array![0, 1, 0, 0], 2, &Euclidean);
+        /// assert_eq!(dists[0].dim(), (3, 3));
+        /// assert_eq!(dists[0], dists[0].t());
+        /// assert!(dists[0].diag().iter().all(|&d| d == 0.0));
+        /// assert_eq!(dists[0][[0, 1]], 5.0);
+        /// assert_eq!(dists[1], array![[0.0]]);
+        /// ```
+        pub fn intra_cluster_pairwise_distances<T>(
+            data: &Array2<T>,
+            assignments: &Array1<usize>,
+            k: usize,
+            metric: &dyn DistanceMetric,
+        ) -> Vec<Array2<f64>>
+        where
+            T: Clone + Copy,
+            f64: From<T>,
+        {
+            let x = data.mapv(f64::from);
+            cluster_members(assignments, k)
+                .iter()
+                .map(|members| {
+                    let n = members.len();
+                    let mut dists = Array2::<f64>::zeros((n, n));
+                    for (a, &i) in members.iter().enumerate() {
+                        for (b, &j) in members.iter().enumerate().skip(a + 1) {
+                            let dist = metric.distance(x.row(i), x.row(j));
+                            dists[[a, b]] = dist;
+                            dists[[b, a]] = dist;
+                        }
+                    }
+                    dists
+                })
+                .collect()
+        }
+
+        /// Largest distance between two data points of the same cluster, per cluster
+        ///
+        /// Clusters with fewer than two data points have diameter 0. Like
+        /// `intra_cluster_pairwise_distances` this takes O(n_c²) time per cluster, but only
+        /// constant memory.
+        ///
+        /// # Arguments
+        ///
+        /// * `data`        - data points (rows)
+        /// * `assignments` - cluster per data point
+        /// * `k`           - number of clusters
+        /// * `metric`      - distance between two data points
+        pub fn intra_cluster_diameter<T>(
+            data: &Array2<T>,
+            assignments: &Array1<usize>,
+            k: usize,
+            metric: &dyn DistanceMetric,
+        ) -> Array1<f64>
+        where
+            T: Clone + Copy,
+            f64: From<T>,
+        {
+            let x = data.mapv(f64::from);
+            cluster_members(assignments, k)
+                .iter()
+                .map(|members| {
+                    let mut diameter = 0.0;
+                    for (a, &i) in members.iter().enumerate() {
+                        for &j in &members[a + 1..] {
+                            diameter = f64::max(diameter, metric.distance(x.row(i), x.row(j)));
+                        }
+                    }
+                    diameter
+                })
+                .collect()
+        }
+
+        /// Indices of the data points in each of the clusters `0..k`
+        fn cluster_members(assignments: &Array1<usize>, k: usize) -> Vec<Vec<usize>> {
+            let mut members = vec![Vec::new(); k];
+            for (i, &cluster) in assignments.iter().enumerate() {
+                if cluster < k {
+                    members[cluster].push(i);
+                }
+            }
+            members
+        }
+
+        /// Mean distance between the points of two index sets, skipping identical points if the
+        /// sets are the same cluster
+        fn linkage(
+            x: &Array2<f64>,
+            members_i: &[usize],
+            members_j: &[usize],
+            same: bool,
+            metric: &dyn DistanceMetric,
+        ) -> f64 {
+            let mut sum = 0.0;
+            let mut n_pairs = 0usize;
+            for &a in members_i {
+                for &b in members_j {
+                    if same && a == b {
+                        continue;
+                    }
+                    sum += metric.distance(x.row(a), x.row(b));
+                    n_pairs += 1;
+                }
+            }
+            if n_pairs == 0 {
+                f64::NAN
+            } else {
+                sum / n_pairs as f64
+            }
+        }
+
+        /// Divide every row of a confusion matrix by its sum
+        ///
+        /// Rows without any entries stay zero.
+        ///
+        /// # Arguments
+        ///
+        /// * `cm` - confusion matrix as returned by `confusion_matrix`
+        pub fn row_normalize(cm: &Array2<usize>) -> Array2<f64> {
+            let mut out = cm.mapv(|val| val as f64);
+            for mut row in out.outer_iter_mut() {
+                let sum = row.sum();
+                if sum > 0.0 {
+                    row.mapv_inplace(|val| val / sum);
+                }
+            }
+            out
+        }
+    }
+}
+
+pub use preprocess::Array2Preprocess;
+
+/// Scaling, sampling and cleaning of data before clustering
+pub mod preprocess {
+    use ndarray::{Array1, Array2, Axis};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    /// Common preprocessing steps on data matrices (rows are data points)
+    ///
+    /// All methods return new arrays, so they can be chained:
+    ///
+    /// ```
+    /// use k_means_rs::Array2Preprocess;
+    /// use ndarray::array;
+    ///
+    /// let data = array![[0.0, 1.0, 10.0], [1.0, 3.0, 20.0]];
+    /// let features = data.standardized().0.drop_columns(&[0]);
+    /// assert_eq!(features, array![[-1.0, -1.0], [1.0, 1.0]]);
+    /// ```
+    pub trait Array2Preprocess {
+        /// Scale every column to the range `[0, 1]`
+        ///
+        /// Returns the scaled data and the minimum and maximum of every column. Constant columns
+        /// become 0.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::Array2Preprocess;
+        /// use ndarray::array;
+        ///
+        /// let (scaled, min, max) = array![[1.0, 5.0], [3.0, 5.0], [2.0, 5.0]].normalized();
+        /// assert_eq!(scaled, array![[0.0, 0.0], [1.0, 0.0], [0.5, 0.0]]);
+        /// assert_eq!(min, array![1.0, 5.0]);
+        /// assert_eq!(max, array![3.0, 5.0]);
+        /// ```
+        fn normalized(&self) -> (Array2<f64>, Array1<f64>, Array1<f64>);
+
+        /// Shift and scale every column to zero mean and unit (population) standard deviation
+        ///
+        /// Returns the standardized data and the mean and standard deviation of every column.
+        /// Constant columns are only centered.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::Array2Preprocess;
+        /// use ndarray::array;
+        ///
+        /// let (standardized, mean, std) = array![[1.0, 2.0], [3.0, 2.0]].standardized();
+        /// assert_eq!(standardized, array![[-1.0, 0.0], [1.0, 0.0]]);
+        /// assert_eq!(mean, array![2.0, 2.0]);
+        /// assert_eq!(std, array![1.0, 0.0]);
+        /// ```
+        fn standardized(&self) -> (Array2<f64>, Array1<f64>, Array1<f64>);
+
+        /// Prepend a column holding the row number `0, 1, 2, ...`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::Array2Preprocess;
+        /// use ndarray::array;
+        ///
+        /// let indexed = array![[0.5], [0.25]].with_row_indices();
+        /// assert_eq!(indexed, array![[0.0, 0.5], [1.0, 0.25]]);
+        /// ```
+        fn with_row_indices(&self) -> Array2<f64>;
+
+        /// Remove the given columns
+        ///
+        /// Indices that are out of range are ignored.
+        ///
+        /// # Arguments
+        ///
+        /// * `cols` - zero-based indices of the columns to remove
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use k_means_rs::Array2Preprocess;
+        /// use ndarray::array;
+        ///
+        /// let data = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        /// assert_eq!(data.drop_columns(&[0, 2]), array![[2.0], [5.0]]);
+        /// ```
+        fn drop_columns(&self, cols: &[usize]) -> Array2<f64>;
+    }
+
+    impl Array2Preprocess for Array2<f64> {
+        fn normalized(&self) -> (Array2<f64>, Array1<f64>, Array1<f64>) {
+            let min = self.fold_axis(Axis(0), f64::INFINITY, |a, b| a.min(*b));
+            let max = self.fold_axis(Axis(0), f64::NEG_INFINITY, |a, b| a.max(*b));
+            let mut scaled = self.clone();
+            for mut row in scaled.outer_iter_mut() {
+                for ((val, lo), hi) in row.iter_mut().zip(min.iter()).zip(max.iter()) {
+                    *val = if hi > lo {
+                        (*val - lo) / (hi - lo)
+                    } else {
+                        0.0
+                    };
+                }
+            }
+            (scaled, min, max)
+        }
+
+        fn standardized(&self) -> (Array2<f64>, Array1<f64>, Array1<f64>) {
+            let mean = self
+                .mean_axis(Axis(0))
+                .unwrap_or_else(|| Array1::zeros(self.ncols()));
+            let std = self.std_axis(Axis(0), 0.0);
+            let mut standardized = self - &mean;
+            for mut row in standardized.outer_iter_mut() {
+                row.zip_mut_with(&std, |val, &s| {
+                    if s > 0.0 {
+                        *val /= s
+                    }
+                });
+            }
+            (standardized, mean, std)
+        }
+
+        fn with_row_indices(&self) -> Array2<f64> {
+            let mut out = Array2::<f64>::zeros((self.nrows(), self.ncols() + 1));
+            for (i, (mut out_row, row)) in out.outer_iter_mut().zip(self.outer_iter()).enumerate() {
+                out_row[0] = i as f64;
+                out_row.slice_mut(ndarray::s![1..]).assign(&row);
+            }
+            out
+        }
+
+        fn drop_columns(&self, cols: &[usize]) -> Array2<f64> {
+            let keep: Vec<usize> = (0..self.ncols()).filter(|j| !cols.contains(j)).collect();
+            self.select(Axis(1), &keep)
+        }
+    }
+
+    /// Copy of `data` with its rows in random order
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data points (rows)
+    /// * `rng`  - random number generator, the same seed gives the same order
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::shuffle_rows;
+    /// use ndarray::Array2;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// // ordered by class: 50 rows of class 0, then 50 of class 1
+    /// let data = Array2::from_shape_fn((100, 1), |(i, _)| (i / 50) as f64);
+    /// let shuffled = shuffle_rows(&data, &mut StdRng::seed_from_u64(7));
+    /// assert_eq!(shuffled, shuffle_rows(&data, &mut StdRng::seed_from_u64(7)));
+    /// assert_eq!(shuffled.sum(), data.sum());
+    /// // the first rows are no longer all of one class
+    /// let head = shuffled.column(0).iter().take(10).sum::<f64>();
+    /// assert!(head > 0.0 && head < 10.0);
+    /// ```
+    pub fn shuffle_rows<T, R>(data: &Array2<T>, rng: &mut R) -> Array2<T>
+    where
+        T: Clone,
+        R: Rng + ?Sized,
+    {
+        let mut order: Vec<usize> = (0..data.nrows()).collect();
+        order.shuffle(rng);
+        data.select(Axis(0), &order)
+    }
+
+    /// Rows of a random subset of `fraction` of the data points, in their original order
+    ///
+    /// Returns the sampled rows and their zero-based indices in `data`. At least one row is kept
+    /// of non-empty data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`     - data points (rows)
+    /// * `fraction` - share of rows to keep, in `(0, 1]`
+    /// * `rng`      - random number generator, the same seed gives the same sample
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::sample_rows;
+    /// use ndarray::Array2;
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let data = Array2::from_shape_fn((200, 2), |(i, j)| (i * 2 + j) as f64);
+    /// let (sample, rows) = sample_rows(&data, 0.1, &mut StdRng::seed_from_u64(1));
+    /// assert_eq!(sample.nrows(), 20);
+    /// assert!(rows.windows(2).all(|pair| pair[0] < pair[1]));
+    /// assert_eq!(sample.row(3), data.row(rows[3]));
+    /// ```
+    pub fn sample_rows<T, R>(
+        data: &Array2<T>,
+        fraction: f64,
+        rng: &mut R,
+    ) -> (Array2<T>, Vec<usize>)
+    where
+        T: Clone,
+        R: Rng + ?Sized,
+    {
+        let n_rows = data.nrows();
+        let amount = ((fraction * n_rows as f64).round() as usize).clamp(n_rows.min(1), n_rows);
+        let mut rows = rand::seq::index::sample(rng, n_rows, amount).into_vec();
+        rows.sort_unstable();
+        (data.select(Axis(0), &rows), rows)
+    }
+
+    /// Smallest number of components a random projection of `n_samples` data points needs to
+    /// keep all their squared pairwise distances within a factor of `1 +- eps` (Johnson-
+    /// Lindenstrauss)
+    ///
+    /// The bound doesn't depend on the number of features, only on the number of data points.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_samples` - number of data points
+    /// * `eps`       - largest relative distortion of squared distances, in `(0, 1)`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::jl_min_components;
+    ///
+    /// assert_eq!(jl_min_components(1000, 0.5), 332);
+    /// assert!(jl_min_components(1000, 0.1) > jl_min_components(1000, 0.5));
+    /// ```
+    pub fn jl_min_components(n_samples: usize, eps: f64) -> usize {
+        let denominator = eps * eps / 2.0 - eps * eps * eps / 3.0;
+        (4.0 * (n_samples as f64).ln() / denominator).ceil() as usize
+    }
+
+    /// Project the data onto `n_components` random directions, which approximately preserves
+    /// the distances between data points
+    ///
+    /// The projection matrix has independent gaussian entries with variance `1 / n_components`
+    /// (Johnson-Lindenstrauss), so squared distances are kept in expectation. Unlike PCA, this
+    /// needs no decomposition of the data, only one matrix product. See `jl_min_components` for
+    /// how many components keep distances within a given distortion. Returns the projected data
+    /// and the `n_features x n_components` projection matrix, to project further data points
+    /// the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`         - data points (rows)
+    /// * `n_components` - number of dimensions to project to
+    /// * `seed`         - seed of the projection matrix
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::{jl_min_components, random_projection};
+    /// use ndarray::{Array2, ArrayView1};
+    /// use rand::{rngs::StdRng, Rng, SeedableRng};
+    ///
+    /// let mut rng = StdRng::seed_from_u64(1);
+    /// let data = Array2::from_shape_simple_fn((30, 2000), || rng.gen::<f64>());
+    /// let eps = 0.5;
+    /// let n_components = jl_min_components(data.nrows(), eps);
+    /// let (projected, matrix) = random_projection(&data, n_components, 7);
+    /// assert_eq!(projected.dim(), (30, n_components));
+    /// assert_eq!(matrix.dim(), (2000, n_components));
+    ///
+    /// let dist_sq = |a: ArrayView1<f64>, b: ArrayView1<f64>| (&a - &b).mapv(|d| d * d).sum();
+    /// for i in 0..data.nrows() {
+    ///     for j in 0..i {
+    ///         let ratio = dist_sq(projected.row(i), projected.row(j))
+    ///             / dist_sq(data.row(i), data.row(j));
+    ///         assert!((1.0 - eps..=1.0 + eps).contains(&ratio));
+    ///     }
+    /// }
+    /// ```
+    pub fn random_projection(
+        data: &Array2<f64>,
+        n_components: usize,
+        seed: u64,
+    ) -> (Array2<f64>, Array2<f64>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let std_dev = 1.0 / (n_components as f64).sqrt();
+        let matrix = Array2::from_shape_simple_fn((data.ncols(), n_components), || {
+            standard_normal(&mut rng) * std_dev
+        });
+        (data.dot(&matrix), matrix)
+    }
+
+    /// Like `random_projection`, but with a sparse projection matrix that is faster to draw
+    ///
+    /// With `s = sqrt(n_features)`, an entry of the matrix is `+sqrt(s / n_components)` or
+    /// `-sqrt(s / n_components)` with probability `1 / (2s)` each and 0 otherwise (Li et al.,
+    /// very sparse random projections). Squared distances are kept in expectation like with
+    /// gaussian entries, while only about one in `s` entries is non-zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`         - data points (rows)
+    /// * `n_components` - number of dimensions to project to
+    /// * `seed`         - seed of the projection matrix
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::{jl_min_components, sparse_random_projection};
+    /// use ndarray::{Array2, ArrayView1};
+    /// use rand::{rngs::StdRng, Rng, SeedableRng};
+    ///
+    /// let mut rng = StdRng::seed_from_u64(2);
+    /// let data = Array2::from_shape_simple_fn((30, 2500), || rng.gen::<f64>());
+    /// let eps = 0.5;
+    /// let (projected, matrix) =
+    ///     sparse_random_projection(&data, jl_min_components(data.nrows(), eps), 7);
+    /// // s = 50, so about 2% of the entries are non-zero
+    /// let nonzero = matrix.iter().filter(|&&val| val != 0.0).count() as f64;
+    /// assert!((nonzero / matrix.len() as f64 - 0.02).abs() < 0.005);
+    ///
+    /// let dist_sq = |a: ArrayView1<f64>, b: ArrayView1<f64>| (&a - &b).mapv(|d| d * d).sum();
+    /// for i in 0..data.nrows() {
+    ///     for j in 0..i {
+    ///         let ratio = dist_sq(projected.row(i), projected.row(j))
+    ///             / dist_sq(data.row(i), data.row(j));
+    ///         assert!((1.0 - eps..=1.0 + eps).contains(&ratio));
+    ///     }
+    /// }
+    /// ```
+    pub fn sparse_random_projection(
+        data: &Array2<f64>,
+        n_components: usize,
+        seed: u64,
+    ) -> (Array2<f64>, Array2<f64>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let s = (data.ncols() as f64).sqrt().max(1.0);
+        let value = (s / n_components as f64).sqrt();
+        let matrix = Array2::from_shape_simple_fn((data.ncols(), n_components), || {
+            let draw: f64 = rng.gen();
+            if draw < 0.5 / s {
+                value
+            } else if draw < 1.0 / s {
+                -value
+            } else {
+                0.0
+            }
+        });
+        (data.dot(&matrix), matrix)
+    }
+
+    /// A draw from the standard normal distribution (Box-Muller transform)
+    fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+        // 1 - u lies in (0, 1], so the logarithm is finite
+        let u: f64 = rng.gen();
+        let v: f64 = rng.gen();
+        (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+
+    /// Variance below which `remove_low_variance_features` should treat a column as constant
+    pub const DEFAULT_MIN_VARIANCE: f64 = 1e-6;
+
+    /// Drop the columns whose (population) variance is below `threshold`
+    ///
+    /// Near-constant columns add noise to the distances without separating clusters. Returns the
+    /// remaining columns and their zero-based indices in `data`, e.g. to look up feature names.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`      - data points (rows)
+    /// * `threshold` - smallest variance a column needs to be kept, usually `DEFAULT_MIN_VARIANCE`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::{remove_low_variance_features, DEFAULT_MIN_VARIANCE};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[1.0, 7.0, 0.0], [2.0, 7.0, 5.0], [3.0, 7.0, 1.0]];
+    /// let (kept, columns) = remove_low_variance_features(&data, DEFAULT_MIN_VARIANCE);
+    /// assert_eq!(kept.ncols(), data.ncols() - 1);
+    /// assert_eq!(columns, vec![0, 2]);
+    /// assert_eq!(kept, array![[1.0, 0.0], [2.0, 5.0], [3.0, 1.0]]);
+    /// ```
+    pub fn remove_low_variance_features(
+        data: &Array2<f64>,
+        threshold: f64,
+    ) -> (Array2<f64>, Vec<usize>) {
+        let variances = data.var_axis(Axis(0), 0.0);
+        let kept: Vec<usize> = (0..data.ncols())
+            .filter(|&j| variances[j] >= threshold)
+            .collect();
+        (data.select(Axis(1), &kept), kept)
+    }
+
+    /// Flag the data points that lie far from the median in any feature
+    ///
+    /// Every feature value is scored by its modified z-score `0.6745 * |x - median| / MAD`, where
+    /// MAD is the median absolute deviation of the feature. Unlike mean and standard deviation,
+    /// median and MAD are hardly moved by the outliers themselves. Features with a MAD of zero,
+    /// like those where most points share one value, are not scored.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`      - data points (rows)
+    /// * `threshold` - largest score of a point that isn't an outlier, usually 3.5
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::{detect_outliers_mad, remove_outliers};
+    /// use ndarray::array;
+    ///
+    /// let data = array![[1.0, 10.0], [2.0, 11.0], [3.0, 12.0], [2.5, 11.5], [1.5, 90.0]];
+    /// let flags = detect_outliers_mad(&data, 3.5);
+    /// assert_eq!(flags, array![false, false, false, false, true]);
+    /// let (cleaned, kept) = remove_outliers(&data, &flags);
+    /// assert_eq!(cleaned.nrows(), 4);
+    /// assert_eq!(kept, vec![0, 1, 2, 3]);
+    /// ```
+    pub fn detect_outliers_mad(data: &Array2<f64>, threshold: f64) -> Array1<bool> {
+        // scales the MAD to the standard deviation of normally distributed data
+        const CONSISTENCY: f64 = 0.6745;
+
+        let mut flags = Array1::from_elem(data.nrows(), false);
+        for column in data.columns() {
+            let center = median(column.to_vec());
+            let mad = median(column.iter().map(|val| (val - center).abs()).collect());
+            if mad.is_nan() || mad <= 0.0 {
+                continue;
+            }
+            for (flag, &val) in flags.iter_mut().zip(column) {
+                if CONSISTENCY * (val - center).abs() / mad > threshold {
+                    *flag = true;
+                }
+            }
+        }
+        flags
+    }
+
+    /// Keep the data points that aren't flagged
+    ///
+    /// Returns the remaining data points and their zero-based row indices in `data`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`  - data points (rows)
+    /// * `flags` - whether each data point is removed, e.g. from `detect_outliers_mad`
+    pub fn remove_outliers(data: &Array2<f64>, flags: &Array1<bool>) -> (Array2<f64>, Vec<usize>) {
+        let kept: Vec<usize> = (0..data.nrows()).filter(|&i| !flags[i]).collect();
+        (data.select(Axis(0), &kept), kept)
+    }
+
+    /// Decorrelate the features and scale them to unit variance (ZCA whitening)
+    ///
+    /// The data is centered and multiplied by the whitening matrix
+    /// `W = V diag(1 / sqrt(l + eps)) V^T`, where `V` and `l` are the eigenvectors and
+    /// eigenvalues of the covariance matrix. `eps` is a tiny fraction of the largest eigenvalue
+    /// so constant or collinear features don't make `W` infinite. Unlike PCA whitening, the
+    /// features keep their orientation. Returns the whitened data and `W`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data points (rows)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use k_means_rs::preprocess::{unwhiten, whiten};
+    /// use ndarray::{Array2, Axis};
+    ///
+    /// // the second feature mostly follows the first
+    /// let data = Array2::from_shape_fn((200, 2), |(i, j)| {
+    ///     let x = (i as f64 * 0.37).sin() * 3.0;
+    ///     let noise = (i as f64 * 1.91).cos();
+    ///     if j == 0 { x } else { 2.0 * x + noise + 5.0 }
+    /// });
+    /// let (whitened, w) = whiten(&data);
+    ///
+    /// let cov = whitened.t().dot(&whitened) / (whitened.nrows() - 1) as f64;
+    /// let identity = Array2::<f64>::eye(2);
+    /// assert!(cov.iter().zip(&identity).all(|(c, i)| (c - i).abs() < 1e-9));
+    ///
+    /// let restored = unwhiten(&whitened, &w) + &data.mean_axis(Axis(0)).unwrap();
+    /// assert!(restored.iter().zip(&data).all(|(r, d)| (r - d).abs() < 1e-9));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` has no rows
+    #[cfg(feature = "linalg")]
+    pub fn whiten(data: &Array2<f64>) -> (Array2<f64>, Array2<f64>) {
+        use linfa_linalg::eigh::Eigh;
+        // relative to the largest eigenvalue
+        const EPSILON: f64 = 1e-12;
+
+        let mean = data
+            .mean_axis(Axis(0))
+            .expect("Can't whiten data without data points!");
+        let centered = data - &mean;
+        let cov = centered.t().dot(&centered) / (data.nrows().max(2) - 1) as f64;
+        let (eigvals, eigvecs) = cov.eigh().expect("covariance matrices are square");
+        let largest = eigvals.iter().copied().fold(0.0, f64::max);
+        let eps = (EPSILON * largest).max(f64::MIN_POSITIVE);
+        let scales = eigvals.mapv(|val| 1.0 / (val.max(0.0) + eps).sqrt());
+        let w = (&eigvecs * &scales).dot(&eigvecs.t());
+        (centered.dot(&w), w)
+    }
+
+    /// Map whitened data, e.g. cluster centers fit on it, back to the original features
+    ///
+    /// Multiplies by the inverse of the whitening matrix. The result is still centered, add the
+    /// mean of the original data to get back original units.
+    ///
+    /// # Arguments
+    ///
+    /// * `whitened` - whitened data points (rows)
+    /// * `w`        - whitening matrix returned by `whiten`
+    #[cfg(feature = "linalg")]
+    pub fn unwhiten(whitened: &Array2<f64>, w: &Array2<f64>) -> Array2<f64> {
+        whitened.dot(&whitening_inverse(w))
+    }
+
+    /// Inverse of a whitening matrix returned by `whiten`
+    ///
+    /// `W` is symmetric with positive eigenvalues, so the inverse has the same eigenvectors and
+    /// the reciprocal eigenvalues.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - whitening matrix
+    #[cfg(feature = "linalg")]
+    pub fn whitening_inverse(w: &Array2<f64>) -> Array2<f64> {
+        use linfa_linalg::eigh::Eigh;
+
+        let (eigvals, eigvecs) = w.eigh().expect("whitening matrices are square");
+        (&eigvecs * &eigvals.mapv(f64::recip)).dot(&eigvecs.t())
+    }
+
+    /// Median of `values`, NaN if there are none
+    fn median(mut values: Vec<f64>) -> f64 {
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        values.sort_by(f64::total_cmp);
+        let mid = values.len() / 2;
+        if values.len().is_multiple_of(2) {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        }
+    }
+}
+
+/// JavaScript bindings for running small clusterings in the browser
+///
+/// Build with `wasm-pack build -- --features wasm`. Arrays cross the boundary flattened in row
+/// major order as typed arrays.
+#[cfg(feature = "wasm")]
+pub mod wasm {
+    use crate::algo::{ClusteringAlgorithm, FuzzyKMeansConfig};
+    use ndarray::Array2;
+    use wasm_bindgen::prelude::*;
+
+    /// Fitted clusters as returned by `fit`
+    #[wasm_bindgen]
+    pub struct FitResult {
+        centers: Vec<f64>,
+        labels: Vec<u32>,
+        memberships: Vec<f64>,
+        n_iter: usize,
+        converged: bool,
+    }
+
+    #[wasm_bindgen]
+    impl FitResult {
+        /// Cluster centers, `k` rows of `n_cols` values
+        #[wasm_bindgen(getter)]
+        pub fn centers(&self) -> Vec<f64> {
+            self.centers.clone()
+        }
+
+        /// Index of the cluster with the highest membership for every data point
+        #[wasm_bindgen(getter)]
+        pub fn labels(&self) -> Vec<u32> {
+            self.labels.clone()
+        }
+
+        /// Memberships, one row of `k` values per data point
+        #[wasm_bindgen(getter)]
+        pub fn memberships(&self) -> Vec<f64> {
+            self.memberships.clone()
+        }
+
+        /// Number of iterations run
+        #[wasm_bindgen(getter, js_name = nIter)]
+        pub fn n_iter(&self) -> usize {
+            self.n_iter
+        }
+
+        /// Whether the centers settled before the iteration cap
+        #[wasm_bindgen(getter)]
+        pub fn converged(&self) -> bool {
+            self.converged
+        }
+    }
+
+    /// Cluster data points using fuzzy k means
+    ///
+    /// # Arguments
+    ///
+    /// * `data`     - data points, flattened row by row
+    /// * `n_cols`   - number of features per data point
+    /// * `k`        - number of clusters
+    /// * `q`        - fuzzifier
+    /// * `max_iter` - upper bound of iteration number
+    /// * `seed`     - seed for the center initialisation
+    #[wasm_bindgen]
+    pub fn fit(
+        data: &[f64],
+        n_cols: usize,
+        k: usize,
+        q: f64,
+        max_iter: usize,
+        seed: u32,
+    ) -> Result<FitResult, JsError> {
+        if n_cols == 0 || !data.len().is_multiple_of(n_cols) {
+            return Err(JsError::new(&format!(
+                "Can't split {} values into rows of {}",
+                data.len(),
+                n_cols
+            )));
+        }
+        let data = Array2::from_shape_vec((data.len() / n_cols, n_cols), data.to_vec())?;
+        let mut config = FuzzyKMeansConfig::new(k, max_iter, q);
+        config.seed = Some(u64::from(seed));
+        let result = config.fit(&data)?;
+        Ok(FitResult {
+            centers: result.centers.iter().copied().collect(),
+            labels: result.labels.iter().map(|&label| label as u32).collect(),
+            memberships: result.memberships.iter().copied().collect(),
+            n_iter: result.n_iter,
+            converged: result.converged,
+        })
+    }
+}
+
+/// C API for linking the clustering into C and C++ programs
+///
+/// Build with `cargo build --release --features ffi`, which writes the header `k_means_rs.h`
+/// next to the static and dynamic library in `target/release`. Functions return `FZY_OK` or
+/// one of the `FZY_ERR_*` codes, `fzy_last_error_message` describes the last error.
+#[cfg(feature = "ffi")]
+pub mod ffi {
+    use crate::algo::{ClusteringAlgorithm, FuzzyKMeansConfig};
+    use ndarray::Array2;
+    use std::cell::RefCell;
+    use std::ffi::{c_char, c_int, CString};
+    use std::panic::{self, AssertUnwindSafe};
+
+    /// The call succeeded
+    pub const FZY_OK: c_int = 0;
+    /// A required pointer was null
+    pub const FZY_ERR_NULL_POINTER: c_int = 1;
+    /// An argument was out of range
+    pub const FZY_ERR_INVALID_ARGUMENT: c_int = 2;
+    /// Clustering failed, e.g. for more clusters than data points
+    pub const FZY_ERR_FIT: c_int = 3;
+    /// An unexpected internal error, which is a bug
+    pub const FZY_ERR_INTERNAL: c_int = 4;
+
+    thread_local! {
+        static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+    }
+
+    /// Remember the error message for `fzy_last_error_message` and return `code`
+    fn fail(code: c_int, message: String) -> c_int {
+        // messages hold no nul bytes, but drop them rather than the message
+        let message = CString::new(message.replace('\0', "")).unwrap();
+        LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+        code
+    }
+
+    /// Fitted clusters as returned by `fzy_fit`, to be released with `fzy_result_free`
+    ///
+    /// Arrays are flattened row by row.
+    #[repr(C)]
+    pub struct FzyResult {
+        /// Cluster centers, `k` rows of `cols` values
+        pub centers: *mut f64,
+        /// Number of values in `centers`
+        pub centers_len: usize,
+        /// Index of the cluster with the highest membership for every data point
+        pub labels: *mut usize,
+        /// Number of values in `labels`
+        pub labels_len: usize,
+        /// Memberships, one row of `k` values per data point
+        pub memberships: *mut f64,
+        /// Number of values in `memberships`
+        pub memberships_len: usize,
+        /// Number of iterations run
+        pub n_iter: usize,
+        /// Whether the centers settled before the iteration cap
+        pub converged: bool,
+    }
+
+    impl FzyResult {
+        fn empty() -> Self {
+            FzyResult {
+                centers: std::ptr::null_mut(),
+                centers_len: 0,
+                labels: std::ptr::null_mut(),
+                labels_len: 0,
+                memberships: std::ptr::null_mut(),
+                memberships_len: 0,
+                n_iter: 0,
+                converged: false,
+            }
+        }
+    }
+
+    /// Hand a vector over to C, to be taken back by `free_array`
+    fn into_raw<T>(vals: Vec<T>) -> (*mut T, usize) {
+        let len = vals.len();
+        (Box::into_raw(vals.into_boxed_slice()) as *mut T, len)
+    }
+
+    /// Release an array handed over by `into_raw`
+    ///
+    /// # Safety
+    ///
+    /// `ptr` and `len` must come from `into_raw` or `ptr` must be null
+    unsafe fn free_array<T>(ptr: *mut T, len: usize) {
+        if !ptr.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+        }
+    }
+
+    /// Cluster data points using fuzzy k means
+    ///
+    /// On success `*out` holds the result, on failure it holds null pointers. Either way it may
+    /// be passed to `fzy_result_free`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data`     - `rows * cols` values, flattened row by row
+    /// * `rows`     - number of data points
+    /// * `cols`     - number of features per data point
+    /// * `k`        - number of clusters
+    /// * `q`        - fuzzifier
+    /// * `max_iter` - upper bound of iteration number
+    /// * `seed`     - seed for the center initialisation
+    /// * `out`      - where to write the result
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to `rows * cols` readable values and `out` to a writable `FzyResult`.
+    /// A result already held by `*out` is overwritten without being freed.
+    #[no_mangle]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe extern "C" fn fzy_fit(
+        data: *const f64,
+        rows: usize,
+        cols: usize,
+        k: usize,
+        q: f64,
+        max_iter: usize,
+        seed: u64,
+        out: *mut FzyResult,
+    ) -> c_int {
+        if out.is_null() {
+            return fail(FZY_ERR_NULL_POINTER, String::from("out is null"));
+        }
+        out.write(FzyResult::empty());
+        if data.is_null() {
+            return fail(FZY_ERR_NULL_POINTER, String::from("data is null"));
+        }
+        let len = match rows.checked_mul(cols) {
+            Some(len) => len,
+            None => {
+                return fail(
+                    FZY_ERR_INVALID_ARGUMENT,
+                    format!("{} rows of {} columns overflow", rows, cols),
+                )
+            }
+        };
+        let vals = std::slice::from_raw_parts(data, len).to_vec();
+        // unwinding into C is undefined behavior
+        let fitted = panic::catch_unwind(AssertUnwindSafe(|| {
+            let data = Array2::from_shape_vec((rows, cols), vals).unwrap();
+            let mut config = FuzzyKMeansConfig::new(k, max_iter, q);
+            config.seed = Some(seed);
+            config.fit(&data)
+        }));
+        let result = match fitted {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => return fail(FZY_ERR_FIT, err.to_string()),
+            Err(_) => {
+                return fail(
+                    FZY_ERR_INTERNAL,
+                    String::from("internal error, clustering panicked"),
+                )
+            }
+        };
+
+        let (centers, centers_len) = into_raw(result.centers.iter().copied().collect());
+        let (labels, labels_len) = into_raw(result.labels.to_vec());
+        let (memberships, memberships_len) = into_raw(result.memberships.iter().copied().collect());
+        out.write(FzyResult {
+            centers,
+            centers_len,
+            labels,
+            labels_len,
+            memberships,
+            memberships_len,
+            n_iter: result.n_iter,
+            converged: result.converged,
+        });
+        FZY_OK
+    }
+
+    /// Release the arrays of a result written by `fzy_fit` and reset it to null pointers
+    ///
+    /// Does nothing for a null `result`.
+    ///
+    /// # Safety
+    ///
+    /// `result` must be null or point to a `FzyResult` written by `fzy_fit` and not changed
+    /// since
+    #[no_mangle]
+    pub unsafe extern "C" fn fzy_result_free(result: *mut FzyResult) {
+        if let Some(result) = result.as_mut() {
+            free_array(result.centers, result.centers_len);
+            free_array(result.labels, result.labels_len);
+            free_array(result.memberships, result.memberships_len);
+            *result = FzyResult::empty();
+        }
+    }
+
+    /// Description of the last error on this thread, null if there was none
+    ///
+    /// The string is owned by the library and stays valid until the next failing call on the
+    /// same thread.
+    #[no_mangle]
+    pub extern "C" fn fzy_last_error_message() -> *const c_char {
+        LAST_ERROR.with(|last| {
+            last.borrow()
+                .as_ref()
+                .map_or(std::ptr::null(), |message| message.as_ptr())
+        })
     }
 }
@@ -1,17 +1,36 @@
-use ndarray::Array2;
-use std::collections::BTreeMap;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use ndarray::{Array1, Array2, Axis};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use term_size;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
 
-mod lib;
-use lib::algo;
-use lib::io::{read_csv, to_csv};
+use k_means_rs::algo::{self, metrics, ClusteringAlgorithm};
+use k_means_rs::io::{
+    cluster_name, format_values, read_cluster_centers, read_csv_from_reader, results_json,
+    scan_csv_columns, to_csv, try_read_csv_delimited, write_centers_to, write_csv,
+    write_memberships_to, write_multi_labels, write_npy, write_results_json, ColumnStats, CsvError,
+    FloatFormat, MultiLabelFormat,
+};
+use k_means_rs::preprocess::{
+    detect_outliers_mad, remove_low_variance_features, remove_outliers, sample_rows,
+};
+use k_means_rs::{Array2Preprocess, ConfigError, Error};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 enum ArgType {
     FloatingNumber(Option<f64>),
     StringType(Option<String>),
     SizeType(Option<usize>),
+    /// Seed of the random number generator, 64 bits on every platform
+    SeedType(Option<u64>),
+    Flag(Option<bool>),
+    /// Flag that may be repeated, counting its occurrences
+    Count(Option<usize>),
 }
 
 impl ArgType {
@@ -35,11 +54,29 @@ impl ArgType {
         }
     }
 
+    fn seed_from_str(input: String) -> ArgType {
+        ArgType::SeedType(input.parse().ok())
+    }
+
+    /// Parse `input` into a value of the same type as `self`
+    fn parse_like(&self, input: String) -> ArgType {
+        match self {
+            ArgType::FloatingNumber(_) => ArgType::float_from_str(input),
+            ArgType::StringType(_) => ArgType::string_from_str(input),
+            ArgType::SizeType(_) => ArgType::size_from_str(input),
+            ArgType::SeedType(_) => ArgType::seed_from_str(input),
+            ArgType::Flag(_) | ArgType::Count(_) => unreachable!(),
+        }
+    }
+
     fn is_some(&self) -> bool {
         match self {
             ArgType::FloatingNumber(val) => val.is_some(),
             ArgType::StringType(val) => val.is_some(),
             ArgType::SizeType(val) => val.is_some(),
+            ArgType::SeedType(val) => val.is_some(),
+            ArgType::Flag(val) => val.is_some(),
+            ArgType::Count(val) => val.is_some(),
         }
     }
 
@@ -47,44 +84,87 @@ impl ArgType {
         !self.is_some()
     }
 
-    fn to_string(&self) -> Result<String, ()> {
-        if self.is_some() {
-            return Ok(match self {
-                ArgType::FloatingNumber(val) => format!("{}", val.unwrap()),
-                ArgType::StringType(val) => format!("{}", val.as_ref().unwrap()),
-                ArgType::SizeType(val) => format!("{}", val.unwrap()),
-            })
-        }
-        Err(())
-    }
-
-    fn get_flt(&self) -> Result<f64, ()>{
+    fn get_flt(&self) -> Result<f64, ()> {
         if self.is_none() {
-            return Err(())
+            return Err(());
         }
         match self {
             ArgType::FloatingNumber(val) => Ok(val.unwrap()),
-            _ => Err(())
+            _ => Err(()),
         }
     }
 
-    fn get_str(&self) -> Result<String, ()>{
+    fn get_str(&self) -> Result<String, ()> {
         if self.is_none() {
-            return Err(())
+            return Err(());
         }
         match self {
             ArgType::StringType(val) => Ok(val.clone().unwrap()),
-            _ => Err(())
+            _ => Err(()),
         }
     }
 
-    fn get_size(&self) -> Result<usize, ()>{
+    fn get_size(&self) -> Result<usize, ()> {
         if self.is_none() {
-            return Err(())
+            return Err(());
         }
         match self {
             ArgType::SizeType(val) => Ok(val.unwrap()),
-            _ => Err(())
+            _ => Err(()),
+        }
+    }
+
+    fn get_seed(&self) -> Result<u64, ()> {
+        if self.is_none() {
+            return Err(());
+        }
+        match self {
+            ArgType::SeedType(val) => Ok(val.unwrap()),
+            _ => Err(()),
+        }
+    }
+
+    fn get_count(&self) -> Result<usize, ()> {
+        if self.is_none() {
+            return Err(());
+        }
+        match self {
+            ArgType::Count(val) => Ok(val.unwrap()),
+            _ => Err(()),
+        }
+    }
+
+    /// What a value of this type looks like, for error messages
+    fn type_description(&self) -> &'static str {
+        match self {
+            ArgType::FloatingNumber(_) => "a number",
+            ArgType::StringType(_) => "text",
+            ArgType::SizeType(_) => "a whole number of at least 0",
+            ArgType::SeedType(_) => "a whole number of at least 0",
+            ArgType::Flag(_) => "1, true, yes, on or 0, false, no, off",
+            ArgType::Count(_) => "a whole number of at least 0",
+        }
+    }
+
+    /// Short name of the type, for errors about values given on the command line
+    fn type_name(&self) -> &'static str {
+        match self {
+            ArgType::FloatingNumber(_) => "number",
+            ArgType::StringType(_) => "text",
+            ArgType::SizeType(_) => "integer",
+            ArgType::SeedType(_) => "integer",
+            ArgType::Flag(_) => "flag",
+            ArgType::Count(_) => "integer",
+        }
+    }
+
+    fn get_flag(&self) -> Result<bool, ()> {
+        if self.is_none() {
+            return Err(());
+        }
+        match self {
+            ArgType::Flag(val) => Ok(val.unwrap()),
+            _ => Err(()),
         }
     }
 }
@@ -92,35 +172,195 @@ impl ArgType {
 struct CmdlineArgument {
     description: &'static str,
     cmdline_expr: &'static str,
+    /// Other names the parameter can be given by, e.g. a long form of a single-letter flag
+    aliases: &'static [&'static str],
+    /// Keys of parameters that have no effect together with this one. Setting both is an
+    /// argument error.
+    conflicts: &'static [&'static str],
+    /// Position in the help output, lower comes first. Input options start at 0, algorithm
+    /// parameters at 10, output options at 20 and evaluation options at 30.
+    priority: u8,
+    required: bool,
     default: ArgType,
     value: ArgType,
+    /// Where `value` came from
+    source: ValueSource,
+    /// What shell completion offers as the value
+    completion: Completion,
+}
+
+/// Values a shell completion script offers for a parameter
+#[derive(Clone, Copy)]
+enum Completion {
+    /// Anything, or no value at all for flags
+    None,
+    /// A file name
+    Path,
+    /// One of the listed words
+    Choices(&'static [&'static str]),
+}
+
+/// Where the value of a parameter came from, in order of increasing precedence
+#[derive(Clone, Debug, PartialEq)]
+enum ValueSource {
+    Default,
+    /// The named environment variable
+    Env(String),
+    /// The file given by --config
+    Config,
+    CommandLine,
 }
 
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ValueSource::Default => write!(f, "default"),
+            ValueSource::Env(var) => write!(f, "environment variable {}", var),
+            ValueSource::Config => write!(f, "config file"),
+            ValueSource::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Prefix of the environment variables that set parameters
+const ENV_PREFIX: &str = "FZY_KMEANS_";
+
 impl CmdlineArgument {
-    fn print_description_str(
+    /// The row of the parameter in the help table, with the value hint and the description
+    /// wrapped into their columns
+    ///
+    /// # Arguments
+    ///
+    /// * `term_width`    - width of the terminal
+    /// * `param_width`   - width of the parameter column, including the gap after it
+    /// * `value_width`   - width of the value column, including the gap after it
+    /// * `default_width` - width of the default column, including the gap after it
+    fn description_str(
         &self,
         term_width: usize,
         param_width: usize,
+        value_width: usize,
         default_width: usize,
-    ) -> () {
-        if term_width < default_width + param_width {
-            panic!("Printing wider than the terminal looks like shit!");
+    ) -> String {
+        const MIN_DESCRIPTION_WIDTH: usize = 20;
+        let indent = 1 + param_width + value_width + default_width;
+        let descr_width = term_width.saturating_sub(indent).max(MIN_DESCRIPTION_WIDTH);
+        let hint_width = value_width.saturating_sub(2).max(1);
+        let hint = break_long_words(wrap_words(&self.value_hint(), hint_width), hint_width);
+        let descr = wrap_words(self.description, descr_width);
+
+        let mut row = String::new();
+        for line in 0..hint.len().max(descr.len()).max(1) {
+            let (names, default) = if line == 0 {
+                (self.names(), self.get_default_str())
+            } else {
+                (String::new(), String::new())
+            };
+            let hint = hint.get(line).map(String::as_str).unwrap_or("");
+            let descr = descr.get(line).map(String::as_str).unwrap_or("");
+            let line = format!(
+                " {: <4$}{: <5$}{: <6$}{}",
+                names, hint, default, descr, param_width, value_width, default_width
+            );
+            row.push_str(line.trim_end());
+            row.push('\n');
         }
-        print!(" {: <1$}", self.cmdline_expr, param_width);
-        print!("{: <1$}", self.get_default_str(), default_width);
-        let mut cur_len = default_width + param_width;
-        for word in self.description.split_whitespace() {
-            if cur_len + word.len() > term_width {
-                print!("\n{: <1$}", "", default_width + param_width + 1);
-                cur_len = default_width + param_width;
-            }
-            print!("{} ", word);
-            cur_len += word.len() + 1;
+        row
+    }
+
+    /// What kind of value the parameter takes, e.g. `<int>`, `<path>` or
+    /// `one of: csv|npy|json`
+    fn value_hint(&self) -> String {
+        match (&self.default, self.completion) {
+            (ArgType::Flag(_), _) => String::from("flag"),
+            (ArgType::Count(_), _) => String::from("count"),
+            (_, Completion::Choices(words)) => format!("one of: {}", words.join("|")),
+            (ArgType::StringType(_), Completion::Path) => String::from("<path>"),
+            (ArgType::StringType(_), Completion::None) => String::from("<text>"),
+            (ArgType::FloatingNumber(_), _) => String::from("<float>"),
+            (ArgType::SizeType(_) | ArgType::SeedType(_), _) => String::from("<int>"),
+        }
+    }
+
+    /// Whether the value wasn't left at its default, for flags whether they are switched on
+    fn is_set(&self) -> bool {
+        self.source != ValueSource::Default && !matches!(self.value, ArgType::Flag(Some(false)))
+    }
+
+    /// The flag and its aliases as shown in the help, e.g. `-i, --input`
+    fn names(&self) -> String {
+        std::iter::once(self.cmdline_expr)
+            .chain(self.aliases.iter().copied())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    /// Environment variables that set the parameter, one per name, e.g. `FZY_KMEANS_K` and
+    /// `FZY_KMEANS_CLUSTERS` for `-k, --clusters`
+    fn env_vars(&self) -> Vec<String> {
+        std::iter::once(self.cmdline_expr)
+            .chain(self.aliases.iter().copied())
+            .map(|name| ENV_PREFIX.to_string() + &name.trim_start_matches('-').replace('-', "_"))
+            .map(|var| var.to_uppercase())
+            .collect()
+    }
+
+    /// Parse the value of an environment variable into a value of the parameter's type
+    ///
+    /// Flags take 1, true, yes or on and 0, false, no or off. The error names the variable and
+    /// the expected type.
+    ///
+    /// # Arguments
+    ///
+    /// * `var`   - name of the variable, for the error message
+    /// * `input` - value of the variable
+    fn parse_env(&self, var: &str, input: &str) -> Result<ArgType, String> {
+        let parsed = match self.default {
+            ArgType::Flag(_) => match input.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => ArgType::Flag(Some(true)),
+                "0" | "false" | "no" | "off" => ArgType::Flag(Some(false)),
+                _ => ArgType::Flag(None),
+            },
+            ArgType::Count(_) => ArgType::Count(input.parse().ok()),
+            _ => self.default.parse_like(input.to_string()),
+        };
+        if parsed.is_none() {
+            return Err(format!(
+                "Invalid value \"{}\" in {} for {}, expected {}",
+                input,
+                var,
+                self.cmdline_expr,
+                self.default.type_description()
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Parse a value given on the command line into a value of the parameter's type
+    ///
+    /// The error names the parameter as it was given and the expected type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`  - name the parameter was given by, for the error message
+    /// * `input` - value after the name
+    fn parse_cmdline(&self, name: &str, input: &str) -> Result<ArgType, String> {
+        let parsed = self.default.parse_like(input.to_string());
+        if parsed.is_none() {
+            return Err(format!(
+                "invalid value '{}' for {}: expected {}",
+                input,
+                name,
+                self.default.type_name()
+            ));
         }
-        print!("\n");
+        Ok(parsed)
     }
 
     fn get_default_str(&self) -> String {
+        if !self.required && self.default.is_none() {
+            return String::from("none");
+        }
         match &self.default {
             ArgType::StringType(strtype) => match strtype {
                 Some(str) => format!("\"{}\"", str),
@@ -134,22 +374,181 @@ impl CmdlineArgument {
                 Some(num) => format!("{}", num),
                 None => String::from("-"),
             },
+            ArgType::SeedType(seed) => match seed {
+                Some(seed) => format!("{}", seed),
+                None => String::from("-"),
+            },
+            ArgType::Flag(flag) => match flag {
+                Some(val) => format!("{}", val),
+                None => String::from("-"),
+            },
+            ArgType::Count(count) => match count {
+                Some(val) => format!("{}", val),
+                None => String::from("-"),
+            },
+        }
+    }
+}
+
+type ArgConfig = BTreeMap<String, CmdlineArgument>;
+
+/// What the program does, chosen by the first parameter
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    /// Cluster the input, also run when no command is given
+    Fit,
+    /// Assign new data points to the clusters of an exported model
+    Predict,
+    /// Fit a range of cluster numbers and report how well each fits
+    Scan,
+}
+
+impl Command {
+    const ALL: [Command; 3] = [Command::Fit, Command::Predict, Command::Scan];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Fit => "fit",
+            Command::Predict => "predict",
+            Command::Scan => "scan",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Command> {
+        Command::ALL
+            .iter()
+            .copied()
+            .find(|command| command.name() == name)
+    }
+
+    /// One line description for the help
+    fn about(&self) -> &'static str {
+        match self {
+            Command::Fit => "Cluster the data points, the default if no command is given.",
+            Command::Predict => "Assign data points to the clusters of a model exported by fit.",
+            Command::Scan => "Fit a range of cluster numbers and report how well each fits.",
+        }
+    }
+
+    /// Whether the parameter with key `key` can be given to this command
+    fn accepts(&self, key: &str) -> bool {
+        const PREDICT: &[&str] = &[
+            "-i",
+            "-o",
+            "-d",
+            "--no-header",
+            "--write-header",
+            "--no-row-index",
+            "--out-delimiter",
+            "--output-format",
+            "--precision",
+            "--scientific",
+            "--cluster-labels",
+            "--distances",
+            "--memberships",
+            "--no-table",
+            "--model",
+            "--force",
+            "--create-dirs",
+            "--config",
+            "--threads",
+            "--verbose",
+            "--quiet",
+        ];
+        const SCAN: &[&str] = &[
+            "-i",
+            "--input-dir",
+            "-o",
+            "-d",
+            "--no-header",
+            "--columns",
+            "--min-variance",
+            "--remove-outliers",
+            "--sample",
+            "--out-delimiter",
+            "-n",
+            "-q",
+            "--auto-q",
+            "--tol",
+            "--init",
+            "--restarts",
+            "-s",
+            "--metric",
+            "--normalize",
+            "--whiten",
+            "--scan-k",
+            "--validity",
+            "--timings",
+            "--config",
+            "--dump-config",
+            "--dry-run",
+            "--force",
+            "--create-dirs",
+            "--threads",
+            "--verbose",
+            "--quiet",
+        ];
+        match self {
+            Command::Fit => key != "--model",
+            Command::Predict => PREDICT.contains(&key),
+            Command::Scan => SCAN.contains(&key),
+        }
+    }
+}
+
+/// Map every alias to the key of the parameter it names
+fn alias_map(config: &ArgConfig) -> BTreeMap<&'static str, String> {
+    let mut aliases = BTreeMap::new();
+    for (key, value) in config.iter() {
+        // a command may show a parameter under another name than its key
+        for alias in std::iter::once(&value.cmdline_expr).chain(value.aliases) {
+            if alias != key {
+                aliases.insert(*alias, key.clone());
+            }
         }
     }
+    aliases
 }
 
-fn parse_args<'a>(
-    args: &'a Vec<String>,
-) -> Result<(BTreeMap<String, CmdlineArgument>, bool), (BTreeMap<String, CmdlineArgument>, bool)> {
-    let mut conf = BTreeMap::<String, CmdlineArgument>::new();
+/// The parameters of all commands, named and described the way `command` shows them
+///
+/// # Arguments
+///
+/// * `command` - command whose names and descriptions are used
+fn arg_config(command: Command) -> ArgConfig {
+    let mut conf = ArgConfig::new();
 
     conf.insert(
         "-i".to_string(),
         CmdlineArgument {
-            description: "Path to input file.",
+            description: "Path to input file. Can also be given without -i, as the only \
+                          argument that isn't a parameter or its value.",
             cmdline_expr: "-i",
+            aliases: &["--input"],
+            conflicts: &[],
+            priority: 0,
+            required: true,
             default: ArgType::StringType(None),
             value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--input-dir".to_string(),
+        CmdlineArgument {
+            description: "Directory to read every .csv file of instead of a single input file. \
+                          The files are stacked in the order of their names and need the same \
+                          columns.",
+            cmdline_expr: "--input-dir",
+            aliases: &[],
+            conflicts: &["-i", "--watch"],
+            priority: 0,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
         },
     );
     conf.insert(
@@ -157,8 +556,267 @@ fn parse_args<'a>(
         CmdlineArgument {
             description: "Path to output file.",
             cmdline_expr: "-o",
+            aliases: &["--output"],
+            conflicts: &[],
+            priority: 20,
+            required: true,
             default: ArgType::StringType(Some(String::from("out.csv"))),
             value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--precision".to_string(),
+        CmdlineArgument {
+            description: "Largest number of decimal places of the values in the labeled \
+                          output, the centers and the memberships file, at most 17. Trailing \
+                          zeros are dropped. Writes all significant digits if not given. With \
+                          --scientific the number of decimal places of the mantissa.",
+            cmdline_expr: "--precision",
+            aliases: &[],
+            conflicts: &[],
+            priority: 20,
+            required: false,
+            // parsed in run, so invalid values are reported instead of ignored
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--scientific".to_string(),
+        CmdlineArgument {
+            description: "Write the values of the labeled output, the centers and the \
+                          memberships file in scientific notation, e.g. 1.3e-47, with \
+                          --precision decimal places, all significant digits if not given.",
+            cmdline_expr: "--scientific",
+            aliases: &[],
+            conflicts: &[],
+            priority: 20,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--output-format".to_string(),
+        CmdlineArgument {
+            description: "Comma-separated formats of the -o output. \"csv\" writes the data \
+                          points with their cluster, \"npy\" the same as a NumPy array and \
+                          \"json\" labels, memberships and centers as one JSON document. With \
+                          several formats, -o is the base name and the extension of each \
+                          format is appended.",
+            cmdline_expr: "--output-format",
+            aliases: &[],
+            conflicts: &[],
+            priority: 20,
+            required: true,
+            default: ArgType::StringType(Some(String::from("csv"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&["csv", "npy", "json"]),
+        },
+    );
+    #[cfg(feature = "watch")]
+    conf.insert(
+        "--watch".to_string(),
+        CmdlineArgument {
+            description: "Keep running and cluster again whenever the input file changes.",
+            cmdline_expr: "--watch",
+            aliases: &[],
+            conflicts: &[],
+            priority: 4,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--config".to_string(),
+        CmdlineArgument {
+            description: "Path to a TOML file with parameters, named like their long form \
+                          without dashes (input = \"data.csv\", clusters = 4, ...). \
+                          Parameters given on the command line take precedence.",
+            cmdline_expr: "--config",
+            aliases: &[],
+            conflicts: &[],
+            priority: 0,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--dump-config".to_string(),
+        CmdlineArgument {
+            description: "Path to write the effective parameters to as a TOML file that \
+                          --config can read, including the seed that was used.",
+            cmdline_expr: "--dump-config",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--force".to_string(),
+        CmdlineArgument {
+            description: "Overwrite output files that already exist instead of refusing to run.",
+            cmdline_expr: "--force",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--create-dirs".to_string(),
+        CmdlineArgument {
+            description: "Create missing directories of output files.",
+            cmdline_expr: "--create-dirs",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--dry-run".to_string(),
+        CmdlineArgument {
+            description: "Check the parameters, summarize every input column and print the \
+                          resolved configuration without clustering or writing any output.",
+            cmdline_expr: "--dry-run",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--min-variance".to_string(),
+        CmdlineArgument {
+            description: "Leave out clustered columns whose variance is below this, e.g. 1e-6 \
+                          to drop constant columns.",
+            cmdline_expr: "--min-variance",
+            aliases: &[],
+            conflicts: &[],
+            priority: 3,
+            required: false,
+            default: ArgType::FloatingNumber(None),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--remove-outliers".to_string(),
+        CmdlineArgument {
+            description: "Leave out data points that are further than this from the median of \
+                          a clustered column, in units of its median absolute deviation \
+                          (modified z-score), e.g. 3.5. The row index of the output keeps the \
+                          row numbers of the input.",
+            cmdline_expr: "--remove-outliers",
+            aliases: &[],
+            conflicts: &[],
+            priority: 3,
+            required: false,
+            default: ArgType::FloatingNumber(None),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--sample".to_string(),
+        CmdlineArgument {
+            description: "Fit the centers on a random share of the data points, like 0.05 for \
+                          5%, drawn with the seed. Every data point is still assigned to the \
+                          clusters in the output.",
+            cmdline_expr: "--sample",
+            aliases: &[],
+            conflicts: &[],
+            priority: 3,
+            required: false,
+            default: ArgType::FloatingNumber(None),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--columns".to_string(),
+        CmdlineArgument {
+            description: "Comma-separated names or zero-based indices of the columns to cluster \
+                          on. All other columns are copied to the output unchanged. Uses all \
+                          columns if not given.",
+            cmdline_expr: "--columns",
+            aliases: &[],
+            conflicts: &[],
+            priority: 3,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--normalize".to_string(),
+        CmdlineArgument {
+            description: "Scale the clustered columns before clustering. One of \"zscore\" \
+                          (zero mean, unit variance), \"minmax\" (range [0, 1]) or \"none\". \
+                          Centers are reported in the original units.",
+            cmdline_expr: "--normalize",
+            aliases: &[],
+            conflicts: &[],
+            priority: 3,
+            required: true,
+            default: ArgType::StringType(Some(String::from("none"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&["zscore", "minmax", "none"]),
+        },
+    );
+    #[cfg(feature = "linalg")]
+    conf.insert(
+        "--whiten".to_string(),
+        CmdlineArgument {
+            description: "Decorrelate the clustered columns and scale them to unit variance \
+                          (ZCA whitening) before clustering, so correlated features don't \
+                          dominate the distances. Centers are reported in the original units.",
+            cmdline_expr: "--whiten",
+            aliases: &[],
+            conflicts: &["--normalize"],
+            priority: 3,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
         },
     );
     conf.insert(
@@ -166,17 +824,30 @@ fn parse_args<'a>(
         CmdlineArgument {
             description: "Number of Clusters",
             cmdline_expr: "-k",
+            aliases: &["--clusters"],
+            conflicts: &[],
+            priority: 10,
+            required: true,
             default: ArgType::SizeType(Some(5)),
             value: ArgType::SizeType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
         },
     );
     conf.insert(
         "-n".to_string(),
         CmdlineArgument {
-            description: "Upper Bound of Iteration number.",
+            description: "Upper Bound of Iteration number. Fewer iterations are run if the \
+                          centers converge according to --tol.",
             cmdline_expr: "-n",
+            aliases: &["--iterations"],
+            conflicts: &[],
+            priority: 11,
+            required: true,
             default: ArgType::SizeType(Some(10)),
             value: ArgType::SizeType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
         },
     );
     conf.insert(
@@ -184,122 +855,5302 @@ fn parse_args<'a>(
         CmdlineArgument {
             description: "Fuzzyfier constant for membership calculation",
             cmdline_expr: "-q",
+            aliases: &["--fuzzifier"],
+            conflicts: &[],
+            priority: 13,
+            required: true,
             default: ArgType::FloatingNumber(Some(2.0)),
             value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
         },
     );
-
-    for val in conf.values_mut() {
-        val.value = val.default.clone();
-    }
-
-    for (i, arg) in args.iter().enumerate() {
-        if arg == &String::from("-h") || arg == &String::from("--help") {
-            return Err((conf, true));
-        }
-        if i + 1 < args.len() {
-            if conf.contains_key(arg) {
-                let mut tmp = conf.get_mut(arg).unwrap();
-                tmp.value = match tmp.default {
-                    ArgType::FloatingNumber(_) => ArgType::float_from_str(args[i + 1].clone()),
-                    ArgType::StringType(_) => ArgType::string_from_str(args[i + 1].clone()),
-                    ArgType::SizeType(_) => ArgType::size_from_str(args[i + 1].clone()),
-                }
-            }
-        }
-    }
-
-    let mut err = false;
-    for (_, arg) in conf.iter() {
-        if arg.value.is_none() {
-            err = true;
-        }
-    }
-
-    if !err {
-        Ok((conf, false))
-    } else {
-        Err((conf, false))
-    }
-}
-
-fn print_help(config: BTreeMap<String, CmdlineArgument>) {
-    const PARAM_TITLE_STR: &str = "Parameter";
-    const H_ITEM_SEP: usize = 2;
-
-    let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
-    let mut param_len: usize = 0;
-    let mut descr_len: usize = 0;
-    let mut default_len: usize = 0;
-
-    for (key, value) in config.iter() {
-        if param_len < key.len() {
-            param_len = key.len();
-        }
-        if default_len < value.get_default_str().len() {
-            default_len = value.get_default_str().len();
-        }
-        if descr_len < value.description.len() {
-            descr_len = value.description.len();
-        }
-    }
-    param_len = param_len + H_ITEM_SEP;
-    default_len = default_len + H_ITEM_SEP;
-
-    if param_len < PARAM_TITLE_STR.len() {
-        param_len = PARAM_TITLE_STR.len() + H_ITEM_SEP;
-    }
-
-    println!("USAGE: ");
-    println!("Parameters without default values are required parameters.");
-    print!(
-        " {: <3$}{: <4$}{}\n",
-        PARAM_TITLE_STR, "Default", "Description", param_len, default_len
+    conf.insert(
+        "--auto-q".to_string(),
+        CmdlineArgument {
+            description: "Choose the fuzzifier from the spread of the distances between data \
+                          points instead of -q: close to 1 where distances are all alike, as in \
+                          many dimensions, and up to 2 for clearly separated data.",
+            cmdline_expr: "--auto-q",
+            aliases: &[],
+            conflicts: &["-q"],
+            priority: 13,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
     );
-    print!("{:-<1$}\n", "", term_width);
-    for (_, value) in config.iter() {
-        value.print_description_str(term_width, param_len, default_len);
-    }
-}
-
-fn main() {
-    match parse_args(&env::args().collect()) {
-        Err((args, print)) => {
-            if print {
-                print_help(args);
-            } else {
-                println!("Missing Parameters");
-                for (key, arg) in args {
-                    if arg.value.is_none() {
-                        println!("Parameter {} needs to be provided", key);
-                    }
-                }
-            }
-        }
-        Ok((args, _)) => {
-            let infname = args["-i"].value.get_str().unwrap();
-            let ofname = args["-o"].value.get_str().unwrap();
-            let n_iter = args["-n"].value.get_size().unwrap();
-            let n_clusters = args["-k"].value.get_size().unwrap();
-            let fuzzifier = args["-q"].value.get_flt().unwrap();
-
-            let input_vals: Array2<f64> = read_csv(infname);
-            let clusters = algo::cluster_k_means_fuzzy(n_clusters, n_iter, fuzzifier, &input_vals);
-            let out_vals = algo::compute_nearest(&input_vals, &clusters);
-            let mut memberships = Array2::<f64>::zeros((out_vals.dim().0, n_clusters));
-            algo::compute_memberships(fuzzifier, &input_vals, &clusters, &mut memberships);
-
-            to_csv(out_vals, String::from(ofname), b';');
-        }
-    }
-    // let n_clusters: usize = 3;
-    // let n_iter: usize = 10;
-    // let fuzzifier: f64 = 2.0;
-    // let input_vals: Array2<f64> = read_csv(String::from("files/sample_data.csv"));
-    // let clusters = algo::cluster_k_means_fuzzy(n_clusters, n_iter, fuzzifier, &input_vals);
-    // let out_vals = algo::compute_nearest(&input_vals, &clusters);
-    // let mut memberships = Array2::<f64>::zeros((out_vals.dim().0, n_clusters));
-    // algo::compute_memberships(fuzzifier, &input_vals, &clusters, &mut memberships);
-
-    // to_csv(out_vals, String::from("files/predicted_classes.csv"), b';');
+    conf.insert(
+        "-d".to_string(),
+        CmdlineArgument {
+            description: "Field delimiter of the input file. A single \
+                          character such as \",\" or \";\", or \"tab\".",
+            cmdline_expr: "-d",
+            aliases: &["--delimiter"],
+            conflicts: &[],
+            priority: 1,
+            required: true,
+            default: ArgType::StringType(Some(String::from(";"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--out-delimiter".to_string(),
+        CmdlineArgument {
+            description: "Field delimiter of all written csv files. Defaults to the input \
+                          delimiter.",
+            cmdline_expr: "--out-delimiter",
+            aliases: &[],
+            conflicts: &[],
+            priority: 21,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--no-header".to_string(),
+        CmdlineArgument {
+            description: "The input file has no header line, so its first line is data. The \
+                          output is written without a header as well unless --write-header is \
+                          given.",
+            cmdline_expr: "--no-header",
+            aliases: &[],
+            conflicts: &[],
+            priority: 2,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--write-header".to_string(),
+        CmdlineArgument {
+            description: "Write a header line to the output file even if the input has none. \
+                          Columns are named feature_<j> and cluster.",
+            cmdline_expr: "--write-header",
+            aliases: &[],
+            conflicts: &[],
+            priority: 22,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--metric".to_string(),
+        CmdlineArgument {
+            description: "Distance between data points and cluster centers. One of \
+                          \"euclidean\", \"manhattan\", \"cosine\" or \"chebyshev\".",
+            cmdline_expr: "--metric",
+            aliases: &[],
+            conflicts: &[],
+            priority: 15,
+            required: true,
+            default: ArgType::StringType(Some(String::from("euclidean"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&["euclidean", "manhattan", "cosine", "chebyshev"]),
+        },
+    );
+    conf.insert(
+        "--init".to_string(),
+        CmdlineArgument {
+            description: "How to choose the initial centers. \"random\" draws them within the \
+                          range of the data, \"kmeans++\" spreads them out over the data points, \
+                          \"sample\" picks random data points, \"quantile\" places them at evenly \
+                          spaced quantiles of every feature and \"provided:<path>\" reads them \
+                          from a csv file with one row per cluster and one column per feature.",
+            cmdline_expr: "--init",
+            aliases: &[],
+            conflicts: &[],
+            priority: 16,
+            required: true,
+            default: ArgType::StringType(Some(String::from("random"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&["random", "kmeans++", "sample", "quantile"]),
+        },
+    );
+    conf.insert(
+        "--init-centers".to_string(),
+        CmdlineArgument {
+            description: "Path to a csv file with the initial centers, one row per cluster and \
+                          one column per feature. Files written by --centers can be used as \
+                          they are. The same as --init provided:<path>.",
+            cmdline_expr: "--init-centers",
+            aliases: &[],
+            conflicts: &["--init"],
+            priority: 16,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--restarts".to_string(),
+        CmdlineArgument {
+            description: "Number of fits from different initialisations. The fit with the \
+                          lowest objective is kept.",
+            cmdline_expr: "--restarts",
+            aliases: &[],
+            conflicts: &[],
+            priority: 17,
+            required: true,
+            default: ArgType::SizeType(Some(1)),
+            value: ArgType::SizeType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--scan-k".to_string(),
+        CmdlineArgument {
+            description: "Fit every number of clusters in an inclusive range like 2..10 instead \
+                          of -k and write a report of objective, FPC and Xie-Beni index per k to \
+                          -o (or --scan-out). The k that is best by --validity is suggested.",
+            cmdline_expr: "--scan-k",
+            aliases: &[],
+            conflicts: &[],
+            priority: 10,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--validity".to_string(),
+        CmdlineArgument {
+            description: "Validity index that picks the suggested k of --scan-k. One of \
+                          \"xie-beni\", \"davies-bouldin\" (lowest is best), \"fpc\", \
+                          \"silhouette\" or \"calinski-harabasz\" (highest is best).",
+            cmdline_expr: "--validity",
+            aliases: &[],
+            conflicts: &[],
+            priority: 10,
+            required: true,
+            default: ArgType::StringType(Some(String::from("xie-beni"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&[
+                "fpc",
+                "xie-beni",
+                "davies-bouldin",
+                "silhouette",
+                "calinski-harabasz",
+            ]),
+        },
+    );
+    conf.insert(
+        "--fit-best".to_string(),
+        CmdlineArgument {
+            description: "With --scan-k, keep the fit of the suggested k and write its labeled \
+                          data points to -o as usual.",
+            cmdline_expr: "--fit-best",
+            aliases: &[],
+            conflicts: &[],
+            priority: 10,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--scan-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write the --scan-k report to. Defaults to -o unless \
+                          --fit-best is given.",
+            cmdline_expr: "--scan-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 20,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    #[cfg(feature = "parallel")]
+    conf.insert(
+        "--threads".to_string(),
+        CmdlineArgument {
+            description: "Number of threads to compute with. Uses all cores if not given, 1 runs \
+                          everything serially.",
+            cmdline_expr: "--threads",
+            aliases: &[],
+            conflicts: &[],
+            priority: 17,
+            required: false,
+            default: ArgType::SizeType(None),
+            value: ArgType::SizeType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--merge-threshold".to_string(),
+        CmdlineArgument {
+            description: "Merge clusters whose centers are closer than this after fitting.",
+            cmdline_expr: "--merge-threshold",
+            aliases: &[],
+            conflicts: &[],
+            priority: 18,
+            required: false,
+            default: ArgType::FloatingNumber(None),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--auto-k".to_string(),
+        CmdlineArgument {
+            description: "Refit with the merged centers until --merge-threshold merges no more \
+                          clusters, choosing the number of clusters automatically. To choose it \
+                          by a validity index instead, use --scan-k <range> --validity <index> \
+                          --fit-best.",
+            cmdline_expr: "--auto-k",
+            aliases: &[],
+            conflicts: &[],
+            priority: 19,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--hard".to_string(),
+        CmdlineArgument {
+            description: "Run crisp k-means, where every data point belongs to exactly one \
+                          cluster, instead of fuzzy k-means. Memberships are written as 0 or \
+                          1. Uses the euclidean metric and a single fit.",
+            cmdline_expr: "--hard",
+            aliases: &[],
+            conflicts: &[
+                "-q",
+                "--auto-q",
+                "--restarts",
+                "--scan-k",
+                "--merge-threshold",
+                "--metric",
+            ],
+            priority: 10,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--density-weighted".to_string(),
+        CmdlineArgument {
+            description: "Weigh every data point by the density of the data around it, so dense \
+                          regions pull harder on the centers than outliers. Estimates the \
+                          density with a Gaussian kernel of Silverman's bandwidth, which takes \
+                          time quadratic in the number of data points. Uses the euclidean \
+                          metric and a single fit over all -n iterations.",
+            cmdline_expr: "--density-weighted",
+            aliases: &[],
+            conflicts: &[
+                "--hard",
+                "--restarts",
+                "--scan-k",
+                "--metric",
+                "--init",
+                "--init-centers",
+                "--tol",
+            ],
+            priority: 10,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--sort-clusters-by-size".to_string(),
+        CmdlineArgument {
+            description: "Number clusters by size, so cluster 0 holds the most data points.",
+            cmdline_expr: "--sort-clusters-by-size",
+            aliases: &[],
+            conflicts: &[],
+            priority: 19,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--tol".to_string(),
+        CmdlineArgument {
+            description: "Stop iterating once no cluster center moves further than this.",
+            cmdline_expr: "--tol",
+            aliases: &[],
+            conflicts: &[],
+            priority: 12,
+            required: true,
+            default: ArgType::FloatingNumber(Some(0.0)),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "-s".to_string(),
+        CmdlineArgument {
+            description: "Seed for the center initialisation. A random seed is \
+                          drawn and printed to stderr if none is given.",
+            cmdline_expr: "-s",
+            aliases: &["--seed"],
+            conflicts: &[],
+            priority: 14,
+            required: false,
+            default: ArgType::SeedType(None),
+            value: ArgType::SeedType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+
+    conf.insert(
+        "--ground-truth".to_string(),
+        CmdlineArgument {
+            description: "Path to a csv file whose last column holds the true class of every \
+                          data point. Prints a confusion matrix after clustering.",
+            cmdline_expr: "--ground-truth",
+            aliases: &[],
+            conflicts: &[],
+            priority: 30,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--linkage-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write the average linkage distances between all pairs of \
+                          clusters to.",
+            cmdline_expr: "--linkage-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 32,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--confusion-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write the confusion matrix to. Requires --ground-truth.",
+            cmdline_expr: "--confusion-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 31,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--json-summary".to_string(),
+        CmdlineArgument {
+            description: "Print a machine-readable JSON summary of the run as the last line \
+                          of stdout.",
+            cmdline_expr: "--json-summary",
+            aliases: &[],
+            conflicts: &[],
+            priority: 24,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--json-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write the JSON summary to instead of stdout.",
+            cmdline_expr: "--json-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 25,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--multi-label-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write all clusters with a membership of at least \
+                          --multi-label-cutoff per data point to.",
+            cmdline_expr: "--multi-label-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 26,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--multi-label-cutoff".to_string(),
+        CmdlineArgument {
+            description: "Minimum membership for a cluster to be reported in the multi-label \
+                          output.",
+            cmdline_expr: "--multi-label-cutoff",
+            aliases: &[],
+            conflicts: &[],
+            priority: 27,
+            required: true,
+            default: ArgType::FloatingNumber(Some(0.2)),
+            value: ArgType::FloatingNumber(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--multi-label-format".to_string(),
+        CmdlineArgument {
+            description: "Layout of the multi-label output. \"long\" writes one \
+                          point_id;cluster;membership row per qualifying cluster, \"wide\" one \
+                          row per data point with a membership column per cluster.",
+            cmdline_expr: "--multi-label-format",
+            aliases: &[],
+            conflicts: &[],
+            priority: 27,
+            required: true,
+            default: ArgType::StringType(Some(String::from("long"))),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Choices(&["long", "wide"]),
+        },
+    );
+    conf.insert(
+        "--multi-label-argmax".to_string(),
+        CmdlineArgument {
+            description: "Report the cluster with the highest membership for data points \
+                          without any cluster above the cutoff.",
+            cmdline_expr: "--multi-label-argmax",
+            aliases: &[],
+            conflicts: &[],
+            priority: 27,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--no-row-index".to_string(),
+        CmdlineArgument {
+            description: "Don't prepend the row number of every data point to the output file.",
+            cmdline_expr: "--no-row-index",
+            aliases: &[],
+            conflicts: &[],
+            priority: 22,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--source-column".to_string(),
+        CmdlineArgument {
+            description: "Append the name of the input file every data point was read from to \
+                          the output file, see --input-dir.",
+            cmdline_expr: "--source-column",
+            aliases: &[],
+            conflicts: &[],
+            priority: 22,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--distances".to_string(),
+        CmdlineArgument {
+            description: "Append a dist_to_center column with the distance of every data point \
+                          to its cluster center in the chosen metric.",
+            cmdline_expr: "--distances",
+            aliases: &[],
+            conflicts: &[],
+            priority: 22,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--centers".to_string(),
+        CmdlineArgument {
+            description: "Path to write the fitted cluster centers to.",
+            cmdline_expr: "--centers",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--memberships".to_string(),
+        CmdlineArgument {
+            description: "Path to write the membership of every data point in every cluster to.",
+            cmdline_expr: "--memberships",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--cluster-labels".to_string(),
+        CmdlineArgument {
+            description: "Names to write instead of cluster numbers in the csv output. Either \
+                          one name per cluster in order, like idle,load,spike, or pairs like \
+                          0=low,1=medium,2=high, where clusters without a name are called \
+                          cluster_<j>.",
+            cmdline_expr: "--cluster-labels",
+            aliases: &["--label-names"],
+            conflicts: &[],
+            priority: 21,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--distance-matrix-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write the distance of every data point to every cluster \
+                          center to, in the chosen metric.",
+            cmdline_expr: "--distance-matrix-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--convergence-out".to_string(),
+        CmdlineArgument {
+            description: "Path to write how far every cluster center moved in each iteration \
+                          to.",
+            cmdline_expr: "--convergence-out",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--no-table".to_string(),
+        CmdlineArgument {
+            description: "Don't print the number of data points per cluster.",
+            cmdline_expr: "--no-table",
+            aliases: &[],
+            conflicts: &[],
+            priority: 24,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--timings".to_string(),
+        CmdlineArgument {
+            description: "Print how long reading, normalization, fitting (and every restart), \
+                          assignment and writing took, and the peak memory use. With \
+                          --output-format json the timings up to writing are part of the output \
+                          instead.",
+            cmdline_expr: "--timings",
+            aliases: &[],
+            conflicts: &[],
+            priority: 39,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--verbose".to_string(),
+        CmdlineArgument {
+            description: "Print a summary of the clustering result and the progress of every \
+                          iteration. Repeat (-vv, -vvv) for more detail.",
+            cmdline_expr: "--verbose",
+            aliases: &["-v"],
+            conflicts: &[],
+            priority: 40,
+            required: true,
+            default: ArgType::Count(Some(0)),
+            value: ArgType::Count(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+    conf.insert(
+        "--quiet".to_string(),
+        CmdlineArgument {
+            description: "Print nothing but errors and explicitly requested output.",
+            cmdline_expr: "--quiet",
+            aliases: &[],
+            conflicts: &[],
+            priority: 41,
+            required: true,
+            default: ArgType::Flag(Some(false)),
+            value: ArgType::Flag(None),
+            source: ValueSource::Default,
+            completion: Completion::None,
+        },
+    );
+
+    conf.insert(
+        "--export-model".to_string(),
+        CmdlineArgument {
+            description: "Path to write the fitted model to as JSON, for assigning new data \
+                          points with the predict command.",
+            cmdline_expr: "--export-model",
+            aliases: &[],
+            conflicts: &[],
+            priority: 23,
+            required: false,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    conf.insert(
+        "--model".to_string(),
+        CmdlineArgument {
+            description: "Path to a model written by fit --export-model.",
+            cmdline_expr: "--model",
+            aliases: &[],
+            conflicts: &[],
+            priority: 1,
+            required: true,
+            default: ArgType::StringType(None),
+            value: ArgType::StringType(None),
+            source: ValueSource::Default,
+            completion: Completion::Path,
+        },
+    );
+    if command == Command::Scan {
+        let range = conf.get_mut("--scan-k").unwrap();
+        range.description = "Inclusive range of cluster numbers to fit, like 2..10. The k that \
+                             is best by --validity is suggested.";
+        range.cmdline_expr = "--k-range";
+        range.aliases = &["--scan-k"];
+        range.required = true;
+        let output = conf.get_mut("-o").unwrap();
+        output.description = "Path to write the objective, FPC and Xie-Beni index per k to.";
+        output.default = ArgType::StringType(Some(String::from("-")));
+    }
+    conf
+}
+
+/// Parse the command and its parameters
+///
+/// The returned config holds the parameters of all commands, those the command doesn't accept
+/// keep their defaults. The flag is set if the help was requested.
+///
+/// # Arguments
+///
+/// * `args` - command line, starting with the program name
+#[allow(clippy::type_complexity)]
+fn parse_args(args: &[String]) -> Result<(Command, ArgConfig, bool), (Command, ArgConfig, bool)> {
+    // without a command, the parameters are those of fit
+    let (command, first) = match args.get(1).and_then(|arg| Command::from_name(arg)) {
+        Some(command) => (command, 2),
+        None => (Command::Fit, 1),
+    };
+    let mut conf = arg_config(command);
+
+    for val in conf.values_mut() {
+        val.value = val.default.clone();
+    }
+    // parameters of other commands can't be given, but keep their defaults
+    let (mut conf, other): (ArgConfig, ArgConfig) =
+        conf.into_iter().partition(|(key, _)| command.accepts(key));
+
+    let aliases = alias_map(&conf);
+    // name each parameter was last given by
+    let mut given_as: BTreeMap<String, &str> = BTreeMap::new();
+    let mut bad_value = false;
+    // parameters whose value didn't parse, not reported as missing on top of that
+    let mut invalid: Vec<String> = Vec::new();
+    let mut is_value = false;
+    let mut positional: Option<&String> = None;
+    // the first element is the program name
+    for (i, arg) in args.iter().enumerate().skip(first) {
+        // the previous parameter takes this as its value, even if it looks like a parameter
+        if std::mem::take(&mut is_value) {
+            continue;
+        }
+        if arg == &String::from("-h") || arg == &String::from("--help") {
+            return Err((command, conf, true));
+        }
+        // -vv is the same as -v -v
+        let repeats =
+            if arg.len() > 2 && arg.starts_with("-v") && arg[1..].bytes().all(|c| c == b'v') {
+                arg.len() - 1
+            } else {
+                1
+            };
+        // --key=value is the same as --key value
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if arg.starts_with("--") => (name, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        let mut key = match aliases.get(name) {
+            Some(key) => key.as_str(),
+            None if name.starts_with("-v") && repeats == name.len() - 1 => "--verbose",
+            None => name,
+        }
+        .to_string();
+        // --no-x switches the flag --x off, and --x the flag --no-x
+        let mut negated = false;
+        if !conf.contains_key(&key) && name.starts_with("--") {
+            let flipped = match name.strip_prefix("--no-") {
+                Some(rest) => format!("--{}", rest),
+                None => format!("--no-{}", &name[2..]),
+            };
+            let flipped = aliases.get(flipped.as_str()).cloned().unwrap_or(flipped);
+            if conf
+                .get(&flipped)
+                .is_some_and(|arg| matches!(arg.default, ArgType::Flag(_)))
+            {
+                key = flipped;
+                negated = true;
+            }
+        }
+        if let Some(tmp) = conf.get_mut(&key) {
+            if let Some(previous) = given_as.insert(key.clone(), name) {
+                // the last of several flags wins without surprise, e.g. --no-quiet after --quiet
+                if previous != name && !matches!(tmp.default, ArgType::Count(_) | ArgType::Flag(_))
+                {
+                    eprintln!(
+                        "Warning: {} and {} name the same parameter, using the last one ({})",
+                        previous, name, name
+                    );
+                }
+            }
+            let takes_value = !matches!(tmp.default, ArgType::Flag(_) | ArgType::Count(_));
+            if let Some(value) = inline_value {
+                if !takes_value {
+                    eprintln!("Parameter {} takes no value", name);
+                    bad_value = true;
+                } else if value.is_empty() && !matches!(tmp.default, ArgType::StringType(_)) {
+                    eprintln!("Parameter {} needs a value after \"=\"", name);
+                    bad_value = true;
+                } else {
+                    match tmp.parse_cmdline(name, value) {
+                        Ok(value) => tmp.value = value,
+                        Err(msg) => {
+                            eprintln!("{}", msg);
+                            bad_value = true;
+                            invalid.push(key.clone());
+                        }
+                    }
+                }
+            // flags take no value
+            } else if let ArgType::Flag(_) = tmp.default {
+                tmp.value = ArgType::Flag(Some(!negated));
+            } else if let ArgType::Count(count) = tmp.value {
+                tmp.value = ArgType::Count(Some(count.unwrap_or(0) + repeats));
+            } else if i + 1 < args.len() {
+                is_value = true;
+                match tmp.parse_cmdline(name, &args[i + 1]) {
+                    Ok(value) => tmp.value = value,
+                    Err(msg) => {
+                        eprintln!("{}", msg);
+                        bad_value = true;
+                        invalid.push(key.clone());
+                    }
+                }
+            } else {
+                eprintln!("Parameter {} needs a value", name);
+                bad_value = true;
+                invalid.push(key.clone());
+            }
+        } else if arg.starts_with('-') && arg != "-" && arg.parse::<f64>().is_err() {
+            let names = conf.values().flat_map(|value| {
+                std::iter::once(value.cmdline_expr).chain(value.aliases.iter().copied())
+            });
+            match closest_name(name, names) {
+                Some(suggestion) => eprintln!(
+                    "unknown argument '{}', did you mean '{}'?",
+                    name, suggestion
+                ),
+                None => eprintln!("unknown argument '{}'", name),
+            }
+            bad_value = true;
+        // a bare argument is the input file
+        } else if let Some(previous) = positional.replace(arg) {
+            eprintln!(
+                "Only one input file can be given without -i, got {} and {}",
+                previous, arg
+            );
+            bad_value = true;
+        }
+    }
+    if let Some(input) = positional {
+        if let Some(option) = given_as.get("-i") {
+            eprintln!(
+                "The input file is given both as {} and with {}",
+                input, option
+            );
+            bad_value = true;
+        } else {
+            conf.get_mut("-i").unwrap().value = ArgType::StringType(Some(input.clone()));
+            given_as.insert(String::from("-i"), "-i");
+        }
+    }
+
+    for (key, arg) in conf.iter_mut() {
+        if given_as.contains_key(key) {
+            arg.source = ValueSource::CommandLine;
+            continue;
+        }
+        // the first name with a variable set wins, empty variables count as unset
+        let found = arg
+            .env_vars()
+            .into_iter()
+            .find_map(|var| match env::var(&var) {
+                Ok(value) if value.is_empty() => None,
+                Ok(value) => Some((var, Ok(value))),
+                Err(env::VarError::NotPresent) => None,
+                Err(err) => {
+                    let msg = format!("{}: {}", var, err);
+                    Some((var, Err(msg)))
+                }
+            });
+        let (var, value) = match found {
+            Some(found) => found,
+            None => continue,
+        };
+        match value.and_then(|value| arg.parse_env(&var, &value)) {
+            Ok(value) => {
+                arg.value = value;
+                arg.source = ValueSource::Env(var);
+            }
+            Err(msg) => {
+                eprintln!("{}", msg);
+                bad_value = true;
+            }
+        }
+    }
+
+    let mut config_failed = false;
+    if let Ok(config_fname) = conf["--config"].value.get_str() {
+        match RunConfig::read(&config_fname) {
+            Ok(config) => {
+                for (key, value) in config.values() {
+                    // the command line wins, options of other commands are left alone
+                    if !given_as.contains_key(key) {
+                        if let Some(arg) = conf.get_mut(key) {
+                            arg.value = value;
+                            arg.source = ValueSource::Config;
+                        }
+                    }
+                }
+            }
+            Err(msg) => {
+                eprintln!("{}", msg);
+                config_failed = true;
+            }
+        }
+    }
+
+    let set: Vec<String> = conf
+        .iter()
+        .filter(|(_, arg)| arg.is_set())
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in &set {
+        for other in conf[key]
+            .conflicts
+            .iter()
+            .filter(|other| set.iter().any(|k| k == *other))
+        {
+            // names the value wasn't given by on the command line come with its source
+            let name = |key: &str| match given_as.get(key) {
+                Some(name) => name.to_string(),
+                None => format!("{} (from {})", conf[key].cmdline_expr, conf[key].source),
+            };
+            eprintln!(
+                "{} can't be used together with {}, it would have no effect",
+                name(other),
+                name(key)
+            );
+            bad_value = true;
+        }
+    }
+
+    // a directory of input files stands in for -i
+    if conf.get("--input-dir").is_some_and(|arg| arg.is_set()) {
+        conf.get_mut("-i").unwrap().required = false;
+    }
+    let missing: Vec<&str> = conf
+        .iter()
+        .filter(|(key, arg)| arg.required && arg.value.is_none() && !invalid.contains(key))
+        .map(|(_, arg)| arg.cmdline_expr)
+        .collect();
+    // a config file that failed to load may have been meant to provide them
+    if !missing.is_empty() && !config_failed {
+        eprintln!("Missing Parameters");
+        for name in &missing {
+            eprintln!("Parameter {} needs to be provided", name);
+        }
+    }
+    let err = bad_value || config_failed || !missing.is_empty();
+
+    conf.extend(other);
+    if !err {
+        Ok((command, conf, false))
+    } else {
+        Err((command, conf, false))
+    }
+}
+
+/// Number of single character insertions, deletions and substitutions that turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // distances from the first i characters of a to every prefix of b
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// The name closest to `arg` by edit distance, if it is close enough to be a likely typo
+fn closest_name<'a>(arg: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (arg.len() / 3).max(1);
+    let lower = arg.to_lowercase();
+    names
+        .map(|name| (edit_distance(arg, name), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        // among equally close names, prefer one that only differs in case
+        .min_by_key(|(distance, name)| {
+            (
+                edit_distance(&lower, &name.to_lowercase()),
+                *distance,
+                *name,
+            )
+        })
+        .map(|(_, name)| name)
+}
+
+/// Break `text` into lines of at most `width` characters
+///
+/// Lines are broken between words and after the `|` of lists of choices. Words longer than
+/// `width` are kept whole.
+///
+/// # Arguments
+///
+/// * `text`  - text to wrap, runs of whitespace count as one space
+/// * `width` - maximum length of a line
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        for (i, piece) in word.split_inclusive('|').enumerate() {
+            // a word starts with a space, the rest of a list of choices continues without one
+            let sep = if i == 0 && !line.is_empty() { " " } else { "" };
+            if !line.is_empty() && line.len() + sep.len() + piece.len() > width {
+                lines.push(std::mem::take(&mut line));
+            } else {
+                line.push_str(sep);
+            }
+            line.push_str(piece);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Break lines of `wrap_words` that are longer than `width`, after a hyphen if there is one
+///
+/// Keeps a long choice like `davies-bouldin|` within a narrow column.
+///
+/// # Arguments
+///
+/// * `lines` - wrapped lines
+/// * `width` - maximum length of a line
+fn break_long_words(lines: Vec<String>, width: usize) -> Vec<String> {
+    let mut broken = Vec::new();
+    for mut line in lines {
+        while line.len() > width {
+            let at = line[..width].rfind('-').map_or(width, |i| i + 1);
+            let rest = line.split_off(at);
+            broken.push(line);
+            line = rest;
+        }
+        broken.push(line);
+    }
+    broken
+}
+
+fn print_help(command: Command, config: ArgConfig) {
+    let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
+    print!("{}", help_text(command, &config, term_width));
+}
+
+/// The help of `command`, with the table of its parameters fit to `term_width`
+///
+/// # Arguments
+///
+/// * `command`    - command to describe
+/// * `config`     - its parameters
+/// * `term_width` - width of the terminal
+fn help_text(command: Command, config: &ArgConfig, term_width: usize) -> String {
+    const PARAM_TITLE_STR: &str = "Parameter";
+    const VALUE_TITLE_STR: &str = "Value";
+    const H_ITEM_SEP: usize = 2;
+    // longer value hints, i.e. lists of choices, are wrapped within the column
+    const MAX_VALUE_LEN: usize = 12;
+
+    let mut param_len: usize = 0;
+    let mut descr_len: usize = 0;
+    let mut default_len: usize = 0;
+    let mut value_len: usize = VALUE_TITLE_STR.len();
+
+    for value in config.values() {
+        value_len = value_len.max(value.value_hint().len().min(MAX_VALUE_LEN));
+        if param_len < value.names().len() {
+            param_len = value.names().len();
+        }
+        if default_len < value.get_default_str().len() {
+            default_len = value.get_default_str().len();
+        }
+        if descr_len < value.description.len() {
+            descr_len = value.description.len();
+        }
+    }
+    param_len += H_ITEM_SEP;
+    default_len += H_ITEM_SEP;
+    value_len += H_ITEM_SEP;
+
+    if param_len < PARAM_TITLE_STR.len() {
+        param_len = PARAM_TITLE_STR.len() + H_ITEM_SEP;
+    }
+
+    let program = env!("CARGO_PKG_NAME");
+    let mut help = String::new();
+    if command == Command::Fit {
+        help.push_str(&format!(
+            "USAGE: {} [COMMAND] [INPUT] [PARAMETERS]\n\nCommands:\n",
+            program
+        ));
+        let name_len = Command::ALL
+            .iter()
+            .map(|command| command.name().len())
+            .max();
+        for command in Command::ALL {
+            help.push_str(&format!(
+                " {: <2$}{}\n",
+                command.name(),
+                command.about(),
+                name_len.unwrap_or(0) + H_ITEM_SEP
+            ));
+        }
+        help.push_str(&format!(
+            "Run {} COMMAND -h for the parameters of a command.\n\nParameters of fit:\n",
+            program
+        ));
+    } else {
+        help.push_str(&format!(
+            "USAGE: {} {} [INPUT] [PARAMETERS]\n{}\n\n",
+            program,
+            command.name(),
+            command.about()
+        ));
+    }
+    help.push_str("Parameters without default values are required parameters.\n");
+    help.push_str("Parameters with default none are optional.\n");
+    help.push_str(
+        "Flags are switched off by --no-<flag>, or by leaving out the no- of a --no- flag.\n",
+    );
+    help.push_str("Parameters not given can be set by environment variables named like them,\n");
+    help.push_str(&format!(
+        "e.g. {0}K or {0}CLUSTERS for -k. --config takes precedence over them.\n",
+        ENV_PREFIX
+    ));
+    help.push_str(&format!(
+        " {: <3$}{: <4$}{: <5$}Description\n",
+        PARAM_TITLE_STR, VALUE_TITLE_STR, "Default", param_len, value_len, default_len
+    ));
+    help.push_str(&format!("{:-<1$}\n", "", term_width));
+    let mut sorted: Vec<(&String, &CmdlineArgument)> = config.iter().collect();
+    sorted.sort_by_key(|(key, value)| (value.priority, *key));
+    for (_, value) in sorted {
+        help.push_str(&value.description_str(term_width, param_len, value_len, default_len));
+    }
+    help
+}
+
+/// Parameters `command` accepts, in the order of the help
+fn command_params(command: Command) -> Vec<CmdlineArgument> {
+    let mut params: Vec<CmdlineArgument> = arg_config(command)
+        .into_iter()
+        .filter(|(key, _)| command.accepts(key))
+        .map(|(_, value)| value)
+        .collect();
+    params.sort_by_key(|value| value.priority);
+    params
+}
+
+/// First sentence of a description, for the one line shells show next to a name
+fn summary_of(description: &str) -> &str {
+    // a sentence ends at a period followed by a capital, unlike "e.g. 4"
+    let end = description
+        .match_indices(". ")
+        .find(|(i, _)| description[i + 2..].starts_with(char::is_uppercase))
+        .map_or(description.len(), |(i, _)| i);
+    description[..end].trim_end_matches('.')
+}
+
+/// Completion script for `shell`, one of "bash", "zsh" or "fish"
+///
+/// The script is derived from the parameters of every command, so it stays in line with the
+/// parser.
+///
+/// # Arguments
+///
+/// * `shell` - name of the shell the script is for
+fn completion_script(shell: &str) -> Result<String, CliError> {
+    match shell {
+        "bash" => Ok(bash_completion()),
+        "zsh" => Ok(zsh_completion()),
+        "fish" => Ok(fish_completion()),
+        other => Err(CliError::args(format!(
+            "Can't generate completions for \"{}\", expected \"bash\", \"zsh\" or \"fish\"",
+            other
+        ))),
+    }
+}
+
+fn bash_completion() -> String {
+    let program = env!("CARGO_PKG_NAME");
+    let commands: Vec<&str> = Command::ALL.iter().map(|command| command.name()).collect();
+    let function = format!("_{}", program.replace('-', "_"));
+    let mut script = format!(
+        "{0}() {{\n    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    \
+         local command=fit opts\n    if [[ ${{COMP_CWORD}} -gt 1 ]]; then\n        \
+         case \"${{COMP_WORDS[1]}}\" in\n            {1}) command=\"${{COMP_WORDS[1]}}\" ;;\n        \
+         esac\n    fi\n    case \"${{command}}\" in\n",
+        function,
+        commands.join("|")
+    );
+    for command in Command::ALL {
+        let params = command_params(command);
+        let mut opts = vec!["-h", "--help"];
+        let mut paths = Vec::new();
+        let mut free = Vec::new();
+        let mut choices = Vec::new();
+        for param in &params {
+            let names: Vec<&str> = std::iter::once(param.cmdline_expr)
+                .chain(param.aliases.iter().copied())
+                .collect();
+            opts.extend(&names);
+            match (param.completion, &param.default) {
+                (_, ArgType::Flag(_) | ArgType::Count(_)) => {}
+                (Completion::Path, _) => paths.extend(names),
+                (Completion::Choices(words), _) => choices.push((names.join("|"), words.join(" "))),
+                (Completion::None, _) => free.extend(names),
+            }
+        }
+        script.push_str(&format!(
+            "        {})\n            opts=\"{}\"\n            case \"${{prev}}\" in\n",
+            command.name(),
+            opts.join(" ")
+        ));
+        if !paths.is_empty() {
+            script.push_str(&format!(
+                "                {})\n                    \
+                 COMPREPLY=($(compgen -f -- \"${{cur}}\"))\n                    return ;;\n",
+                paths.join("|")
+            ));
+        }
+        for (names, words) in choices {
+            script.push_str(&format!(
+                "                {})\n                    \
+                 COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))\n                    return ;;\n",
+                names, words
+            ));
+        }
+        // nothing sensible to offer, but the value isn't a parameter either
+        if !free.is_empty() {
+            script.push_str(&format!(
+                "                {})\n                    return ;;\n",
+                free.join("|")
+            ));
+        }
+        script.push_str("            esac\n            ;;\n");
+    }
+    script.push_str(&format!(
+        "    esac\n    if [[ \"${{cur}}\" == -* ]]; then\n        \
+         COMPREPLY=($(compgen -W \"${{opts}}\" -- \"${{cur}}\"))\n    \
+         elif [[ ${{COMP_CWORD}} -eq 1 ]]; then\n        \
+         COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\") $(compgen -f -- \"${{cur}}\"))\n    \
+         else\n        COMPREPLY=($(compgen -f -- \"${{cur}}\"))\n    fi\n}}\n\
+         complete -o filenames -F {} {}\n",
+        commands.join(" "),
+        function,
+        program
+    ));
+    script
+}
+
+fn zsh_completion() -> String {
+    let program = env!("CARGO_PKG_NAME");
+    let commands: Vec<&str> = Command::ALL.iter().map(|command| command.name()).collect();
+    let function = format!("_{}", program.replace('-', "_"));
+    let mut script = format!(
+        "#compdef {0}\n\n{1}() {{\n    local command=fit\n    \
+         if (( CURRENT == 2 )) && [[ $PREFIX != -* ]]; then\n        \
+         _alternative 'commands:command:({2})' 'files:input file:_files'\n        return\n    fi\n    \
+         case $words[2] in\n        {3})\n            command=$words[2]\n            \
+         shift words\n            (( CURRENT-- ))\n            ;;\n    esac\n    \
+         case $command in\n",
+        program,
+        function,
+        commands.join(" "),
+        commands.join("|")
+    );
+    for command in Command::ALL {
+        script.push_str(&format!(
+            "        {})\n            _arguments -S \\\n                \
+             '(- *)'{{-h,--help}}'[Show the parameters of {}]' \\\n",
+            command.name(),
+            command.name()
+        ));
+        for param in command_params(command) {
+            // quoted for zsh, which also gives [ ] and : a meaning in the description
+            let description = summary_of(param.description)
+                .replace('\'', "'\\''")
+                .replace('[', "\\[")
+                .replace(']', "\\]")
+                .replace(':', "\\:");
+            let value = std::iter::once(param.cmdline_expr)
+                .chain(param.aliases.iter().copied())
+                .find(|name| name.starts_with("--"))
+                .unwrap_or(param.cmdline_expr)
+                .trim_start_matches('-');
+            let action = match (param.completion, &param.default) {
+                (_, ArgType::Flag(_) | ArgType::Count(_)) => String::new(),
+                (Completion::Path, _) => format!(":{}:_files", value),
+                (Completion::Choices(words), _) => format!(":{}:({})", value, words.join(" ")),
+                (Completion::None, _) => format!(":{}: ", value),
+            };
+            let repeat = if matches!(param.default, ArgType::Count(_)) {
+                "*"
+            } else {
+                ""
+            };
+            for name in std::iter::once(param.cmdline_expr).chain(param.aliases.iter().copied()) {
+                script.push_str(&format!(
+                    "                '{}{}[{}]{}' \\\n",
+                    repeat, name, description, action
+                ));
+            }
+        }
+        script.push_str("                '*:input file:_files'\n            ;;\n");
+    }
+    script.push_str(&format!("    esac\n}}\n\n{} \"$@\"\n", function));
+    script
+}
+
+fn fish_completion() -> String {
+    let program = env!("CARGO_PKG_NAME");
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let mut script = String::new();
+    for command in Command::ALL {
+        script.push_str(&format!(
+            "complete -c {} -n __fish_use_subcommand -f -a {} -d {}\n",
+            program,
+            command.name(),
+            quote(summary_of(command.about()))
+        ));
+    }
+    for command in Command::ALL {
+        // fit is also what runs without a command
+        let condition = match command {
+            Command::Fit => format!(
+                "not __fish_seen_subcommand_from {}",
+                Command::ALL
+                    .iter()
+                    .filter(|other| **other != Command::Fit)
+                    .map(|other| other.name())
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            ),
+            _ => format!("__fish_seen_subcommand_from {}", command.name()),
+        };
+        script.push_str(&format!(
+            "complete -c {} -n {} -s h -l help -d {}\n",
+            program,
+            quote(&condition),
+            quote(&format!("Show the parameters of {}", command.name()))
+        ));
+        for param in command_params(command) {
+            let mut line = format!("complete -c {} -n {}", program, quote(&condition));
+            for name in std::iter::once(param.cmdline_expr).chain(param.aliases.iter().copied()) {
+                match name.strip_prefix("--") {
+                    Some(long) => line.push_str(&format!(" -l {}", long)),
+                    None if name.len() == 2 => line.push_str(&format!(" -s {}", &name[1..])),
+                    None => line.push_str(&format!(" -o {}", &name[1..])),
+                }
+            }
+            line.push_str(&format!(" -d {}", quote(summary_of(param.description))));
+            match (param.completion, &param.default) {
+                (_, ArgType::Flag(_) | ArgType::Count(_)) => {}
+                (Completion::Path, _) => line.push_str(" -r -F"),
+                (Completion::Choices(words), _) => {
+                    line.push_str(&format!(" -x -a {}", quote(&words.join(" "))))
+                }
+                (Completion::None, _) => line.push_str(" -x"),
+            }
+            script.push_str(&line);
+            script.push('\n');
+        }
+    }
+    script
+}
+
+/// Parse a delimiter given on the command line
+///
+/// Accepts a single ASCII character, or "tab" / "\t" for a tab.
+///
+/// # Arguments
+///
+/// * `input` - delimiter as given on the command line
+fn parse_delimiter(input: &str) -> Option<u8> {
+    match input {
+        "tab" | "\\t" => Some(b'\t'),
+        _ if input.len() == 1 && input.is_ascii() => Some(input.as_bytes()[0]),
+        _ => None,
+    }
+}
+
+/// Whether a --cluster-labels specification lists the names in order instead of pairing them
+/// with clusters
+fn is_name_list(spec: &str) -> bool {
+    !spec.contains('=')
+}
+
+/// Parse a --cluster-labels specification, a list like "idle,load,spike" or pairs like
+/// "0=low,1=medium,2=high"
+///
+/// # Arguments
+///
+/// * `spec` - comma-separated names or `<cluster>=<name>` pairs
+fn parse_cluster_labels(spec: &str) -> Result<HashMap<usize, &str>, String> {
+    if is_name_list(spec) {
+        parse_label_names(spec)
+    } else {
+        parse_label_pairs(spec)
+    }
+}
+
+/// Parse comma-separated `<cluster>=<name>` pairs, clusters are given by number or by name from
+/// "zero" to "nine"
+fn parse_label_pairs(spec: &str) -> Result<HashMap<usize, &str>, String> {
+    const NUMBER_NAMES: [&str; 10] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+    let mut names = HashMap::new();
+    for pair in spec.split(',') {
+        let (cluster, name) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("\"{}\" is not of the form <cluster>=<name>", pair))?;
+        let cluster = cluster.trim();
+        let label = match cluster.parse::<usize>() {
+            Ok(label) => label,
+            Err(_) => NUMBER_NAMES
+                .iter()
+                .position(|number| number.eq_ignore_ascii_case(cluster))
+                .ok_or_else(|| format!("\"{}\" is not a cluster number", cluster))?,
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("cluster {} has an empty name", label));
+        }
+        if names.insert(label, name).is_some() {
+            return Err(format!("cluster {} is named more than once", label));
+        }
+    }
+    Ok(names)
+}
+
+/// Parse a list like "idle,load,spike", naming clusters 0, 1, 2, ... in order
+fn parse_label_names(spec: &str) -> Result<HashMap<usize, &str>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .enumerate()
+        .map(|(label, name)| match name {
+            "" => Err(format!("cluster {} has an empty name", label)),
+            name => Ok((label, name)),
+        })
+        .collect()
+}
+
+/// The cluster names of --cluster-labels, reporting a malformed specification as a parameter
+/// error
+fn parse_cluster_labels_arg(spec: Option<&str>) -> Result<Option<HashMap<usize, &str>>, CliError> {
+    spec.map(|spec| {
+        parse_cluster_labels(spec)
+            .map_err(|msg| CliError::args(format!("Invalid --cluster-labels: {}", msg)))
+    })
+    .transpose()
+}
+
+/// Error unless a list of --cluster-labels names every one of `n_clusters` clusters, pairs may
+/// leave clusters unnamed
+fn check_cluster_labels(spec: Option<&str>, n_clusters: usize) -> Result<(), CliError> {
+    match spec.filter(|spec| is_name_list(spec)) {
+        Some(spec) if spec.split(',').count() != n_clusters => Err(CliError::args(format!(
+            "Parameter --cluster-labels gives {} names, but there are {} clusters",
+            spec.split(',').count(),
+            n_clusters
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Cells of the labeled output, with the cluster column named by `cluster_labels` if given
+///
+/// # Arguments
+///
+/// * `out_vals`       - data points followed by their cluster and possibly further columns
+/// * `float_format`   - how to write the values, see `format_values`
+/// * `cluster_col`    - index of the cluster column
+/// * `labels`         - cluster of every data point
+/// * `cluster_labels` - name of every cluster, `cluster_<j>` for those without one
+fn labeled_cells(
+    out_vals: &Array2<f64>,
+    float_format: Option<FloatFormat>,
+    cluster_col: usize,
+    labels: &Array1<usize>,
+    cluster_labels: Option<&HashMap<usize, &str>>,
+) -> Array2<String> {
+    let mut cells = format_values(out_vals, float_format).mapv(|val| val.to_string());
+    // clusters stay whole numbers in scientific notation as well
+    for (cell, &label) in cells.column_mut(cluster_col).iter_mut().zip(labels) {
+        *cell = match cluster_labels {
+            Some(names) => cluster_name(label, names),
+            None => label.to_string(),
+        };
+    }
+    cells
+}
+
+/// Parse a --scan-k specification, an inclusive range of cluster counts like "2..10"
+///
+/// # Arguments
+///
+/// * `spec` - range of cluster counts, `2..=10` is accepted as well
+fn parse_k_range(spec: &str) -> Result<Vec<usize>, String> {
+    let (start, end) = spec
+        .split_once("..")
+        .ok_or_else(|| format!("expected a range like 2..10, got \"{}\"", spec))?;
+    let end = end.strip_prefix('=').unwrap_or(end);
+    let parse_bound = |bound: &str, name: &str| {
+        bound.trim().parse::<usize>().map_err(|_| {
+            format!(
+                "{} of the range \"{}\" is not a non-negative integer",
+                name, bound
+            )
+        })
+    };
+    let start = parse_bound(start, "start")?;
+    let end = parse_bound(end, "end")?;
+    if start < 2 {
+        return Err(format!(
+            "the range needs to start at 2 or more clusters, got {}",
+            start
+        ));
+    }
+    if end < start {
+        return Err(format!("end {} is smaller than start {}", end, start));
+    }
+    Ok((start..=end).collect())
+}
+
+/// Print objective and validity indices for every scanned number of clusters, marking the
+/// suggested one
+///
+/// # Arguments
+///
+/// * `results`   - best fit for every number of clusters
+/// * `fpc`       - partition coefficient of every fit
+/// * `xie_beni`  - Xie-Beni index of every fit
+/// * `extra`     - another validity index and its value for every fit, if it was computed
+/// * `suggested` - index of the suggested fit
+/// * `validity`  - index the suggestion is based on
+fn print_scan_table(
+    results: &[algo::ClusteringResult<f64>],
+    fpc: &[f64],
+    xie_beni: &[f64],
+    extra: Option<(algo::ValidityMeasure, &[f64])>,
+    suggested: usize,
+    validity: algo::ValidityMeasure,
+) {
+    const H_ITEM_SEP: usize = 2;
+
+    let mut titles = vec!["k", "objective", "FPC", "Xie-Beni"];
+    if let Some((measure, _)) = extra {
+        titles.push(measure.name());
+    }
+    let widths: Vec<usize> = titles
+        .iter()
+        .map(|title| title.len().max(12) + H_ITEM_SEP)
+        .collect();
+    eprint!(" ");
+    for (title, width) in titles.iter().zip(widths.iter()) {
+        eprint!("{: >1$}", title, width);
+    }
+    eprintln!();
+    eprintln!("{:-<1$}", "", 1 + widths.iter().sum::<usize>());
+    for (i, result) in results.iter().enumerate() {
+        let marker = if i == suggested { "*" } else { " " };
+        eprint!(
+            "{}{: >5$}{: >6$.6}{: >7$.4}{: >8$.6}",
+            marker,
+            result.centers.nrows(),
+            result.objective,
+            fpc[i],
+            xie_beni[i],
+            widths[0],
+            widths[1],
+            widths[2],
+            widths[3]
+        );
+        if let Some((_, scores)) = extra {
+            eprint!("{: >1$.6}", scores[i], widths[4]);
+        }
+        eprintln!();
+    }
+    eprintln!(
+        "suggested k: {} ({} {})",
+        results[suggested].centers.nrows(),
+        if validity.higher_is_better() {
+            "highest"
+        } else {
+            "lowest"
+        },
+        validity.name()
+    );
+}
+
+/// Resolve a --columns specification to column indices
+///
+/// Every entry is either a zero-based index or a column name from the header.
+///
+/// # Arguments
+///
+/// * `spec`   - comma-separated list of column names or indices
+/// * `header` - column names of the input file, if it has a header
+/// * `n_cols` - number of columns in the input file
+fn resolve_columns(
+    spec: &str,
+    header: Option<&[String]>,
+    n_cols: usize,
+) -> Result<Vec<usize>, String> {
+    let mut columns = Vec::new();
+    for entry in spec.split(',').map(str::trim) {
+        if entry.is_empty() {
+            return Err(format!("Empty entry in column list \"{}\"", spec));
+        }
+        let col = match entry.parse::<usize>() {
+            Ok(idx) if idx < n_cols => idx,
+            Ok(idx) => {
+                return Err(format!(
+                    "Column index {} out of range, the input has {} columns",
+                    idx, n_cols
+                ))
+            }
+            Err(_) => match header.and_then(|names| names.iter().position(|name| name == entry)) {
+                Some(idx) => idx,
+                None if header.is_none() => {
+                    return Err(format!(
+                        "Column \"{}\" can't be resolved by name without a header",
+                        entry
+                    ))
+                }
+                None => return Err(format!("No column named \"{}\" in the input", entry)),
+            },
+        };
+        if columns.contains(&col) {
+            return Err(format!("Column \"{}\" selected more than once", entry));
+        }
+        columns.push(col);
+    }
+    Ok(columns)
+}
+
+/// Print row and column counts and the statistics of every input column for --dry-run
+///
+/// # Arguments
+///
+/// * `fname`     - input file name
+/// * `delimiter` - field delimiter of the input
+/// * `header`    - column names, if the input has a header
+/// * `n_rows`    - number of data rows
+/// * `stats`     - statistics of every column
+/// * `columns`   - columns selected by --columns, all if `None`
+fn print_dataset_summary(
+    fname: &str,
+    delimiter: u8,
+    header: Option<&[String]>,
+    n_rows: usize,
+    stats: &[ColumnStats],
+    columns: Option<&[usize]>,
+) {
+    const TITLES: [&str; 7] = [
+        "column",
+        "min",
+        "max",
+        "mean",
+        "stddev",
+        "missing",
+        "treatment",
+    ];
+
+    println!("input:     {}", fname);
+    println!("delimiter: '{}'", (delimiter as char).escape_default());
+    println!("rows:      {}", n_rows);
+    println!("columns:   {}", stats.len());
+    println!();
+
+    let rows: Vec<Vec<String>> = stats
+        .iter()
+        .enumerate()
+        .map(|(col, col_stats)| {
+            let name = match header {
+                Some(names) => names[col].clone(),
+                None => col.to_string(),
+            };
+            let selected = columns.is_none_or(|columns| columns.contains(&col));
+            // every column is read as numbers, clustered on or not
+            let treatment = if col_stats.non_numeric > 0 {
+                "not numeric, reading fails"
+            } else if col_stats.missing > 0 {
+                "missing values, reading fails"
+            } else if selected {
+                "clustered"
+            } else {
+                "copied to the output"
+            };
+            let mut row = vec![name];
+            row.extend(
+                [
+                    col_stats.min,
+                    col_stats.max,
+                    col_stats.mean(),
+                    col_stats.std_dev(),
+                ]
+                .iter()
+                .map(|val| match col_stats.count {
+                    0 => String::from("-"),
+                    _ => format!("{:.4}", val),
+                }),
+            );
+            row.push(col_stats.missing.to_string());
+            row.push(treatment.to_string());
+            row
+        })
+        .collect();
+    let mut left_aligned = [false; 7];
+    left_aligned[0] = true;
+    left_aligned[6] = true;
+    print!(
+        "{}",
+        render_table(&TITLES, &rows, &left_aligned, usize::MAX)
+    );
+}
+
+/// Lay out a table with a title row, one line per row
+///
+/// Every column is as wide as its widest cell. Columns that don't fit into `width` are left
+/// out, which is marked by "..." at the end of every line; the first column is always shown.
+///
+/// # Arguments
+///
+/// * `titles`       - title of every column
+/// * `rows`         - cells of every row, one per column
+/// * `left_aligned` - whether each column is aligned left instead of right
+/// * `width`        - width available for the table
+fn render_table(
+    titles: &[&str],
+    rows: &[Vec<String>],
+    left_aligned: &[bool],
+    width: usize,
+) -> String {
+    const H_ITEM_SEP: usize = 2;
+    const ELLIPSIS: &str = "  ...";
+
+    let col_lens: Vec<usize> = titles
+        .iter()
+        .enumerate()
+        .map(|(col, title)| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .chain(std::iter::once(title.chars().count()))
+                .max()
+                .unwrap()
+                + H_ITEM_SEP
+        })
+        .collect();
+    let mut n_shown = titles.len();
+    let mut line_len = 1 + col_lens.iter().sum::<usize>();
+    while n_shown > 1 && line_len > width {
+        n_shown -= 1;
+        line_len = 1 + col_lens[..n_shown].iter().sum::<usize>() + ELLIPSIS.len();
+    }
+    let truncated = n_shown < titles.len();
+
+    let render_line = |cells: &mut dyn Iterator<Item = &str>| {
+        let mut line = String::from(" ");
+        for (col, cell) in cells.take(n_shown).enumerate() {
+            let pad = " ".repeat(col_lens[col] - cell.chars().count());
+            if left_aligned[col] && col > 0 {
+                // keep the gap to the previous column in front of the text
+                line.push_str(&pad[..H_ITEM_SEP]);
+                line.push_str(cell);
+                line.push_str(&pad[H_ITEM_SEP..]);
+            } else if left_aligned[col] {
+                line.push_str(cell);
+                line.push_str(&pad);
+            } else {
+                line.push_str(&pad);
+                line.push_str(cell);
+            }
+        }
+        if truncated {
+            line.push_str(ELLIPSIS);
+        }
+        line.trim_end().to_string()
+    };
+    let title_line = render_line(&mut titles.iter().copied());
+    let lines: Vec<String> = rows
+        .iter()
+        .map(|row| render_line(&mut row.iter().map(String::as_str)))
+        .collect();
+    let rule_len = lines
+        .iter()
+        .chain(std::iter::once(&title_line))
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap();
+    let mut table = format!("{}\n{}\n", title_line, "-".repeat(rule_len));
+    for line in lines {
+        table.push_str(&line);
+        table.push('\n');
+    }
+    table
+}
+
+/// Print size, fuzzy size, mean distance to the center and the center of every cluster to
+/// stderr, see `cluster_summary_table`
+fn print_cluster_summary(
+    summary: &[ClusterSummary],
+    centers: &Array2<f64>,
+    feature_names: &[String],
+    term_width: usize,
+) {
+    eprint!(
+        "{}",
+        cluster_summary_table(summary, centers, feature_names, term_width)
+    );
+}
+
+/// Table of size, fuzzy size, mean distance to the center and the center of every cluster
+///
+/// # Arguments
+///
+/// * `summary`       - numbers to print, see `ClusterSummary`
+/// * `centers`       - cluster centers (rows)
+/// * `feature_names` - names of the center coordinates
+/// * `term_width`    - width of the terminal, center coordinates that don't fit are left out
+fn cluster_summary_table(
+    summary: &[ClusterSummary],
+    centers: &Array2<f64>,
+    feature_names: &[String],
+    term_width: usize,
+) -> String {
+    let mut titles = vec!["cluster", "size", "fuzzy size", "mean dist"];
+    titles.extend(feature_names.iter().map(String::as_str));
+    let rows: Vec<Vec<String>> = summary
+        .iter()
+        .zip(centers.outer_iter())
+        .enumerate()
+        .map(|(cluster, (cluster_summary, center))| {
+            let mut row = vec![
+                cluster.to_string(),
+                cluster_summary.size.to_string(),
+                format!("{:.2}", cluster_summary.fuzzy_size),
+                format!("{:.4}", cluster_summary.mean_distance),
+            ];
+            row.extend(center.iter().map(|val| format!("{:.4}", val)));
+            row
+        })
+        .collect();
+    let mut left_aligned = vec![false; titles.len()];
+    left_aligned[0] = true;
+    render_table(&titles, &rows, &left_aligned, term_width)
+}
+
+/// Check that a file can be created at the given path
+///
+/// # Arguments
+///
+/// * `fname` - path of the file to write
+fn check_output_path(fname: &str) -> Result<(), String> {
+    let path = std::path::Path::new(fname);
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => std::path::Path::new("."),
+    };
+    if !parent.is_dir() {
+        return Err(format!("directory {} does not exist", parent.display()));
+    }
+    if path.is_dir() {
+        return Err(format!("{} is a directory", fname));
+    }
+    // permission bits don't tell, e.g. for root, ACLs or read-only mounts, so try to write
+    if path.exists() {
+        // opening without truncating leaves the file as it is until the results are written
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|err| format!("{} is not writable: {}", fname, err))?;
+    } else {
+        let probe = parent.join(format!(".k_means_rs-probe-{}", std::process::id()));
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&probe)
+            .map_err(|err| format!("directory {} is not writable: {}", parent.display(), err))?;
+        let _ = std::fs::remove_file(&probe);
+    }
+    Ok(())
+}
+
+/// Check the files a command reads and writes before doing any work
+///
+/// Input files need to be readable. Output files need an existing, writable directory, which is
+/// created with --create-dirs, and may only exist already with --force. Outputs aren't checked
+/// for a dry run, which writes none.
+///
+/// # Arguments
+///
+/// * `command` - command to run
+/// * `args`    - its parameters
+fn validate_paths(command: Command, args: &ArgConfig) -> Result<(), CliError> {
+    const INPUTS: &[&str] = &["-i", "--model", "--ground-truth", "--init-centers"];
+    // -o is named by the output formats
+    const OUTPUTS: &[&str] = &[
+        "--dump-config",
+        "--scan-out",
+        "--linkage-out",
+        "--confusion-out",
+        "--json-out",
+        "--multi-label-out",
+        "--centers",
+        "--memberships",
+        "--distance-matrix-out",
+        "--convergence-out",
+        "--export-model",
+    ];
+    // stdin and stdout need no checks
+    let given = |key: &&str| {
+        command
+            .accepts(key)
+            .then(|| args[*key].value.get_str().ok())
+            .flatten()
+            .filter(|fname| fname != "-")
+    };
+
+    for (key, fname) in INPUTS.iter().filter_map(|key| Some((key, given(key)?))) {
+        if let Err(err) = std::fs::File::open(&fname) {
+            return Err(CliError::io_message(format!(
+                "Can't read {} {}: {}",
+                args[*key].cmdline_expr, fname, err
+            )));
+        }
+        if !std::path::Path::new(&fname).is_file() {
+            return Err(CliError::io_message(format!(
+                "Can't read {} {}: not a file",
+                args[*key].cmdline_expr, fname
+            )));
+        }
+    }
+    if args["--dry-run"].value.get_flag().unwrap() {
+        return Ok(());
+    }
+
+    let force = args["--force"].value.get_flag().unwrap();
+    let create_dirs = args["--create-dirs"].value.get_flag().unwrap();
+    let mut outputs: Vec<String> = parse_output_formats(args)?
+        .into_iter()
+        .map(|(_, fname)| fname)
+        .filter(|fname| fname != "-")
+        .collect();
+    outputs.extend(OUTPUTS.iter().filter_map(given));
+    for fname in &outputs {
+        let path = std::path::Path::new(fname);
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            if create_dirs && !parent.exists() {
+                std::fs::create_dir_all(parent).map_err(|err| {
+                    CliError::io_message(format!(
+                        "Can't create directory {}: {}",
+                        parent.display(),
+                        err
+                    ))
+                })?;
+            }
+        }
+        check_output_path(fname)
+            .map_err(|msg| CliError::io_message(format!("Can't write {}: {}", fname, msg)))?;
+        // devices like /dev/null are fine to write to
+        if path.is_file() && !force {
+            return Err(CliError::io_message(format!(
+                "{} already exists, use --force to overwrite it",
+                fname
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Writes log messages to stderr
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            // hide progress bars while printing so they are redrawn below the message
+            progress().suspend(|| eprintln!("[{}] {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Progress bars on stderr, shared with the logger
+fn progress() -> &'static MultiProgress {
+    static PROGRESS: OnceLock<MultiProgress> = OnceLock::new();
+    PROGRESS.get_or_init(MultiProgress::new)
+}
+
+/// Add a progress bar of length `len` to stderr, or a hidden one if `show` is false
+///
+/// # Arguments
+///
+/// * `len`      - number of steps
+/// * `template` - indicatif template, `{msg}` and `{prefix}` are set by the caller
+/// * `show`     - whether to draw the bar at all
+fn progress_bar(len: usize, template: &str, show: bool) -> ProgressBar {
+    if !show {
+        return ProgressBar::hidden();
+    }
+    let bar = progress().add(ProgressBar::new(len as u64));
+    bar.set_style(ProgressStyle::with_template(template).unwrap());
+    bar
+}
+
+/// Send log messages up to `level` to stderr
+///
+/// # Arguments
+///
+/// * `level` - most detailed level to print
+fn init_logging(level: log::LevelFilter) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Per-feature scaling applied to the clustered columns before clustering
+#[derive(Clone, Serialize, Deserialize)]
+struct Scaler {
+    /// "zscore", "minmax" or "whiten"
+    method: String,
+    /// Subtracted from every feature, the mean or minimum
+    offset: Vec<f64>,
+    /// Every feature is divided by this after subtracting the offset, the standard deviation or
+    /// range. Constant features have scale 0 and are only shifted.
+    scale: Vec<f64>,
+    /// Whitening matrix (rows) the shifted features are multiplied by instead of being scaled,
+    /// only for "whiten"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    matrix: Option<Vec<Vec<f64>>>,
+    /// Inverse of `matrix`, maps whitened points back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inverse: Option<Vec<Vec<f64>>>,
+}
+
+impl Scaler {
+    /// Fit a scaler to `data` and return it together with the scaled data
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - "zscore", "minmax" or, with the linalg feature, "whiten"
+    /// * `data`   - data points (rows)
+    fn fit(method: &'static str, data: &Array2<f64>) -> (Scaler, Array2<f64>) {
+        #[cfg(feature = "linalg")]
+        if method == "whiten" {
+            let (whitened, w) = k_means_rs::preprocess::whiten(data);
+            let to_rows = |arr: Array2<f64>| arr.outer_iter().map(|row| row.to_vec()).collect();
+            let scaler = Scaler {
+                method: method.to_string(),
+                offset: data.mean_axis(Axis(0)).unwrap().to_vec(),
+                scale: vec![1.0; data.ncols()],
+                inverse: Some(to_rows(k_means_rs::preprocess::whitening_inverse(&w))),
+                matrix: Some(to_rows(w)),
+            };
+            return (scaler, whitened);
+        }
+        let (scaled, offset, scale) = match method {
+            "zscore" => data.standardized(),
+            "minmax" => {
+                let (scaled, min, max) = data.normalized();
+                (scaled, min.clone(), max - min)
+            }
+            _ => unreachable!("unknown scaling method {}", method),
+        };
+        let scaler = Scaler {
+            method: method.to_string(),
+            offset: offset.to_vec(),
+            scale: scale.to_vec(),
+            matrix: None,
+            inverse: None,
+        };
+        (scaler, scaled)
+    }
+
+    /// Scale points given in original units, e.g. initial centers
+    fn transform(&self, points: &Array2<f64>) -> Array2<f64> {
+        if let Some(matrix) = &self.matrix {
+            let offset = Array1::from(self.offset.clone());
+            return (points - &offset).dot(&rows_to_array(matrix));
+        }
+        let mut out = points.clone();
+        for mut row in out.outer_iter_mut() {
+            for ((val, offset), scale) in row.iter_mut().zip(&self.offset).zip(&self.scale) {
+                *val = if *scale > 0.0 {
+                    (*val - offset) / scale
+                } else {
+                    0.0
+                };
+            }
+        }
+        out
+    }
+
+    /// Map scaled points, e.g. fitted centers, back to original units
+    fn inverse_transform(&self, points: &Array2<f64>) -> Array2<f64> {
+        if let Some(inverse) = &self.inverse {
+            let offset = Array1::from(self.offset.clone());
+            return points.dot(&rows_to_array(inverse)) + &offset;
+        }
+        let mut out = points.clone();
+        for mut row in out.outer_iter_mut() {
+            for ((val, offset), scale) in row.iter_mut().zip(&self.offset).zip(&self.scale) {
+                *val = *val * scale + offset;
+            }
+        }
+        out
+    }
+}
+
+/// Array of the given rows, which all need the same length
+fn rows_to_array(rows: &[Vec<f64>]) -> Array2<f64> {
+    let n_cols = rows.first().map_or(0, Vec::len);
+    Array2::from_shape_vec((rows.len(), n_cols), rows.concat()).unwrap()
+}
+
+/// Layout version of model files, bumped whenever `Model` changes incompatibly
+const MODEL_FORMAT_VERSION: u32 = 1;
+
+/// A fitted clustering as written by `--export-model`, everything `predict` needs
+#[derive(Serialize, Deserialize)]
+struct Model {
+    /// `MODEL_FORMAT_VERSION` of the program that wrote the file, models written before
+    /// versioning are version 1
+    #[serde(default = "first_model_format_version")]
+    format_version: u32,
+    /// Names of the clustered columns, looked up in the header of the data to predict
+    feature_names: Vec<String>,
+    /// Zero-based indices of the clustered columns, used for data without header
+    columns: Vec<usize>,
+    /// Cluster centers (rows) in original units
+    centers: Vec<Vec<f64>>,
+    fuzzifier: f64,
+    /// Name as given to --metric
+    metric: String,
+    scaler: Option<Scaler>,
+}
+
+fn first_model_format_version() -> u32 {
+    1
+}
+
+/// Parameters as read by --config and written by --dump-config
+///
+/// Every field is the long form of a parameter without the leading dashes, `k-range` stands for
+/// --scan-k. Every parameter but --config and --dump-config can be set.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RunConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delimiter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    out_delimiter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_header: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    write_header: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_row_index: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    columns: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_variance: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remove_outliers: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normalize: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    whiten: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clusters: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_k: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merge_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    k_range: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fit_best: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fuzzifier: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auto_q: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iterations: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tol: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restarts: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metric: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    init_centers: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hard: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    density_weighted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_clusters_by_size: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    precision: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scientific: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_column: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distances: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    centers: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memberships: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cluster_labels: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multi_label_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multi_label_cutoff: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multi_label_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multi_label_argmax: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_matrix_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    convergence_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    linkage_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ground_truth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confusion_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_summary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_out: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    export_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_table: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verbose: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quiet: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    force: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    create_dirs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dry_run: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    watch: Option<bool>,
+}
+
+impl RunConfig {
+    /// Read a config file, errors name the file and the line of the problem
+    fn read(fname: &str) -> Result<RunConfig, String> {
+        let text = std::fs::read_to_string(fname).map_err(|err| format!("{}: {}", fname, err))?;
+        toml::from_str(&text).map_err(|err| format!("Invalid config {}: {}", fname, err))
+    }
+
+    /// Key and value of every parameter the config sets
+    fn values(&self) -> Vec<(&'static str, ArgType)> {
+        let strings = [
+            ("-i", &self.input),
+            ("--input-dir", &self.input_dir),
+            ("-o", &self.output),
+            ("-d", &self.delimiter),
+            ("--out-delimiter", &self.out_delimiter),
+            ("--columns", &self.columns),
+            ("--normalize", &self.normalize),
+            ("--scan-k", &self.k_range),
+            ("--validity", &self.validity),
+            ("--scan-out", &self.scan_out),
+            ("--metric", &self.metric),
+            ("--init", &self.init),
+            ("--init-centers", &self.init_centers),
+            ("--output-format", &self.output_format),
+            ("--centers", &self.centers),
+            ("--memberships", &self.memberships),
+            ("--cluster-labels", &self.cluster_labels),
+            ("--multi-label-out", &self.multi_label_out),
+            ("--multi-label-format", &self.multi_label_format),
+            ("--distance-matrix-out", &self.distance_matrix_out),
+            ("--convergence-out", &self.convergence_out),
+            ("--linkage-out", &self.linkage_out),
+            ("--ground-truth", &self.ground_truth),
+            ("--confusion-out", &self.confusion_out),
+            ("--json-out", &self.json_out),
+            ("--export-model", &self.export_model),
+            ("--model", &self.model),
+        ];
+        let sizes = [
+            ("-k", self.clusters),
+            ("-n", self.iterations),
+            ("--restarts", self.restarts),
+            ("--threads", self.threads),
+        ];
+        let floats = [
+            ("--min-variance", self.min_variance),
+            ("--remove-outliers", self.remove_outliers),
+            ("--sample", self.sample),
+            ("--merge-threshold", self.merge_threshold),
+            ("-q", self.fuzzifier),
+            ("--tol", self.tol),
+            ("--multi-label-cutoff", self.multi_label_cutoff),
+        ];
+        let flags = [
+            ("--no-header", self.no_header),
+            ("--write-header", self.write_header),
+            ("--no-row-index", self.no_row_index),
+            ("--whiten", self.whiten),
+            ("--auto-k", self.auto_k),
+            ("--fit-best", self.fit_best),
+            ("--auto-q", self.auto_q),
+            ("--hard", self.hard),
+            ("--density-weighted", self.density_weighted),
+            ("--sort-clusters-by-size", self.sort_clusters_by_size),
+            ("--scientific", self.scientific),
+            ("--source-column", self.source_column),
+            ("--distances", self.distances),
+            ("--multi-label-argmax", self.multi_label_argmax),
+            ("--json-summary", self.json_summary),
+            ("--no-table", self.no_table),
+            ("--timings", self.timings),
+            ("--quiet", self.quiet),
+            ("--force", self.force),
+            ("--create-dirs", self.create_dirs),
+            ("--dry-run", self.dry_run),
+            ("--watch", self.watch),
+        ];
+        let mut values: Vec<(&'static str, ArgType)> = strings
+            .iter()
+            .filter_map(|(key, val)| Some((*key, ArgType::StringType(Some((*val).clone()?)))))
+            .collect();
+        values.extend(
+            sizes
+                .iter()
+                .filter_map(|(key, val)| Some((*key, ArgType::SizeType(Some((*val)?))))),
+        );
+        values.extend(
+            floats
+                .iter()
+                .filter_map(|(key, val)| Some((*key, ArgType::FloatingNumber(Some((*val)?))))),
+        );
+        values.extend(
+            flags
+                .iter()
+                .filter_map(|(key, val)| Some((*key, ArgType::Flag(Some((*val)?))))),
+        );
+        if let Some(seed) = self.seed {
+            values.push(("-s", ArgType::SeedType(Some(seed))));
+        }
+        if let Some(verbose) = self.verbose {
+            values.push(("--verbose", ArgType::Count(Some(verbose))));
+        }
+        // parsed in run like the command line value
+        if let Some(precision) = self.precision {
+            values.push((
+                "--precision",
+                ArgType::StringType(Some(precision.to_string())),
+            ));
+        }
+        values
+    }
+
+    /// The parameters a run uses, given or default
+    ///
+    /// Defaults of parameters that conflict with a set one are left out, so the config can be
+    /// read again.
+    fn from_args(args: &ArgConfig) -> RunConfig {
+        let conflicting = |key: &str| {
+            args.values()
+                .any(|arg| arg.is_set() && arg.conflicts.contains(&key))
+        };
+        // parameters of disabled features are missing
+        let value = |key: &str| {
+            args.get(key)
+                .filter(|arg| arg.source != ValueSource::Default || !conflicting(key))
+                .map(|arg| &arg.value)
+        };
+        let string = |key: &str| value(key).and_then(|val| val.get_str().ok());
+        let size = |key: &str| value(key).and_then(|val| val.get_size().ok());
+        let float = |key: &str| value(key).and_then(|val| val.get_flt().ok());
+        let flag = |key: &str| value(key).and_then(|val| val.get_flag().ok());
+        RunConfig {
+            input: string("-i"),
+            input_dir: string("--input-dir"),
+            output: string("-o"),
+            delimiter: string("-d"),
+            out_delimiter: string("--out-delimiter"),
+            no_header: flag("--no-header"),
+            write_header: flag("--write-header"),
+            no_row_index: flag("--no-row-index"),
+            columns: string("--columns"),
+            min_variance: float("--min-variance"),
+            remove_outliers: float("--remove-outliers"),
+            sample: float("--sample"),
+            normalize: string("--normalize"),
+            whiten: flag("--whiten"),
+            clusters: size("-k"),
+            auto_k: flag("--auto-k"),
+            merge_threshold: float("--merge-threshold"),
+            k_range: string("--scan-k"),
+            validity: string("--validity"),
+            fit_best: flag("--fit-best"),
+            scan_out: string("--scan-out"),
+            fuzzifier: float("-q"),
+            auto_q: flag("--auto-q"),
+            iterations: size("-n"),
+            tol: float("--tol"),
+            restarts: size("--restarts"),
+            metric: string("--metric"),
+            init: string("--init"),
+            init_centers: string("--init-centers"),
+            seed: value("-s").and_then(|val| val.get_seed().ok()),
+            hard: flag("--hard"),
+            density_weighted: flag("--density-weighted"),
+            sort_clusters_by_size: flag("--sort-clusters-by-size"),
+            threads: size("--threads"),
+            output_format: string("--output-format"),
+            precision: string("--precision").and_then(|precision| precision.parse().ok()),
+            scientific: flag("--scientific"),
+            source_column: flag("--source-column"),
+            distances: flag("--distances"),
+            centers: string("--centers"),
+            memberships: string("--memberships"),
+            cluster_labels: string("--cluster-labels"),
+            multi_label_out: string("--multi-label-out"),
+            multi_label_cutoff: float("--multi-label-cutoff"),
+            multi_label_format: string("--multi-label-format"),
+            multi_label_argmax: flag("--multi-label-argmax"),
+            distance_matrix_out: string("--distance-matrix-out"),
+            convergence_out: string("--convergence-out"),
+            linkage_out: string("--linkage-out"),
+            ground_truth: string("--ground-truth"),
+            confusion_out: string("--confusion-out"),
+            json_summary: flag("--json-summary"),
+            json_out: string("--json-out"),
+            export_model: string("--export-model"),
+            model: string("--model"),
+            no_table: flag("--no-table"),
+            timings: flag("--timings"),
+            verbose: value("--verbose").and_then(|val| val.get_count().ok()),
+            quiet: flag("--quiet"),
+            force: flag("--force"),
+            create_dirs: flag("--create-dirs"),
+            dry_run: flag("--dry-run"),
+            watch: flag("--watch"),
+        }
+    }
+}
+
+/// Wall-clock time spent in every phase of a run, reported by --timings
+#[derive(Default, Serialize)]
+struct PhaseTimings {
+    /// Phases in the order they ran
+    phases: Vec<PhaseTime>,
+    /// Seconds spent iterating in every restart of the fit, empty for a single fit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    restarts: Vec<f64>,
+    /// Largest resident set size of the process so far, where the OS reports it
+    peak_rss_kib: Option<u64>,
+    /// Name and start of the phase that is running
+    #[serde(skip)]
+    current: Option<(&'static str, Instant)>,
+}
+
+#[derive(Serialize)]
+struct PhaseTime {
+    phase: &'static str,
+    seconds: f64,
+}
+
+impl PhaseTimings {
+    /// End the running phase, if any, and start the phase `name`
+    fn start(&mut self, name: &'static str) {
+        self.stop();
+        self.current = Some((name, Instant::now()));
+    }
+
+    /// End the running phase and note the peak memory use up to now
+    fn stop(&mut self) {
+        if let Some((phase, start)) = self.current.take() {
+            self.phases.push(PhaseTime {
+                phase,
+                seconds: start.elapsed().as_secs_f64(),
+            });
+        }
+        self.peak_rss_kib = peak_rss_kib();
+    }
+
+    /// Print the phases as a table on stderr
+    fn print(&self) {
+        const NAME_WIDTH: usize = 14;
+        const SECONDS_WIDTH: usize = 12;
+
+        eprintln!(
+            "{: <2$}{: >3$}",
+            "phase", "seconds", NAME_WIDTH, SECONDS_WIDTH
+        );
+        for phase in &self.phases {
+            eprintln!(
+                "{: <2$}{: >3$.4}",
+                phase.phase, phase.seconds, NAME_WIDTH, SECONDS_WIDTH
+            );
+            if phase.phase == "fit" {
+                for (i, seconds) in self.restarts.iter().enumerate() {
+                    let name = format!("  restart {}", i + 1);
+                    eprintln!("{: <2$}{: >3$.4}", name, seconds, NAME_WIDTH, SECONDS_WIDTH);
+                }
+            }
+        }
+        let total: f64 = self.phases.iter().map(|phase| phase.seconds).sum();
+        eprintln!(
+            "{: <2$}{: >3$.4}",
+            "total", total, NAME_WIDTH, SECONDS_WIDTH
+        );
+        if let Some(rss) = self.peak_rss_kib {
+            eprintln!(
+                "{: <2$}{: >3$} KiB",
+                "peak RSS",
+                rss,
+                NAME_WIDTH,
+                SECONDS_WIDTH - 4
+            );
+        }
+    }
+}
+
+/// Peak resident set size of this process in KiB, only known on Linux
+fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// Machine-readable summary of a clustering run
+#[derive(Serialize)]
+struct Summary {
+    n_clusters: usize,
+    n_iter_run: usize,
+    converged: bool,
+    objective: f64,
+    fpc: f64,
+    silhouette: f64,
+    fuzzy_dunn: f64,
+    soft_counts: Vec<f64>,
+    effective_k: usize,
+    cluster_entropy: Vec<f64>,
+    scaler: Option<Scaler>,
+    /// Only reported at -v
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_overlap: Option<ClusterOverlap>,
+}
+
+/// Size of a cluster and how close its members are, as printed after a run and written to the
+/// JSON output
+#[derive(Serialize)]
+struct ClusterSummary {
+    /// Number of data points assigned to the cluster
+    size: usize,
+    /// Sum of the memberships in the cluster, see `metrics::soft_counts`
+    fuzzy_size: f64,
+    /// Mean distance of the assigned data points to the center, NaN for empty clusters
+    mean_distance: f64,
+}
+
+impl ClusterSummary {
+    /// Summarize every cluster of a result
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - result of the fit
+    /// * `data`   - data the result was fit on
+    /// * `metric` - distance the fit used
+    fn from_result(
+        result: &algo::ClusteringResult<f64>,
+        data: &Array2<f64>,
+        metric: &dyn algo::DistanceMetric,
+    ) -> Result<Vec<ClusterSummary>, algo::AlgoError> {
+        let k = result.centers.nrows();
+        let (_, dists) = algo::assign_labels_with_dist(data, &result.centers, metric)?;
+        let sizes = metrics::cluster_sizes(&result.labels, k)?;
+        let fuzzy_sizes = metrics::soft_counts(&result.memberships);
+        let mean_distances = metrics::mean_distance_to_center(&dists, &result.labels, k)?;
+        Ok((0..k)
+            .map(|j| ClusterSummary {
+                size: sizes[j],
+                fuzzy_size: fuzzy_sizes[j],
+                mean_distance: mean_distances[j],
+            })
+            .collect())
+    }
+}
+
+/// The most overlapping pair of clusters, see `metrics::max_cluster_overlap`
+#[derive(Serialize)]
+struct ClusterOverlap {
+    clusters: (usize, usize),
+    overlap: f64,
+}
+
+impl Summary {
+    /// Summarize a clustering result
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - result of the fit
+    /// * `data`   - data the result was fit on
+    /// * `q`      - fuzzifier of the fit
+    /// * `metric` - distance the fit used
+    /// * `scaler` - scaling applied to the data before the fit
+    fn from_result(
+        result: &algo::ClusteringResult<f64>,
+        data: &Array2<f64>,
+        q: f64,
+        metric: &dyn algo::DistanceMetric,
+        scaler: Option<&Scaler>,
+    ) -> Result<Summary, algo::AlgoError> {
+        // clusters holding less than one data point's worth of membership don't count
+        const EFFECTIVE_K_THRESHOLD: f64 = 1.0;
+
+        let soft_counts = metrics::soft_counts(&result.memberships);
+        Ok(Summary {
+            n_clusters: result.centers.nrows(),
+            n_iter_run: result.n_iter,
+            converged: result.converged,
+            objective: result.objective,
+            fpc: metrics::partition_coefficient(&result.memberships),
+            silhouette: metrics::silhouette_score(data, &result.labels, result.centers.nrows())?,
+            fuzzy_dunn: metrics::fuzzy_dunn_index(
+                data,
+                &result.centers,
+                &result.memberships,
+                q,
+                metric,
+            ),
+            effective_k: metrics::effective_k(&soft_counts, EFFECTIVE_K_THRESHOLD),
+            soft_counts: soft_counts.to_vec(),
+            cluster_entropy: metrics::cluster_membership_entropy(&result.memberships).to_vec(),
+            scaler: scaler.cloned(),
+            max_overlap: None,
+        })
+    }
+}
+
+/// Print the number and share of data points and the membership entropy per cluster with a
+/// bar chart
+///
+/// # Arguments
+///
+/// * `assignments` - cluster per data point
+/// * `memberships` - memberships of data points (rows) in clusters (columns)
+/// * `term_width`  - width of the terminal, the bars use whatever the other columns leave
+fn print_cluster_table(assignments: &Array1<usize>, memberships: &Array2<f64>, term_width: usize) {
+    const H_ITEM_SEP: usize = 2;
+    const CLUSTER_TITLE_STR: &str = "cluster";
+    const COUNT_TITLE_STR: &str = "count";
+    const SHARE_TITLE_STR: &str = "share";
+    const ENTROPY_TITLE_STR: &str = "entropy";
+
+    let k = memberships.ncols();
+    let entropy = metrics::cluster_membership_entropy(memberships);
+
+    let mut counts = vec![0usize; k];
+    for &cluster in assignments.iter() {
+        counts[cluster] += 1;
+    }
+    let total = assignments.len();
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+
+    let cluster_len = CLUSTER_TITLE_STR.len().max(k.to_string().len()) + H_ITEM_SEP;
+    let count_len = COUNT_TITLE_STR.len().max(total.to_string().len()) + H_ITEM_SEP;
+    let share_len = "100.0%".len() + H_ITEM_SEP;
+    let entropy_len = ENTROPY_TITLE_STR.len() + H_ITEM_SEP;
+    let bar_len = term_width
+        .saturating_sub(1 + cluster_len + count_len + share_len + entropy_len + H_ITEM_SEP)
+        .max(1);
+
+    eprintln!(
+        " {: <4$}{: >5$}{: >6$}{: >7$}",
+        CLUSTER_TITLE_STR,
+        COUNT_TITLE_STR,
+        SHARE_TITLE_STR,
+        ENTROPY_TITLE_STR,
+        cluster_len,
+        count_len,
+        share_len,
+        entropy_len
+    );
+    eprintln!("{:-<1$}", "", term_width);
+    for (cluster, &count) in counts.iter().enumerate() {
+        let share = if total > 0 {
+            count as f64 / total as f64
+        } else {
+            0.0
+        };
+        let bar = (count * bar_len + max_count / 2)
+            .checked_div(max_count)
+            .unwrap_or(0);
+        eprintln!(
+            " {: <6$}{: >7$}{: >8$}{: >9$.3}{: <10$}{}",
+            cluster,
+            count,
+            format!("{:.1}%", share * 100.0),
+            entropy[cluster],
+            "",
+            "#".repeat(bar),
+            cluster_len,
+            count_len,
+            share_len,
+            entropy_len,
+            H_ITEM_SEP
+        );
+    }
+}
+
+/// Print objective and iterations of every restart, marking the chosen one
+///
+/// # Arguments
+///
+/// * `results` - result of every restart
+/// * `best`    - index of the chosen restart
+fn print_restart_table(results: &[algo::ClusteringResult<f64>], best: usize) {
+    const H_ITEM_SEP: usize = 2;
+    const TITLES: [&str; 4] = ["restart", "objective", "iterations", "converged"];
+
+    let widths: Vec<usize> = TITLES
+        .iter()
+        .map(|title| title.len().max(12) + H_ITEM_SEP)
+        .collect();
+    eprint!(" ");
+    for (title, width) in TITLES.iter().zip(widths.iter()) {
+        eprint!("{: >1$}", title, width);
+    }
+    eprintln!();
+    eprintln!("{:-<1$}", "", 1 + widths.iter().sum::<usize>());
+    for (i, result) in results.iter().enumerate() {
+        let marker = if i == best { "*" } else { " " };
+        eprintln!(
+            "{}{: >5$}{: >6$.6}{: >7$}{: >8$}",
+            marker,
+            i,
+            result.objective,
+            result.n_iter,
+            result.converged,
+            widths[0],
+            widths[1],
+            widths[2],
+            widths[3]
+        );
+    }
+}
+
+/// Print a confusion matrix with counts and row percentages
+///
+/// # Arguments
+///
+/// * `cm` - confusion matrix, rows are true classes and columns predicted clusters
+fn print_confusion_matrix(cm: &Array2<usize>) {
+    const ROW_TITLE_STR: &str = "true \\ pred";
+    const H_ITEM_SEP: usize = 2;
+
+    let normalized = metrics::row_normalize(cm);
+    let cells: Array2<String> = ndarray::Zip::from(cm)
+        .and(&normalized)
+        .map_collect(|count, frac| format!("{} ({:.0}%)", count, frac * 100.0));
+
+    let title_len = ROW_TITLE_STR.len() + H_ITEM_SEP;
+    let cell_len = cells
+        .iter()
+        .map(|cell| cell.len())
+        .chain(std::iter::once(cm.ncols().to_string().len()))
+        .max()
+        .unwrap_or(0)
+        + H_ITEM_SEP;
+
+    eprint!(" {: <1$}", ROW_TITLE_STR, title_len);
+    for j in 0..cm.ncols() {
+        eprint!("{: >1$}", j, cell_len);
+    }
+    eprintln!();
+    eprintln!("{:-<1$}", "", 1 + title_len + cell_len * cm.ncols());
+    for (i, row) in cells.outer_iter().enumerate() {
+        eprint!(" {: <1$}", i, title_len);
+        for cell in row.iter() {
+            eprint!("{: >1$}", cell, cell_len);
+        }
+        eprintln!();
+    }
+}
+
+/// Why a run failed: an error of the library, with the file or step it occurred in
+///
+/// The kind of the error decides the exit code of the process.
+#[derive(Debug)]
+struct CliError {
+    /// The file or step the error occurred in, shown before the error
+    context: Option<String>,
+    error: Error,
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self.error {
+            Error::Config(_) => 2,
+            Error::Io(_) => 3,
+            Error::Fit(_) => 4,
+        }
+    }
+
+    /// Error for invalid or missing command line parameters
+    fn args(msg: impl Into<String>) -> CliError {
+        CliError {
+            context: None,
+            error: ConfigError::new(msg).into(),
+        }
+    }
+
+    /// Error for a failed read or write of `fname`
+    fn io(fname: &str, err: CsvError) -> CliError {
+        CliError {
+            context: Some(fname.to_string()),
+            error: err.into(),
+        }
+    }
+
+    /// Error for a file that can't be opened, or whose content isn't csv and can't be used
+    fn file(fname: &str, err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> CliError {
+        CliError::io(fname, std::io::Error::other(err).into())
+    }
+
+    /// Error for a file or directory that can't be used, explained by `msg`
+    fn io_message(msg: impl Into<String>) -> CliError {
+        CliError {
+            context: None,
+            error: CsvError::from(std::io::Error::other(msg.into())).into(),
+        }
+    }
+
+    /// Error for data that can't be clustered, in the step described by `context`
+    fn fit(context: impl Into<String>, err: algo::AlgoError) -> CliError {
+        CliError {
+            context: Some(context.into()),
+            error: err.into(),
+        }
+    }
+}
+
+impl From<algo::AlgoError> for CliError {
+    fn from(err: algo::AlgoError) -> Self {
+        CliError {
+            context: None,
+            error: err.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{}: {}", context, self.error),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// Open an output file for writing, or stdout for "-"
+///
+/// # Arguments
+///
+/// * `fname` - path of the output file
+fn open_output(fname: &str) -> Result<Box<dyn std::io::Write>, CliError> {
+    if fname == "-" {
+        return Ok(Box::new(std::io::stdout()));
+    }
+    match std::fs::File::create(fname) {
+        Ok(file) => Ok(Box::new(file)),
+        Err(err) => Err(CliError::file(fname, err)),
+    }
+}
+
+/// Where every parameter that isn't at its default got its value from, one line each
+fn value_sources(args: &ArgConfig) -> Vec<String> {
+    args.values()
+        .filter(|arg| arg.source != ValueSource::Default)
+        .map(|arg| format!("{}: {}", arg.names(), arg.source))
+        .collect()
+}
+
+/// Most detailed log level to print for the given -v count and --quiet
+fn log_level(verbosity: usize, quiet: bool) -> log::LevelFilter {
+    if quiet {
+        return log::LevelFilter::Error;
+    }
+    match verbosity {
+        0 => log::LevelFilter::Warn,
+        1 => log::LevelFilter::Info,
+        2 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
+
+/// The distance metric called `name` on the command line
+fn parse_metric(name: &str) -> Result<Arc<dyn algo::DistanceMetric>, CliError> {
+    algo::metric_from_name(name).ok_or_else(|| {
+        CliError::args(format!(
+            "Unknown metric \"{}\", expected one of \"euclidean\", \"manhattan\", \
+             \"cosine\" or \"chebyshev\"",
+            name
+        ))
+    })
+}
+
+/// Input and output delimiter, the output one defaults to the input one
+fn parse_delimiters(args: &ArgConfig) -> Result<(u8, u8), CliError> {
+    let delimiter_str = args["-d"].value.get_str().unwrap();
+    let delimiter = match parse_delimiter(&delimiter_str) {
+        Some(delimiter) => delimiter,
+        None => {
+            return Err(CliError::args(format!(
+                "Invalid delimiter \"{}\", expected a single character or \"tab\"\n\
+                 Run with -h for usage.",
+                delimiter_str
+            )));
+        }
+    };
+    let out_delimiter = match args["--out-delimiter"].value.get_str() {
+        Ok(out_delimiter_str) => match parse_delimiter(&out_delimiter_str) {
+            Some(out_delimiter) => out_delimiter,
+            None => {
+                return Err(CliError::args(format!(
+                    "Invalid output delimiter \"{}\", expected a single character or \
+                     \"tab\"\nRun with -h for usage.",
+                    out_delimiter_str
+                )));
+            }
+        },
+        Err(_) => delimiter,
+    };
+    Ok((delimiter, out_delimiter))
+}
+
+/// Format of the written values given by --precision and --scientific, `None` for all
+/// significant digits in decimal notation
+fn parse_float_format(args: &ArgConfig) -> Result<Option<FloatFormat>, CliError> {
+    let precision = match args["--precision"].value.get_str() {
+        Ok(spec) => match spec.parse::<usize>() {
+            Ok(precision) if precision <= 17 => Some(precision),
+            _ => {
+                return Err(CliError::args(format!(
+                    "Parameter --precision needs to be a whole number from 0 to 17, got \"{}\"",
+                    spec
+                )))
+            }
+        },
+        Err(_) => None,
+    };
+    if args["--scientific"].value.get_flag().unwrap() {
+        // 16 decimal places in the mantissa are the 17 significant digits of an f64
+        Ok(Some(FloatFormat::Scientific(precision.unwrap_or(16))))
+    } else {
+        Ok(precision.map(FloatFormat::Fixed))
+    }
+}
+
+/// Format of the labeled data points written to -o
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    /// Data points with their cluster as csv
+    Csv,
+    /// Data points with their cluster as a NumPy array of floats
+    Npy,
+    /// Labels, memberships and centers as one JSON document
+    Json,
+}
+
+impl OutputFormat {
+    /// Format of the given --output-format entry
+    fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "csv" => Some(OutputFormat::Csv),
+            "npy" => Some(OutputFormat::Npy),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Extension appended to -o when several formats are written
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Npy => "npy",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+/// Formats given by --output-format, each with the file it is written to
+///
+/// A single format is written to -o. Several formats use -o as the base name and append the
+/// extension of each format.
+fn parse_output_formats(args: &ArgConfig) -> Result<Vec<(OutputFormat, String)>, CliError> {
+    let ofname = args["-o"].value.get_str().unwrap();
+    let spec = args["--output-format"].value.get_str().unwrap();
+    let mut formats: Vec<OutputFormat> = Vec::new();
+    for name in spec.split(',').map(str::trim) {
+        let format = OutputFormat::from_name(name).ok_or_else(|| {
+            CliError::args(format!(
+                "Unknown output format \"{}\", expected \"csv\", \"npy\" or \"json\"",
+                name
+            ))
+        })?;
+        if formats.contains(&format) {
+            return Err(CliError::args(format!(
+                "Output format \"{}\" given more than once",
+                name
+            )));
+        }
+        formats.push(format);
+    }
+    if formats.len() == 1 {
+        return Ok(vec![(formats[0], ofname)]);
+    }
+    if ofname == "-" {
+        return Err(CliError::args(String::from(
+            "Several output formats need -o to name a file, they can't all go to stdout",
+        )));
+    }
+    Ok(formats
+        .into_iter()
+        .map(|format| (format, format!("{}.{}", ofname, format.extension())))
+        .collect())
+}
+
+/// Read a csv file, or stdin for "-", and return its header if it has one
+///
+/// # Arguments
+///
+/// * `fname`      - path of the input file
+/// * `delimiter`  - field delimiter
+/// * `has_header` - whether the first line holds column names
+fn read_input(
+    fname: &str,
+    delimiter: u8,
+    has_header: bool,
+) -> Result<(Option<Vec<String>>, Array2<f64>), CliError> {
+    let input: Box<dyn std::io::Read> = if fname == "-" {
+        Box::new(std::io::stdin())
+    } else {
+        Box::new(std::fs::File::open(fname).map_err(|err| CliError::file(fname, err))?)
+    };
+    let (header, vals): (Vec<String>, Array2<f64>) =
+        read_csv_from_reader(input, delimiter, has_header)
+            .map_err(|err| CliError::io(fname, err))?;
+    Ok((has_header.then_some(header), vals))
+}
+
+/// Names of all .csv files in `dir`, sorted
+///
+/// # Arguments
+///
+/// * `dir` - directory to list
+fn csv_files_in(dir: &str) -> Result<Vec<String>, CliError> {
+    let entries = std::fs::read_dir(dir).map_err(|err| CliError::file(dir, err))?;
+    let mut fnames = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|err| CliError::file(dir, err))?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "csv") {
+            fnames.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if fnames.is_empty() {
+        return Err(CliError::io_message(format!(
+            "{}: no .csv files found",
+            dir
+        )));
+    }
+    fnames.sort();
+    Ok(fnames)
+}
+
+/// Column names if there is a header, the data and the file every row was read from
+type SourcedInput = (Option<Vec<String>>, Array2<f64>, Vec<String>);
+
+/// Read and stack all .csv files of a directory like `concat_csv_files`, with the name of the
+/// file every row came from
+///
+/// The header of the first file names the columns.
+///
+/// # Arguments
+///
+/// * `dir`        - directory holding the input files
+/// * `delimiter`  - field delimiter
+/// * `has_header` - whether the first line of every file holds column names
+fn read_input_dir(dir: &str, delimiter: u8, has_header: bool) -> Result<SourcedInput, CliError> {
+    let mut header = None;
+    let mut parts: Vec<Array2<f64>> = Vec::new();
+    let mut sources = Vec::new();
+    for fname in csv_files_in(dir)? {
+        let (part_header, part) = read_input(&fname, delimiter, has_header)?;
+        if let Some(first) = parts.first() {
+            if part.ncols() != first.ncols() {
+                // the error names the file already
+                let err = CsvError::ColumnCountMismatch {
+                    file: fname.clone(),
+                    expected: first.ncols(),
+                    got: part.ncols(),
+                };
+                return Err(CliError {
+                    context: None,
+                    error: err.into(),
+                });
+            }
+            if part_header != header {
+                log::warn!(
+                    "Warning: {} names its columns differently than the first file, using the \
+                     names of the first",
+                    fname
+                );
+            }
+        } else {
+            header = part_header;
+        }
+        let name = std::path::Path::new(&fname)
+            .file_name()
+            .map_or(fname.clone(), |name| name.to_string_lossy().into_owned());
+        sources.extend(std::iter::repeat_n(name, part.nrows()));
+        parts.push(part);
+    }
+    let views: Vec<_> = parts.iter().map(Array2::view).collect();
+    Ok((
+        header,
+        ndarray::concatenate(Axis(0), &views).unwrap(),
+        sources,
+    ))
+}
+
+/// Read the input, cluster it and write all requested outputs
+///
+/// Returns the result of the fit, `None` for --dry-run.
+///
+/// # Arguments
+///
+/// * `args` - parsed command line parameters
+fn run(args: &ArgConfig) -> Result<Option<algo::ClusteringResult<f64>>, CliError> {
+    let input_dir = args["--input-dir"].value.get_str().ok();
+    let infname = match &input_dir {
+        Some(dir) => dir.clone(),
+        None => args["-i"].value.get_str().unwrap(),
+    };
+    let ofname = args["-o"].value.get_str().unwrap();
+    let n_iter = args["-n"].value.get_size().unwrap();
+    let n_clusters = args["-k"].value.get_size().unwrap();
+    let fuzzifier = args["-q"].value.get_flt().unwrap();
+    let tol = args["--tol"].value.get_flt().unwrap();
+    let init_spec = match args["--init-centers"].value.get_str() {
+        Ok(fname) => format!("provided:{}", fname),
+        Err(_) => args["--init"].value.get_str().unwrap(),
+    };
+    let restarts = args["--restarts"].value.get_size().unwrap();
+    // scan calls it --k-range
+    let scan_name = args["--scan-k"].cmdline_expr;
+    let scan_ks = match args["--scan-k"].value.get_str() {
+        Ok(spec) => Some(
+            parse_k_range(&spec)
+                .map_err(|msg| CliError::args(format!("Invalid {}: {}", scan_name, msg)))?,
+        ),
+        Err(_) => None,
+    };
+    let validity_name = args["--validity"].value.get_str().unwrap();
+    let validity = algo::ValidityMeasure::from_name(&validity_name).ok_or_else(|| {
+        let names: Vec<String> = algo::ValidityMeasure::ALL
+            .iter()
+            .map(|measure| format!("\"{}\"", measure.name()))
+            .collect();
+        CliError::args(format!(
+            "Unknown validity index \"{}\", expected one of {}",
+            validity_name,
+            names.join(", ")
+        ))
+    })?;
+    let fit_best = args["--fit-best"].value.get_flag().unwrap();
+    if fit_best && scan_ks.is_none() {
+        return Err(CliError::args(String::from(
+            "Parameter --fit-best needs --scan-k",
+        )));
+    }
+    if scan_ks.is_some() && init_spec.starts_with("provided:") {
+        return Err(CliError::args(format!(
+            "Parameter {} can't be combined with provided initial centers",
+            scan_name
+        )));
+    }
+    let model_out = args["--export-model"].value.get_str().ok();
+    let config_out = args["--dump-config"].value.get_str().ok();
+    if model_out.is_some() && scan_ks.is_some() && !fit_best {
+        return Err(CliError::args(String::from(
+            "Parameter --export-model needs --fit-best with --scan-k",
+        )));
+    }
+    let scan_out_arg = args["--scan-out"].value.get_str().ok();
+    // without --fit-best the report takes the place of the labeled data points
+    let scan_out = match (&scan_ks, &scan_out_arg) {
+        (None, _) => None,
+        (Some(_), Some(fname)) => Some(fname.clone()),
+        (Some(_), None) => (!fit_best).then(|| ofname.clone()),
+    };
+    let normalize = match args["--normalize"].value.get_str().unwrap().as_str() {
+        "none" => None,
+        "zscore" => Some("zscore"),
+        "minmax" => Some("minmax"),
+        other => {
+            return Err(CliError::args(format!(
+                "Unknown normalization \"{}\", expected \"zscore\", \"minmax\" or \"none\"",
+                other
+            )));
+        }
+    };
+    #[cfg(feature = "linalg")]
+    let normalize = if args["--whiten"].value.get_flag().unwrap() {
+        Some("whiten")
+    } else {
+        normalize
+    };
+    if n_clusters == 0 {
+        return Err(CliError::fit("-k", algo::AlgoError::NoClusters));
+    }
+    if restarts == 0 {
+        return Err(CliError::args(String::from(
+            "Parameter --restarts needs to be at least 1",
+        )));
+    }
+    let merge_threshold = args["--merge-threshold"].value.get_flt().ok();
+    let auto_k = args["--auto-k"].value.get_flag().unwrap();
+    let hard = args["--hard"].value.get_flag().unwrap();
+    let density_weighted = args["--density-weighted"].value.get_flag().unwrap();
+    let sample_fraction = args["--sample"].value.get_flt().ok();
+    if sample_fraction.is_some_and(|fraction| !(fraction > 0.0 && fraction <= 1.0)) {
+        return Err(CliError::args(String::from(
+            "Parameter --sample needs to be greater than 0 and at most 1",
+        )));
+    }
+    let sort_by_size = args["--sort-clusters-by-size"].value.get_flag().unwrap();
+    if auto_k && merge_threshold.is_none() {
+        return Err(CliError::args(String::from(
+            "Parameter --auto-k needs --merge-threshold",
+        )));
+    }
+    let metric_name = args["--metric"].value.get_str().unwrap();
+    let metric = parse_metric(&metric_name)?;
+    let verbosity = args["--verbose"].value.get_count().unwrap();
+    let quiet = args["--quiet"].value.get_flag().unwrap();
+    init_logging(log_level(verbosity, quiet));
+    for line in value_sources(args) {
+        log::info!("{}", line);
+    }
+    let verbose = !quiet && verbosity > 0;
+    #[cfg(feature = "parallel")]
+    log::info!("using {} threads", rayon::current_num_threads());
+    let seed = match args["-s"].value.get_seed() {
+        Ok(seed) => seed,
+        Err(_) => {
+            // below 2^63, so --dump-config can write it as a TOML integer
+            let seed = rand::random::<u64>() >> 1;
+            if !quiet {
+                eprintln!("Using seed: {}", seed);
+            }
+            seed
+        }
+    };
+    let no_header = args["--no-header"].value.get_flag().unwrap();
+    let write_header = args["--write-header"].value.get_flag().unwrap();
+    let no_row_index = args["--no-row-index"].value.get_flag().unwrap();
+    let source_column = args["--source-column"].value.get_flag().unwrap();
+    let with_distances = args["--distances"].value.get_flag().unwrap();
+    let ground_truth = args["--ground-truth"].value.get_str().ok();
+    let confusion_out = args["--confusion-out"].value.get_str().ok();
+    let columns_spec = args["--columns"].value.get_str().ok();
+    let min_variance = args["--min-variance"].value.get_flt().ok();
+    let outlier_threshold = args["--remove-outliers"].value.get_flt().ok();
+    let linkage_out = args["--linkage-out"].value.get_str().ok();
+    let memberships_out = args["--memberships"].value.get_str().ok();
+    let distance_matrix_out = args["--distance-matrix-out"].value.get_str().ok();
+    let convergence_out = args["--convergence-out"].value.get_str().ok();
+    let centers_out = args["--centers"].value.get_str().ok();
+    let json_summary = args["--json-summary"].value.get_flag().unwrap();
+    let show_timings = args["--timings"].value.get_flag().unwrap();
+    let json_out = args["--json-out"].value.get_str().ok();
+    let no_table = args["--no-table"].value.get_flag().unwrap();
+    let multi_label_out = args["--multi-label-out"].value.get_str().ok();
+    let multi_label_cutoff = args["--multi-label-cutoff"].value.get_flt().unwrap();
+    let multi_label_argmax = args["--multi-label-argmax"].value.get_flag().unwrap();
+    let multi_label_format = match args["--multi-label-format"]
+        .value
+        .get_str()
+        .unwrap()
+        .as_str()
+    {
+        "long" => MultiLabelFormat::Long,
+        "wide" => MultiLabelFormat::Wide,
+        other => {
+            return Err(CliError::args(format!(
+                "Unknown multi-label format \"{}\", expected \"long\" or \"wide\"",
+                other
+            )));
+        }
+    };
+
+    let float_format = parse_float_format(args)?;
+    let outputs = parse_output_formats(args)?;
+    let has_format = |format| outputs.iter().any(|(f, _)| *f == format);
+    let json_output = has_format(OutputFormat::Json);
+    let cluster_labels_spec = args["--cluster-labels"].value.get_str().ok();
+    let cluster_labels = parse_cluster_labels_arg(cluster_labels_spec.as_deref())?;
+    // otherwise the number of clusters is only known after fitting
+    if scan_ks.is_none() && merge_threshold.is_none() {
+        check_cluster_labels(cluster_labels_spec.as_deref(), n_clusters)?;
+    }
+    if json_output {
+        for (name, given) in [
+            ("--centers", centers_out.is_some()),
+            ("--memberships", memberships_out.is_some()),
+        ] {
+            if given {
+                log::warn!(
+                    "Warning: {} is redundant with --output-format json, which already \
+                     includes it",
+                    name
+                );
+            }
+        }
+    }
+    if with_distances && !has_format(OutputFormat::Csv) && !has_format(OutputFormat::Npy) {
+        log::warn!("Warning: --distances is only written with --output-format csv or npy");
+    }
+    if source_column && !has_format(OutputFormat::Csv) {
+        log::warn!("Warning: --source-column is only written with --output-format csv");
+    }
+    if cluster_labels.is_some() && !has_format(OutputFormat::Csv) {
+        log::warn!("Warning: --cluster-labels only names clusters with --output-format csv");
+    }
+
+    let (delimiter, out_delimiter) = parse_delimiters(args)?;
+
+    if confusion_out.is_some() && ground_truth.is_none() {
+        return Err(CliError::args(String::from(
+            "Parameter --confusion-out requires --ground-truth",
+        )));
+    }
+
+    // every output given as "-" goes to stdout, where they would be mixed up
+    let mut stdout_users: Vec<&str> = [
+        ("-o", Some(&ofname)),
+        ("--centers", centers_out.as_ref()),
+        ("--memberships", memberships_out.as_ref()),
+        ("--distance-matrix-out", distance_matrix_out.as_ref()),
+        ("--convergence-out", convergence_out.as_ref()),
+        ("--dump-config", config_out.as_ref()),
+        ("--scan-out", scan_out_arg.as_ref()),
+    ]
+    .iter()
+    .filter(|(_, fname)| fname.is_some_and(|fname| fname == "-"))
+    .map(|(name, _)| *name)
+    .collect();
+    if json_summary && json_out.is_none() {
+        stdout_users.push("--json-summary");
+    }
+    if stdout_users.len() > 1 {
+        return Err(CliError::args(format!(
+            "Only one output can go to stdout, but {} all do",
+            stdout_users.join(", ")
+        )));
+    }
+
+    if args["--dry-run"].value.get_flag().unwrap() {
+        let fnames = match &input_dir {
+            Some(dir) => csv_files_in(dir)?,
+            None => vec![infname.clone()],
+        };
+        for (i, fname) in fnames.iter().enumerate() {
+            let input: Box<dyn std::io::Read> = if fname == "-" {
+                Box::new(std::io::stdin())
+            } else {
+                Box::new(std::fs::File::open(fname).map_err(|err| CliError::file(fname, err))?)
+            };
+            let (header, n_rows, stats) = scan_csv_columns(input, delimiter, !no_header)
+                .map_err(|err| CliError::io(fname, err))?;
+            let header = (!no_header).then_some(header);
+            let columns = match &columns_spec {
+                Some(spec) => Some(
+                    resolve_columns(spec, header.as_deref(), stats.len())
+                        .map_err(|msg| CliError::args(format!("Invalid --columns: {}", msg)))?,
+                ),
+                None => None,
+            };
+            if i > 0 {
+                println!();
+            }
+            print_dataset_summary(
+                fname,
+                delimiter,
+                header.as_deref(),
+                n_rows,
+                &stats,
+                columns.as_deref(),
+            );
+        }
+        let config = RunConfig {
+            seed: Some(seed),
+            ..RunConfig::from_args(args)
+        };
+        println!();
+        println!("resolved configuration:");
+        print!("{}", toml::to_string(&config).unwrap());
+        return Ok(None);
+    }
+
+    if let Some(config_fname) = config_out {
+        let config = RunConfig {
+            seed: Some(seed),
+            ..RunConfig::from_args(args)
+        };
+        let config = toml::to_string(&config).map_err(|err| {
+            CliError::io_message(format!("Can't write config to {}: {}", config_fname, err))
+        })?;
+        let sources: String = value_sources(args)
+            .iter()
+            .map(|line| format!("# {}\n", line))
+            .collect();
+        let config = format!("# Sources of the values given\n{}\n{}", sources, config);
+        open_output(&config_fname)?
+            .write_all(config.as_bytes())
+            .map_err(|err| CliError::file(&config_fname, err))?;
+    }
+
+    let mut timings = PhaseTimings::default();
+    timings.start("read");
+    let (header, mut input_vals, mut sources) = match &input_dir {
+        Some(dir) => read_input_dir(dir, delimiter, !no_header)?,
+        None => {
+            let (header, input_vals) = read_input(&infname, delimiter, !no_header)?;
+            let sources = vec![infname.clone(); input_vals.nrows()];
+            (header, input_vals, sources)
+        }
+    };
+    let mut columns = match columns_spec {
+        Some(spec) => match resolve_columns(&spec, header.as_deref(), input_vals.ncols()) {
+            Ok(columns) => columns,
+            Err(msg) => return Err(CliError::args(format!("Invalid --columns: {}", msg))),
+        },
+        None => (0..input_vals.ncols()).collect(),
+    };
+    if let Some(min_variance) = min_variance {
+        let (_, kept) =
+            remove_low_variance_features(&input_vals.select(Axis(1), &columns), min_variance);
+        if kept.is_empty() {
+            return Err(CliError::fit(
+                format!(
+                    "Every clustered column has a variance below {}",
+                    min_variance
+                ),
+                algo::AlgoError::EmptyData,
+            ));
+        }
+        for (pos, &j) in columns.iter().enumerate() {
+            if !kept.contains(&pos) {
+                log::info!(
+                    "dropping column {} with a variance below {}",
+                    header
+                        .as_ref()
+                        .map_or_else(|| j.to_string(), |names| names[j].clone()),
+                    min_variance
+                );
+            }
+        }
+        columns = kept.iter().map(|&pos| columns[pos]).collect();
+    }
+    // input row of every data point, if outliers were left out
+    let mut retained_rows: Option<Vec<usize>> = None;
+    if let Some(threshold) = outlier_threshold {
+        let flags = detect_outliers_mad(&input_vals.select(Axis(1), &columns), threshold);
+        let (kept_vals, kept) = remove_outliers(&input_vals, &flags);
+        if kept.is_empty() {
+            return Err(CliError::fit(
+                format!(
+                    "Every data point is an outlier at --remove-outliers {}",
+                    threshold
+                ),
+                algo::AlgoError::EmptyData,
+            ));
+        }
+        if !quiet {
+            eprintln!(
+                "removed {} of {} data points as outliers",
+                input_vals.nrows() - kept.len(),
+                input_vals.nrows()
+            );
+        }
+        input_vals = kept_vals;
+        sources = kept.iter().map(|&i| sources[i].clone()).collect();
+        retained_rows = Some(kept);
+    }
+    let features = input_vals.select(Axis(1), &columns);
+    let feature_names: Vec<String> = columns
+        .iter()
+        .map(|&j| match &header {
+            Some(names) => names[j].clone(),
+            None => format!("feature_{}", j),
+        })
+        .collect();
+    if metric_name == "cosine" {
+        let n_zero_rows = features
+            .outer_iter()
+            .filter(|row| row.iter().all(|&val| val == 0.0))
+            .count();
+        if n_zero_rows > 0 {
+            log::warn!(
+                "Warning: {} data points are all zeros and have no direction, they are \
+                 equally far from every cluster under the cosine metric",
+                n_zero_rows
+            );
+        }
+    }
+    // the scaler is fit on the clustered columns only, the output keeps the original values
+    timings.start("normalize");
+    let (scaler, features) = match normalize {
+        Some(method) => {
+            let (scaler, scaled) = Scaler::fit(method, &features);
+            (Some(scaler), scaled)
+        }
+        None => (None, features),
+    };
+    // the centers are fit on the sample, all data points are assigned to them afterwards
+    let (features, all_features) = match sample_fraction {
+        Some(fraction) => {
+            let (sample, _) = sample_rows(&features, fraction, &mut StdRng::seed_from_u64(seed));
+            log::info!(
+                "fitting on {} of {} data points",
+                sample.nrows(),
+                features.nrows()
+            );
+            (sample, Some(features))
+        }
+        None => (features, None),
+    };
+    let fuzzifier = if args["--auto-q"].value.get_flag().unwrap() {
+        let fuzzifier = algo::suggest_fuzzifier(&features);
+        if !quiet {
+            eprintln!("Using fuzzifier: {:.3}", fuzzifier);
+        }
+        fuzzifier
+    } else {
+        fuzzifier
+    };
+    let init = match init_spec.as_str() {
+        "random" => algo::InitStrategy::RandomBounds,
+        "kmeans++" => algo::InitStrategy::KMeansPlusPlus,
+        "sample" => algo::InitStrategy::RandomDataPoints,
+        "quantile" => algo::InitStrategy::Quantile,
+        spec if spec.starts_with("provided:") => {
+            let centers_fname = &spec["provided:".len()..];
+            if !std::path::Path::new(centers_fname).is_file() {
+                return Err(CliError::io_message(format!(
+                    "Initial centers file {} does not exist",
+                    centers_fname
+                )));
+            }
+            let centers = match read_cluster_centers(
+                centers_fname,
+                n_clusters,
+                features.ncols(),
+                delimiter,
+            ) {
+                Ok(centers) => centers,
+                Err(CsvError::ShapeMismatch { expected, got }) => {
+                    return Err(CliError::fit(
+                        format!(
+                            "Initial centers in {} need one row per cluster and one column \
+                             per feature",
+                            centers_fname
+                        ),
+                        algo::AlgoError::DimensionMismatch { expected, got },
+                    ));
+                }
+                Err(err) => return Err(CliError::io(centers_fname, err)),
+            };
+            // initial centers are given in original units
+            algo::InitStrategy::Provided(match &scaler {
+                Some(scaler) => scaler.transform(&centers),
+                None => centers,
+            })
+        }
+        other => {
+            return Err(CliError::args(format!(
+                "Unknown initialisation \"{}\", expected \"random\", \"kmeans++\", \
+                 \"sample\", \"quantile\" or \"provided:<path>\"",
+                other
+            )));
+        }
+    };
+    if init == algo::InitStrategy::RandomDataPoints && n_clusters > features.nrows() {
+        return Err(CliError::fit(
+            "Can't sample the initial centers",
+            algo::AlgoError::TooManyClusters {
+                k: n_clusters,
+                n: features.nrows(),
+            },
+        ));
+    }
+    let mut config = algo::FuzzyKMeansConfig::new(n_clusters, n_iter, fuzzifier);
+    config.init = init;
+    config.metric = metric.clone();
+    config.seed = Some(seed);
+    config.tol = tol;
+    config.track_convergence = convergence_out.is_some();
+    timings.start("fit");
+    // the cost is linear in the number of clusters, so all fits add up to one with their sum
+    let total_k = scan_ks.as_ref().map_or(n_clusters, |ks| ks.iter().sum()) * restarts;
+    let estimate = algo::estimate_runtime(features.nrows(), features.ncols(), total_k, n_iter);
+    if estimate > std::time::Duration::from_secs(60) {
+        log::warn!(
+            "Warning: estimated runtime {:.0}s. Consider fewer iterations (-n), a larger --tol \
+             or fewer --columns.",
+            estimate.as_secs_f64()
+        );
+    }
+    // progress bars are for people watching a terminal, not for logs
+    let show_progress = !quiet && std::io::stderr().is_terminal();
+    let iteration_bar = progress_bar(
+        n_iter,
+        "{prefix}[{elapsed_precise}] {bar:40} {pos}/{len} iterations, objective {msg}",
+        show_progress,
+    );
+    let mut last_shift = f64::NAN;
+    let mut on_iteration = |info: &algo::IterationInfo| {
+        if info.iteration == 1 {
+            iteration_bar.reset();
+        }
+        iteration_bar.set_position(info.iteration as u64);
+        iteration_bar.set_message(format!("{:.6}", info.objective));
+        log::info!(
+            "iteration {}: objective {:.6}, max shift {:e}",
+            info.iteration,
+            info.objective,
+            info.max_shift
+        );
+        log::debug!("iteration {} done after {:?}", info.iteration, info.elapsed);
+        last_shift = info.max_shift;
+        true
+    };
+    let mut result = if let Some(ks) = &scan_ks {
+        let mut results = algo::scan_k(&config, &features, ks, restarts)?;
+        let fpc: Vec<f64> = results
+            .iter()
+            .map(|result| metrics::partition_coefficient(&result.memberships))
+            .collect();
+        let xie_beni: Vec<f64> = results
+            .iter()
+            .map(|result| {
+                metrics::xie_beni_index(&features, &result.centers, &result.memberships, fuzzifier)
+            })
+            .collect();
+        // FPC and Xie-Beni are always reported, any other index gets a column of its own
+        let extra_scores: Option<Vec<f64>> = match validity {
+            algo::ValidityMeasure::Fpc | algo::ValidityMeasure::XieBeni => None,
+            _ => Some(
+                results
+                    .iter()
+                    .map(|result| validity.score(&features, result, fuzzifier))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        let scores = match validity {
+            algo::ValidityMeasure::Fpc => &fpc,
+            algo::ValidityMeasure::XieBeni => &xie_beni,
+            _ => extra_scores.as_ref().unwrap(),
+        };
+        let suggested = validity.best(scores).unwrap_or(0);
+        let extra = extra_scores.as_deref().map(|scores| (validity, scores));
+        if !quiet {
+            progress().suspend(|| {
+                print_scan_table(&results, &fpc, &xie_beni, extra, suggested, validity)
+            });
+        }
+        if let Some(scan_fname) = &scan_out {
+            let n_cols = if extra.is_some() { 5 } else { 4 };
+            let mut report = Array2::<f64>::zeros((results.len(), n_cols));
+            for (i, (mut row, result)) in report.outer_iter_mut().zip(&results).enumerate() {
+                row[0] = result.centers.nrows() as f64;
+                row[1] = result.objective;
+                row[2] = fpc[i];
+                row[3] = xie_beni[i];
+                if let Some((_, scores)) = extra {
+                    row[4] = scores[i];
+                }
+            }
+            let mut report_header = vec!["k", "objective", "fpc", "xie_beni"];
+            let extra_name = validity.name().replace('-', "_");
+            if extra.is_some() {
+                report_header.push(&extra_name);
+            }
+            let report_header: Vec<String> = report_header.into_iter().map(String::from).collect();
+            write_csv(
+                &report,
+                open_output(scan_fname)?,
+                out_delimiter,
+                Some(&report_header),
+                None,
+            )
+            .map_err(|err| CliError::io(scan_fname, err))?;
+        }
+        let best = results.swap_remove(suggested);
+        if !fit_best {
+            iteration_bar.finish_and_clear();
+            timings.stop();
+            if show_timings {
+                timings.print();
+            }
+            return Ok(Some(match &scaler {
+                Some(scaler) => algo::ClusteringResult {
+                    centers: scaler.inverse_transform(&best.centers),
+                    ..best
+                },
+                None => best,
+            }));
+        }
+        best
+    } else if restarts > 1 {
+        let restart_bar = progress_bar(
+            restarts,
+            "[{elapsed_precise}] {bar:40} {pos}/{len} restarts",
+            show_progress,
+        );
+        let restart_seconds = Mutex::new(vec![0.0; restarts]);
+        let on_restart_iteration = |restart: usize, info: &algo::IterationInfo| {
+            restart_seconds.lock().unwrap()[restart] = info.elapsed.as_secs_f64();
+            if info.iteration == 1 {
+                iteration_bar.reset();
+            }
+            iteration_bar.set_prefix(format!("restart {}: ", restart + 1));
+            iteration_bar.set_position(info.iteration as u64);
+            iteration_bar.set_message(format!("{:.6}", info.objective));
+            // same stopping rule as the fit, which doesn't report when a restart is done
+            if info.iteration >= n_iter || info.max_shift <= tol {
+                restart_bar.inc(1);
+            }
+            true
+        };
+        let results =
+            algo::fit_restarts_with_callback(&config, &features, restarts, &on_restart_iteration);
+        restart_bar.finish_and_clear();
+        timings.restarts = restart_seconds.into_inner().unwrap();
+        let mut results = results?;
+        let best = (0..results.len())
+            .min_by(|&a, &b| results[a].objective.total_cmp(&results[b].objective))
+            .unwrap();
+        if !quiet {
+            progress().suspend(|| print_restart_table(&results, best));
+        }
+        results.swap_remove(best)
+    } else if density_weighted {
+        algo::cluster_k_means_fuzzy_density_weighted(
+            n_clusters,
+            n_iter,
+            fuzzifier,
+            &features,
+            None,
+            Some(seed),
+        )?
+    } else {
+        let algorithm: Box<dyn ClusteringAlgorithm<f64>> = if hard {
+            Box::new(algo::HardKMeansConfig {
+                tol,
+                seed: Some(seed),
+                init: config.init.clone(),
+                track_convergence: config.track_convergence,
+                ..algo::HardKMeansConfig::new(n_clusters, n_iter)
+            })
+        } else {
+            Box::new(config.clone())
+        };
+        algorithm.fit_with_callback(&features, &mut on_iteration)?
+    };
+    if let Some(threshold) = merge_threshold {
+        loop {
+            let merged = algo::merge_close_clusters(
+                &result,
+                &features,
+                fuzzifier,
+                threshold,
+                metric.as_ref(),
+            )?;
+            if merged.centers.nrows() == result.centers.nrows() {
+                break;
+            }
+            if !quiet {
+                progress().suspend(|| {
+                    eprintln!(
+                        "merged {} clusters into {}",
+                        result.centers.nrows(),
+                        merged.centers.nrows()
+                    )
+                });
+            }
+            if !auto_k {
+                result = merged;
+                break;
+            }
+            let refit = algo::FuzzyKMeansConfig {
+                k: merged.centers.nrows(),
+                init: algo::InitStrategy::Provided(merged.centers),
+                ..config.clone()
+            };
+            result = refit.fit_with_callback(&features, &mut on_iteration)?;
+        }
+    }
+    iteration_bar.finish_and_clear();
+    timings.start("assign");
+    let features = match all_features {
+        Some(features) => {
+            let labels = algo::assign_crisp(&features, &result.centers, metric.as_ref())?;
+            result.memberships = if hard {
+                Array2::from_shape_fn((features.nrows(), result.centers.nrows()), |(i, j)| {
+                    if labels[i] == j {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                })
+            } else {
+                // the only membership pass after the fit, logged so extra ones would show
+                log::debug!("membership pass over {} points", features.nrows());
+                algo::predict_memberships(&features, &result.centers, fuzzifier, metric.as_ref())?
+            };
+            result.labels = labels;
+            result.objective = algo::fcm_objective(
+                &features,
+                &result.centers,
+                &result.memberships,
+                fuzzifier,
+                metric.as_ref(),
+            )?;
+            features
+        }
+        None => features,
+    };
+    if sort_by_size {
+        result = algo::relabel_by_size(result);
+    }
+    // merging may have reduced the number of clusters
+    let n_clusters = result.centers.nrows();
+    check_cluster_labels(cluster_labels_spec.as_deref(), n_clusters)?;
+    // distances and memberships below need the centers in the scaled space, everything reported
+    // gets them in original units
+    let unscaled_centers = match &scaler {
+        Some(scaler) => scaler.inverse_transform(&result.centers),
+        None => result.centers.clone(),
+    };
+    let shift_str = if last_shift.is_nan() {
+        String::new()
+    } else {
+        format!(" (max shift {:e})", last_shift)
+    };
+    if !quiet {
+        let algorithm = if hard { "hard" } else { "fuzzy" };
+        if result.converged {
+            eprintln!(
+                "{} k-means converged after {} iteration{}{}",
+                algorithm,
+                result.n_iter,
+                if result.n_iter == 1 { "" } else { "s" },
+                shift_str
+            );
+        } else {
+            eprintln!(
+                "{} k-means hit iteration cap {} without converging{}",
+                algorithm, n_iter, shift_str
+            );
+        }
+    }
+    if verbose {
+        match &scaler {
+            Some(_) => eprintln!(
+                "{}",
+                algo::ClusteringResult {
+                    centers: unscaled_centers.clone(),
+                    ..result.clone()
+                }
+            ),
+            None => eprintln!("{}", result),
+        }
+    }
+    if !no_table && !quiet {
+        let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
+        print_cluster_table(&result.labels, &result.memberships, term_width);
+    }
+    let cluster_summary = ClusterSummary::from_result(&result, &features, metric.as_ref())?;
+    if !quiet {
+        let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
+        eprintln!();
+        print_cluster_summary(
+            &cluster_summary,
+            &unscaled_centers,
+            &feature_names,
+            term_width,
+        );
+    }
+    // carry all input columns through, including those not clustered on
+    let mut out_vals = input_vals.clone();
+    out_vals
+        .push_column(result.labels.mapv(|label| label as f64).view())
+        .unwrap();
+    if with_distances {
+        let (_, dists) =
+            algo::assign_labels_with_dist(&features, &result.centers, metric.as_ref())?;
+        out_vals.push_column(dists.view()).unwrap();
+    }
+
+    let mut out_header = match header {
+        Some(names) => Some(names),
+        None if write_header => Some(
+            (0..input_vals.ncols())
+                .map(|j| format!("feature_{}", j))
+                .collect::<Vec<String>>(),
+        ),
+        None => None,
+    };
+    if let Some(names) = out_header.as_mut() {
+        names.push(String::from("cluster"));
+        if with_distances {
+            names.push(String::from("dist_to_center"));
+        }
+        if source_column {
+            names.push(String::from("source"));
+        }
+    }
+    timings.start("write");
+    for (format, fname) in &outputs {
+        match format {
+            OutputFormat::Json => {
+                let mut doc = results_json(
+                    &result.labels,
+                    &result.memberships,
+                    &unscaled_centers,
+                    &feature_names,
+                );
+                doc["clusters"] = serde_json::to_value(&cluster_summary).unwrap();
+                // the output can't include the time it takes to write itself, the running
+                // phase is left out
+                if show_timings {
+                    doc["timings"] = serde_json::to_value(&timings).unwrap();
+                }
+                writeln!(open_output(fname)?, "{}", doc)
+                    .map_err(|err| CliError::file(fname, err))?;
+            }
+            OutputFormat::Npy => write_npy(&out_vals, open_output(fname)?)
+                .map_err(|err| CliError::file(fname, err))?,
+            OutputFormat::Csv => {
+                let mut cells = labeled_cells(
+                    &out_vals,
+                    float_format,
+                    input_vals.ncols(),
+                    &result.labels,
+                    cluster_labels.as_ref(),
+                );
+                if source_column {
+                    let source =
+                        Array2::from_shape_fn((sources.len(), 1), |(i, _)| sources[i].clone());
+                    cells = ndarray::concatenate(Axis(1), &[cells.view(), source.view()]).unwrap();
+                }
+                match &retained_rows {
+                    // the index column is written here, numbering the rows as in the input
+                    Some(rows) if !no_row_index => {
+                        let index =
+                            Array2::from_shape_fn((rows.len(), 1), |(i, _)| rows[i].to_string());
+                        let cells =
+                            ndarray::concatenate(Axis(1), &[index.view(), cells.view()]).unwrap();
+                        let index_header = out_header.as_ref().map(|names| {
+                            std::iter::once(String::from("index"))
+                                .chain(names.iter().cloned())
+                                .collect::<Vec<String>>()
+                        });
+                        write_csv(
+                            &cells,
+                            open_output(fname)?,
+                            out_delimiter,
+                            index_header.as_deref(),
+                            None,
+                        )
+                    }
+                    _ => write_csv(
+                        &cells,
+                        open_output(fname)?,
+                        out_delimiter,
+                        out_header.as_deref(),
+                        (!no_row_index).then_some("index"),
+                    ),
+                }
+                .map_err(|err| CliError::io(fname, err))?;
+            }
+        }
+    }
+
+    if let Some(truth_fname) = ground_truth {
+        let mut truth_vals: Array2<f64> =
+            try_read_csv_delimited(&truth_fname, delimiter, !no_header)
+                .map_err(|err| CliError::io(&truth_fname, err))?;
+        if let Some(rows) = &retained_rows {
+            truth_vals = truth_vals.select(Axis(0), rows);
+        }
+        let labels_true = truth_vals
+            .index_axis(Axis(1), truth_vals.ncols() - 1)
+            .mapv(|val| val as usize);
+        let n_true = labels_true.iter().max().map_or(0, |max| max + 1);
+        let cm = metrics::confusion_matrix(&labels_true, &result.labels, n_true, n_clusters)?;
+        if !quiet {
+            print_confusion_matrix(&cm);
+        }
+        if let Some(cm_fname) = confusion_out {
+            to_csv(cm, cm_fname.clone(), out_delimiter)
+                .map_err(|err| CliError::io(&cm_fname, err))?;
+        }
+    }
+
+    if let Some(centers_fname) = centers_out {
+        write_centers_to(
+            &format_values(&unscaled_centers, float_format),
+            &feature_names,
+            open_output(&centers_fname)?,
+            out_delimiter,
+        )
+        .map_err(|err| CliError::io(&centers_fname, err))?;
+    }
+
+    if let Some(memberships_fname) = memberships_out {
+        write_memberships_to(
+            &format_values(&result.memberships, float_format),
+            open_output(&memberships_fname)?,
+            out_delimiter,
+        )
+        .map_err(|err| CliError::io(&memberships_fname, err))?;
+    }
+
+    if let Some(dists_fname) = distance_matrix_out {
+        let dists = algo::compute_distance_matrix(&features, &result.centers, metric.as_ref())?;
+        let dists_header: Vec<String> = (0..n_clusters).map(|j| format!("dist_{}", j)).collect();
+        write_csv(
+            &format_values(&dists, float_format),
+            open_output(&dists_fname)?,
+            out_delimiter,
+            Some(&dists_header),
+            None,
+        )
+        .map_err(|err| CliError::io(&dists_fname, err))?;
+    }
+
+    if let Some(convergence_fname) = convergence_out {
+        // one row per iteration, one column per cluster as fitted
+        let history = &result.center_movement_history;
+        let n_fitted = history
+            .first()
+            .map_or(n_clusters, |movement| movement.len());
+        let movements = Array2::from_shape_fn((history.len(), n_fitted), |(i, j)| history[i][j]);
+        let movement_header: Vec<String> =
+            (0..n_fitted).map(|j| format!("movement_{}", j)).collect();
+        write_csv(
+            &format_values(&movements, float_format),
+            open_output(&convergence_fname)?,
+            out_delimiter,
+            Some(&movement_header),
+            Some("iteration"),
+        )
+        .map_err(|err| CliError::io(&convergence_fname, err))?;
+    }
+
+    if let Some(model_fname) = model_out {
+        let model = Model {
+            format_version: MODEL_FORMAT_VERSION,
+            feature_names: feature_names.clone(),
+            columns: columns.clone(),
+            centers: unscaled_centers
+                .outer_iter()
+                .map(|row| row.to_vec())
+                .collect(),
+            fuzzifier,
+            metric: metric_name.clone(),
+            scaler: scaler.clone(),
+        };
+        let model = serde_json::to_string_pretty(&model).unwrap();
+        std::fs::write(&model_fname, model + "\n")
+            .map_err(|err| CliError::file(&model_fname, err))?;
+    }
+
+    if let Some(linkage_fname) = linkage_out {
+        let linkage =
+            metrics::average_linkage_matrix(&features, &result.labels, n_clusters, metric.as_ref());
+        to_csv(linkage, linkage_fname.clone(), out_delimiter)
+            .map_err(|err| CliError::io(&linkage_fname, err))?;
+    }
+
+    if let Some(multi_label_fname) = multi_label_out {
+        let mut labels = algo::multi_labels(&result.memberships, multi_label_cutoff);
+        if multi_label_argmax {
+            for (i, point_labels) in labels.iter_mut().enumerate() {
+                if point_labels.is_empty() {
+                    let best = result.labels[i];
+                    point_labels.push((best, result.memberships[[i, best]]));
+                }
+            }
+        }
+        write_multi_labels(
+            &labels,
+            n_clusters,
+            multi_label_fname.clone(),
+            out_delimiter,
+            multi_label_format,
+        )
+        .map_err(|err| CliError::io(&multi_label_fname, err))?;
+    }
+
+    if json_summary || json_out.is_some() {
+        let mut summary = Summary::from_result(
+            &result,
+            &features,
+            fuzzifier,
+            metric.as_ref(),
+            scaler.as_ref(),
+        )?;
+        if verbose {
+            let (j, l, overlap) = metrics::max_cluster_overlap(&result.memberships);
+            summary.max_overlap = Some(ClusterOverlap {
+                clusters: (j, l),
+                overlap,
+            });
+        }
+        let summary = serde_json::to_string(&summary).unwrap();
+        match json_out {
+            Some(json_fname) => std::fs::write(&json_fname, summary + "\n")
+                .map_err(|err| CliError::file(&json_fname, err))?,
+            None => println!("{}", summary),
+        }
+    }
+    timings.stop();
+    if show_timings && !json_output {
+        timings.print();
+    }
+    Ok(Some(algo::ClusteringResult {
+        centers: unscaled_centers,
+        ..result
+    }))
+}
+
+/// Assign the input to the clusters of a model and write the labeled data points
+///
+/// # Arguments
+///
+/// * `args` - parsed command line parameters
+fn predict(args: &ArgConfig) -> Result<(), CliError> {
+    let infname = args["-i"].value.get_str().unwrap();
+    let ofname = args["-o"].value.get_str().unwrap();
+    let model_fname = args["--model"].value.get_str().unwrap();
+    let no_header = args["--no-header"].value.get_flag().unwrap();
+    let write_header = args["--write-header"].value.get_flag().unwrap();
+    let no_row_index = args["--no-row-index"].value.get_flag().unwrap();
+    let with_distances = args["--distances"].value.get_flag().unwrap();
+    let memberships_out = args["--memberships"].value.get_str().ok();
+    let no_table = args["--no-table"].value.get_flag().unwrap();
+    let quiet = args["--quiet"].value.get_flag().unwrap();
+    init_logging(log_level(
+        args["--verbose"].value.get_count().unwrap(),
+        quiet,
+    ));
+    for line in value_sources(args) {
+        log::info!("{}", line);
+    }
+    let float_format = parse_float_format(args)?;
+    let outputs = parse_output_formats(args)?;
+    let cluster_labels_spec = args["--cluster-labels"].value.get_str().ok();
+    let cluster_labels = parse_cluster_labels_arg(cluster_labels_spec.as_deref())?;
+    let (delimiter, out_delimiter) = parse_delimiters(args)?;
+    if ofname == "-" && memberships_out.as_deref() == Some("-") {
+        return Err(CliError::args(String::from(
+            "Only one output can go to stdout, but -o, --memberships all do",
+        )));
+    }
+
+    let model =
+        std::fs::read_to_string(&model_fname).map_err(|err| CliError::file(&model_fname, err))?;
+    let model: Model = serde_json::from_str(&model).map_err(|err| {
+        CliError::io_message(format!("{}: not a model file: {}", model_fname, err))
+    })?;
+    if model.format_version != MODEL_FORMAT_VERSION {
+        return Err(CliError::io_message(format!(
+            "{}: unsupported model format version {}, expected {}",
+            model_fname, model.format_version, MODEL_FORMAT_VERSION
+        )));
+    }
+    let metric = parse_metric(&model.metric)?;
+    let n_features = model.feature_names.len();
+    if model
+        .centers
+        .iter()
+        .any(|center| center.len() != n_features)
+    {
+        return Err(CliError::io_message(format!(
+            "{}: every center needs {} values, one per feature",
+            model_fname, n_features
+        )));
+    }
+    let whitening = model
+        .scaler
+        .iter()
+        .flat_map(|scaler| scaler.matrix.iter().chain(&scaler.inverse));
+    if model
+        .scaler
+        .as_ref()
+        .is_some_and(|scaler| scaler.offset.len() != n_features || scaler.scale.len() != n_features)
+        || whitening.into_iter().any(|matrix| {
+            matrix.len() != n_features || matrix.iter().any(|row| row.len() != n_features)
+        })
+    {
+        return Err(CliError::io_message(format!(
+            "{}: the scaler doesn't match the {} features",
+            model_fname, n_features
+        )));
+    }
+    let centers =
+        Array2::from_shape_vec((model.centers.len(), n_features), model.centers.concat()).unwrap();
+    check_cluster_labels(cluster_labels_spec.as_deref(), centers.nrows())?;
+
+    let (header, input_vals) = read_input(&infname, delimiter, !no_header)?;
+    // columns are matched by name where possible, the input may order them differently
+    let columns = match &header {
+        Some(names) => model
+            .feature_names
+            .iter()
+            .map(|feature| {
+                names
+                    .iter()
+                    .position(|name| name == feature)
+                    .ok_or_else(|| {
+                        CliError::args(format!("{} has no column \"{}\"", infname, feature))
+                    })
+            })
+            .collect::<Result<Vec<usize>, CliError>>()?,
+        None => model.columns.clone(),
+    };
+    if let Some(&missing) = columns.iter().find(|&&j| j >= input_vals.ncols()) {
+        return Err(CliError::args(format!(
+            "{} has {} columns, but the model needs column {}",
+            infname,
+            input_vals.ncols(),
+            missing
+        )));
+    }
+    let features = input_vals.select(Axis(1), &columns);
+    // memberships are computed where the model was fit, in the scaled space
+    let (features, scaled_centers) = match &model.scaler {
+        Some(scaler) => (scaler.transform(&features), scaler.transform(&centers)),
+        None => (features, centers.clone()),
+    };
+    let memberships =
+        algo::predict_memberships(&features, &scaled_centers, model.fuzzifier, metric.as_ref())?;
+    let (labels, dists) =
+        algo::assign_labels_with_dist(&features, &scaled_centers, metric.as_ref())?;
+
+    if !no_table && !quiet {
+        let (term_width, _) = term_size::dimensions().unwrap_or((80, 0));
+        print_cluster_table(&labels, &memberships, term_width);
+    }
+    let mut out_vals = input_vals.clone();
+    out_vals
+        .push_column(labels.mapv(|label| label as f64).view())
+        .unwrap();
+    if with_distances {
+        out_vals.push_column(dists.view()).unwrap();
+    }
+    let mut out_header = match header {
+        Some(names) => Some(names),
+        None if write_header => Some(
+            (0..input_vals.ncols())
+                .map(|j| format!("feature_{}", j))
+                .collect::<Vec<String>>(),
+        ),
+        None => None,
+    };
+    if let Some(names) = out_header.as_mut() {
+        names.push(String::from("cluster"));
+        if with_distances {
+            names.push(String::from("dist_to_center"));
+        }
+    }
+    for (format, fname) in &outputs {
+        match format {
+            OutputFormat::Json => write_results_json(
+                &labels,
+                &memberships,
+                &centers,
+                &model.feature_names,
+                open_output(fname)?,
+            )
+            .map_err(|err| CliError::io(fname, err.into()))?,
+            OutputFormat::Npy => write_npy(&out_vals, open_output(fname)?)
+                .map_err(|err| CliError::file(fname, err))?,
+            OutputFormat::Csv => {
+                let cells = labeled_cells(
+                    &out_vals,
+                    float_format,
+                    input_vals.ncols(),
+                    &labels,
+                    cluster_labels.as_ref(),
+                );
+                write_csv(
+                    &cells,
+                    open_output(fname)?,
+                    out_delimiter,
+                    out_header.as_deref(),
+                    (!no_row_index).then_some("index"),
+                )
+                .map_err(|err| CliError::io(fname, err))?;
+            }
+        }
+    }
+    if let Some(memberships_fname) = memberships_out {
+        write_memberships_to(
+            &format_values(&memberships, float_format),
+            open_output(&memberships_fname)?,
+            out_delimiter,
+        )
+        .map_err(|err| CliError::io(&memberships_fname, err))?;
+    }
+    Ok(())
+}
+
+/// Run once, then again every time the input file changes
+///
+/// All runs use the same seed, so results only change because the data did.
+///
+/// # Arguments
+///
+/// * `args` - parsed command line parameters
+#[cfg(feature = "watch")]
+fn watch(mut args: ArgConfig) -> Result<(), CliError> {
+    use notify::{RecursiveMode, Watcher};
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const RETRY_DELAY: Duration = Duration::from_millis(100);
+    const MAX_ATTEMPTS: usize = 20;
+
+    if args["-s"].value.is_none() {
+        let seed = rand::random::<u64>() >> 1;
+        if !args["--quiet"].value.get_flag().unwrap() {
+            eprintln!("Using seed: {}", seed);
+        }
+        args.get_mut("-s").unwrap().value = ArgType::SeedType(Some(seed));
+    }
+
+    let infname = args["-i"].value.get_str().unwrap();
+    if infname == "-" {
+        return Err(CliError::args(String::from(
+            "--watch needs an input file, stdin can't be watched",
+        )));
+    }
+    let infname = Path::new(&infname);
+    // watch the directory, editors often replace the file instead of writing to it
+    let dir = match infname.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            return Err(CliError::io_message(format!(
+                "Can't watch for file changes: {}",
+                err
+            )))
+        }
+    };
+    if let Err(err) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        return Err(CliError::io_message(format!(
+            "Can't watch {}: {}",
+            dir.display(),
+            err
+        )));
+    }
+
+    print_watch_report(&run(&args));
+    for event in rx.iter() {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("watching {} failed: {}", dir.display(), err);
+                continue;
+            }
+        };
+        let touches_input = event
+            .paths
+            .iter()
+            .any(|path| path.file_name() == infname.file_name());
+        if !touches_input || !(event.kind.is_modify() || event.kind.is_create()) {
+            continue;
+        }
+        // saving a file usually causes a burst of events
+        while rx.recv_timeout(RETRY_DELAY).is_ok() {}
+
+        // the file may be missing or half written while it is being saved
+        let mut result = Err(CliError::io_message(format!(
+            "{} stayed unreadable",
+            infname.display()
+        )));
+        for _ in 0..MAX_ATTEMPTS {
+            if std::fs::metadata(infname).is_ok_and(|meta| meta.len() > 0) {
+                // only the last attempt's error is reported, a read or parse error of an earlier
+                // attempt is retried
+                result = run(&args);
+                if !matches!(
+                    result,
+                    Err(CliError {
+                        error: Error::Io(_),
+                        ..
+                    })
+                ) {
+                    break;
+                }
+            }
+            std::thread::sleep(RETRY_DELAY);
+        }
+        print_watch_report(&result);
+    }
+    Ok(())
+}
+
+/// Print the time of a `--watch` run and the quality of its result
+///
+/// # Arguments
+///
+/// * `result` - result of the run
+#[cfg(feature = "watch")]
+fn print_watch_report(result: &Result<Option<algo::ClusteringResult<f64>>, CliError>) {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let time = format!(
+        "{:02}:{:02}:{:02} UTC",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    );
+    match result {
+        Ok(None) => {}
+        Ok(Some(result)) => println!(
+            "[{}] FPC: {:.4}, objective: {:.6}",
+            time,
+            metrics::partition_coefficient(&result.memberships),
+            result.objective
+        ),
+        Err(err) => eprintln!(
+            "[{}] run failed, waiting for the next change: {}",
+            time, err
+        ),
+    }
+}
+
+/// Run the command, for fit once or watching the input depending on the parameters
+///
+/// # Arguments
+///
+/// * `command` - command to run
+/// * `args`    - parsed command line parameters
+fn dispatch(command: Command, args: ArgConfig) -> Result<(), CliError> {
+    // fail before reading or fitting anything instead of losing the result
+    validate_paths(command, &args)?;
+    match command {
+        Command::Predict => predict(&args),
+        // scan is fit with --scan-k
+        Command::Scan => run(&args).map(|_| ()),
+        Command::Fit => {
+            #[cfg(feature = "watch")]
+            // a dry run doesn't fit anything to repeat
+            if args["--watch"].value.get_flag().unwrap()
+                && !args["--dry-run"].value.get_flag().unwrap()
+            {
+                return watch(args);
+            }
+            run(&args).map(|_| ())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+    // hidden from the help, meant for packaging rather than for users
+    if args.get(1).map(String::as_str) == Some("--generate-completions") {
+        return match completion_script(args.get(2).map_or("", String::as_str)) {
+            Ok(script) => {
+                print!("{}", script);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::from(err.exit_code())
+            }
+        };
+    }
+    let outcome = match parse_args(&args) {
+        Err((command, args, true)) => {
+            print_help(command, args);
+            Ok(())
+        }
+        Err((_, _, false)) => Err(CliError::args(String::from("Run with -h for usage."))),
+        #[cfg(feature = "parallel")]
+        Ok((command, args, _)) if args["--threads"].value.get_size().is_ok() => {
+            let n_threads = args["--threads"].value.get_size().unwrap();
+            if n_threads == 0 {
+                Err(CliError::args(String::from(
+                    "Parameter --threads needs to be at least 1",
+                )))
+            } else {
+                // a pool of our own leaves the global one alone
+                match rayon::ThreadPoolBuilder::new()
+                    .num_threads(n_threads)
+                    .build()
+                {
+                    Ok(pool) => pool.install(|| dispatch(command, args)),
+                    Err(err) => Err(CliError::io_message(format!(
+                        "Can't start {} threads: {}",
+                        n_threads, err
+                    ))),
+                }
+            }
+        }
+        Ok((command, args, _)) => dispatch(command, args),
+    };
+    match outcome {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(err.exit_code())
+        }
+    }
+    // let n_clusters: usize = 3;
+    // let n_iter: usize = 10;
+    // let fuzzifier: f64 = 2.0;
+    // let input_vals: Array2<f64> = read_csv(String::from("files/sample_data.csv"));
+    // let clusters = algo::cluster_k_means_fuzzy(n_clusters, n_iter, fuzzifier, &input_vals);
+    // let out_vals = algo::compute_nearest(&input_vals, &clusters);
+    // let mut memberships = Array2::<f64>::zeros((out_vals.dim().0, n_clusters));
+    // algo::compute_memberships(fuzzifier, &input_vals, &clusters, &mut memberships);
+
+    // to_csv(out_vals, String::from("files/predicted_classes.csv"), b';');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Held while parsing, so tests that set environment variables don't leak them into others
+    static ENV: Mutex<()> = Mutex::new(());
+
+    fn lock_env() -> MutexGuard<'static, ()> {
+        // a failed test holding the lock doesn't make the environment unusable
+        ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Command line of the program with `args` after the program name
+    fn cmdline(args: &[&str]) -> Vec<String> {
+        std::iter::once("k_means_rs")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    /// `parse_args` on `args`, with the environment locked
+    fn parse(args: &[&str]) -> Result<(Command, ArgConfig, bool), (Command, ArgConfig, bool)> {
+        let _env = lock_env();
+        parse_args(&cmdline(args))
+    }
+
+    /// Value of every parameter after parsing `args`, which must succeed
+    fn parsed(args: &[&str]) -> BTreeMap<String, ArgType> {
+        match parse(args) {
+            Ok((_, conf, _)) => conf
+                .into_iter()
+                .map(|(key, arg)| (key, arg.value))
+                .collect(),
+            Err(_) => panic!("{:?} didn't parse", args),
+        }
+    }
+
+    fn fails(args: &[&str]) -> bool {
+        matches!(parse(args), Err((_, _, false)))
+    }
+
+    #[test]
+    fn key_value_syntax_matches_separate_value() {
+        let separate = parsed(&[
+            "-i",
+            "in.csv",
+            "--clusters",
+            "3",
+            "-q",
+            "1.5",
+            "--no-header",
+        ]);
+        assert_eq!(separate["-k"], ArgType::SizeType(Some(3)));
+        assert_eq!(
+            parsed(&[
+                "--input=in.csv",
+                "--clusters=3",
+                "--fuzzifier=1.5",
+                "--no-header"
+            ]),
+            separate
+        );
+        // both syntaxes mixed in one command line
+        assert_eq!(
+            parsed(&["-i", "in.csv", "--clusters=3", "-q", "1.5", "--no-header"]),
+            separate
+        );
+        // only the first = splits
+        assert_eq!(
+            parsed(&["-i", "in.csv", "--columns=a=b"])["--columns"],
+            ArgType::StringType(Some(String::from("a=b")))
+        );
+    }
+
+    #[test]
+    fn key_value_syntax_errors() {
+        assert!(fails(&["-i", "in.csv", "--clusters="]));
+        assert!(fails(&["-i", "in.csv", "--clusters=three"]));
+        assert!(fails(&["-i", "in.csv", "--hard=yes"]));
+        // an empty text is a value
+        assert!(!fails(&["-i", "in.csv", "--columns="]));
+    }
+
+    #[test]
+    fn trailing_option_without_value_is_an_error() {
+        assert!(fails(&["-i", "in.csv", "-k"]));
+        assert!(fails(&["-i", "in.csv", "--clusters"]));
+        assert!(fails(&["-i"]));
+    }
+
+    #[test]
+    fn cli_errors_wrap_library_errors() {
+        let err = CliError::args("Parameter -i needs to be provided");
+        assert!(matches!(err.error, Error::Config(_)));
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!(err.to_string(), "Parameter -i needs to be provided");
+
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let err = CliError::file("in.csv", not_found);
+        assert!(matches!(err.error, Error::Io(_)));
+        assert_eq!(err.exit_code(), 3);
+        assert_eq!(err.to_string(), "in.csv: entity not found");
+
+        let err = CliError::fit("-k", algo::AlgoError::NoClusters);
+        assert!(matches!(err.error, Error::Fit(algo::AlgoError::NoClusters)));
+        assert_eq!(err.exit_code(), 4);
+        assert_eq!(
+            err.to_string(),
+            "-k: Number of clusters needs to be at least 1"
+        );
+    }
+
+    #[test]
+    fn invalid_value_names_parameter_and_type() {
+        let conf = arg_config(Command::Fit);
+        assert_eq!(
+            conf["-k"].parse_cmdline("-k", "3.5"),
+            Err(String::from("invalid value '3.5' for -k: expected integer"))
+        );
+        // the name is the one given, which may be an alias
+        assert_eq!(
+            conf["-k"].parse_cmdline("--clusters", "many"),
+            Err(String::from(
+                "invalid value 'many' for --clusters: expected integer"
+            ))
+        );
+        assert_eq!(
+            conf["--tol"].parse_cmdline("--tol", "small"),
+            Err(String::from(
+                "invalid value 'small' for --tol: expected number"
+            ))
+        );
+        assert_eq!(
+            conf["-k"].parse_cmdline("-k", "3"),
+            Ok(ArgType::SizeType(Some(3)))
+        );
+        assert!(fails(&["-i", "in.csv", "-k", "3.5"]));
+        assert!(fails(&["-i", "in.csv", "--clusters=3.5"]));
+    }
+
+    /// Lines of the row of `name` in the help of fit at `term_width`
+    fn help_row(name: &str, term_width: usize) -> Vec<String> {
+        let help = help_text(Command::Fit, &arg_config(Command::Fit), term_width);
+        let mut lines = help.lines().skip_while(|line| !line.starts_with(name));
+        let first = lines.next().unwrap().to_string();
+        std::iter::once(first)
+            .chain(
+                lines
+                    .take_while(|line| line.starts_with("   "))
+                    .map(str::to_string),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn help_at_fixed_widths() {
+        assert_eq!(
+            help_row(" -k, --clusters", 80),
+            [" -k, --clusters                   <int>         5            Number of Clusters"]
+        );
+        // choices longer than the value column break after a hyphen instead of sticking out
+        assert_eq!(
+            help_row(" --validity", 80)[..7],
+            [
+                " --validity                       one of: fpc|  \"xie-beni\"   Validity index that",
+                "                                  xie-beni|                  picks the suggested",
+                "                                  davies-                    k of --scan-k. One",
+                "                                  bouldin|                   of \"xie-beni\",",
+                "                                  silhouette|                \"davies-bouldin\"",
+                "                                  calinski-                  (lowest is best),",
+                "                                  harabasz                   \"fpc\", \"silhouette\"",
+            ]
+        );
+        // a wider terminal leaves more room for the description
+        assert_eq!(
+            help_row(" --validity", 120)[0],
+            " --validity                       one of: fpc|  \"xie-beni\"   Validity index that picks \
+             the suggested k of --scan-k. One"
+        );
+
+        for term_width in [60, 80, 120] {
+            for command in Command::ALL {
+                let help = help_text(command, &arg_config(command), term_width);
+                let header = help
+                    .lines()
+                    .find(|line| line.starts_with(" Parameter"))
+                    .unwrap();
+                let columns = ["Default", "Description"].map(|title| header.find(title).unwrap());
+                let mut rows = help.lines().skip_while(|line| !line.starts_with("---"));
+                assert_eq!(rows.next().unwrap().len(), term_width);
+                for row in rows {
+                    // every column ends in a gap before the next one starts
+                    for start in columns {
+                        let gap = row.get(start - 2..start).unwrap_or(" ");
+                        assert!(gap.trim().is_empty(), "{:?} at width {}", row, term_width);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write `text` to a config file named after `test` and return its path
+    fn config_file(test: &str, text: &str) -> String {
+        let path = env::temp_dir().join(format!("k_means_rs-{}-{}.toml", test, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn config_file_alone_sets_parameters() {
+        let config = config_file(
+            "config-only",
+            "input = \"in.csv\"\nclusters = 4\nseed = 9223372036854775807\nverbose = 2\n\
+             threads = 2\nout-delimiter = \";\"\nwrite-header = true\nmulti-label-cutoff = 0.3\n\
+             merge-threshold = 0.5\ncluster-labels = \"a,b,c,d\"\ndry-run = true\n",
+        );
+        let (_, conf, _) = parse(&["--config", &config]).ok().unwrap();
+        std::fs::remove_file(&config).unwrap();
+        assert_eq!(
+            conf["-i"].value,
+            ArgType::StringType(Some(String::from("in.csv")))
+        );
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(4)));
+        assert_eq!(conf["-s"].value, ArgType::SeedType(Some(u64::MAX >> 1)));
+        assert_eq!(conf["--verbose"].value, ArgType::Count(Some(2)));
+        assert_eq!(
+            conf["--out-delimiter"].value,
+            ArgType::StringType(Some(String::from(";")))
+        );
+        assert_eq!(conf["--write-header"].value, ArgType::Flag(Some(true)));
+        assert_eq!(
+            conf["--multi-label-cutoff"].value,
+            ArgType::FloatingNumber(Some(0.3))
+        );
+        assert_eq!(
+            conf["--merge-threshold"].value,
+            ArgType::FloatingNumber(Some(0.5))
+        );
+        assert_eq!(conf["--dry-run"].value, ArgType::Flag(Some(true)));
+        assert_eq!(conf["-k"].source, ValueSource::Config);
+        #[cfg(feature = "parallel")]
+        assert_eq!(conf["--threads"].value, ArgType::SizeType(Some(2)));
+    }
+
+    #[test]
+    fn command_line_overrides_config_file() {
+        let config = config_file(
+            "config-override",
+            "input = \"in.csv\"\nclusters = 4\nquiet = true\nforce = true\n",
+        );
+        let (_, conf, _) = parse(&["--config", &config, "-k", "6", "--no-force"])
+            .ok()
+            .unwrap();
+        std::fs::remove_file(&config).unwrap();
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(6)));
+        assert_eq!(conf["-k"].source, ValueSource::CommandLine);
+        assert_eq!(conf["--force"].value, ArgType::Flag(Some(false)));
+        // the rest of the file still applies
+        assert_eq!(conf["--quiet"].value, ArgType::Flag(Some(true)));
+        assert_eq!(conf["--quiet"].source, ValueSource::Config);
+    }
+
+    #[test]
+    fn unknown_config_key_is_an_error() {
+        let config = config_file("config-unknown", "input = \"in.csv\"\nclusterz = 4\n");
+        let failed = fails(&["--config", &config]);
+        let dump_only = config_file("config-dump", "dump-config = \"out.toml\"\n");
+        let dump_failed = fails(&["-i", "in.csv", "--config", &dump_only]);
+        std::fs::remove_file(&config).unwrap();
+        std::fs::remove_file(&dump_only).unwrap();
+        assert!(failed);
+        // --config and --dump-config can't be set in a config file
+        assert!(dump_failed);
+        assert!(RunConfig::read("/nonexistent/config.toml").is_err());
+    }
+
+    #[test]
+    fn dumped_config_covers_every_parameter() {
+        let mut conf = arg_config(Command::Fit);
+        // a value for every parameter, as if all were given
+        for arg in conf.values_mut() {
+            arg.value = match arg.default {
+                ArgType::FloatingNumber(_) => ArgType::FloatingNumber(Some(1.0)),
+                ArgType::StringType(_) => ArgType::StringType(Some(String::from("1"))),
+                ArgType::SizeType(_) => ArgType::SizeType(Some(1)),
+                ArgType::SeedType(_) => ArgType::SeedType(Some(1)),
+                ArgType::Flag(_) => ArgType::Flag(Some(true)),
+                ArgType::Count(_) => ArgType::Count(Some(1)),
+            };
+        }
+        let dumped = toml::to_string(&RunConfig::from_args(&conf)).unwrap();
+        let read: RunConfig = toml::from_str(&dumped).unwrap();
+        let values = read.values();
+        for (key, arg) in conf.iter() {
+            if key == "--config" || key == "--dump-config" {
+                continue;
+            }
+            match values.iter().find(|(dumped_key, _)| dumped_key == key) {
+                Some((_, value)) => assert_eq!(*value, arg.value, "{}", key),
+                None => panic!("{} is missing", key),
+            }
+        }
+    }
+
+    #[test]
+    fn flag_does_not_take_the_next_option() {
+        let conf = parsed(&["--no-header", "-i", "in.csv", "--hard", "-k", "3"]);
+        assert_eq!(conf["--no-header"], ArgType::Flag(Some(true)));
+        assert_eq!(
+            conf["-i"],
+            ArgType::StringType(Some(String::from("in.csv")))
+        );
+        assert_eq!(conf["--hard"], ArgType::Flag(Some(true)));
+        assert_eq!(conf["-k"], ArgType::SizeType(Some(3)));
+        // a value that looks like an option is still the value
+        assert_eq!(
+            parsed(&["-i", "--hard"])["-i"],
+            ArgType::StringType(Some(String::from("--hard")))
+        );
+    }
+
+    #[test]
+    fn flags_are_negated_by_no_prefix() {
+        assert_eq!(
+            parsed(&["-i", "in.csv", "--no-hard"])["--hard"],
+            ArgType::Flag(Some(false))
+        );
+        // --no-header is switched off by leaving out the no-
+        assert_eq!(
+            parsed(&["-i", "in.csv", "--no-header", "--header"])["--no-header"],
+            ArgType::Flag(Some(false))
+        );
+        assert!(fails(&["-i", "in.csv", "--no-clusters"]));
+    }
+
+    #[test]
+    fn repeated_flags_keep_the_last() {
+        let conf = parsed(&["-i", "in.csv", "--quiet", "--no-quiet", "--quiet"]);
+        assert_eq!(conf["--quiet"], ArgType::Flag(Some(true)));
+        let conf = parsed(&["-i", "in.csv", "--force", "--no-force"]);
+        assert_eq!(conf["--force"], ArgType::Flag(Some(false)));
+        // counted flags add up
+        assert_eq!(
+            parsed(&["-i", "in.csv", "-v", "-vv", "--verbose"])["--verbose"],
+            ArgType::Count(Some(4))
+        );
+    }
+
+    #[test]
+    fn conflicting_parameters_are_an_error() {
+        assert!(fails(&["-i", "in.csv", "--hard", "--metric", "cosine"]));
+        assert!(fails(&["-i", "in.csv", "--input-dir", "dir"]));
+        assert!(fails(&[
+            "-i",
+            "in.csv",
+            "--density-weighted",
+            "--tol",
+            "1e-3"
+        ]));
+        assert!(fails(&["-i", "in.csv", "--auto-q", "-q", "3"]));
+        // a flag switched off conflicts with nothing
+        assert!(!fails(&["-i", "in.csv", "--no-hard", "--metric", "cosine"]));
+    }
+
+    #[test]
+    fn positional_argument_is_the_input() {
+        let conf = parsed(&["data.csv", "-k", "3"]);
+        assert_eq!(
+            conf["-i"],
+            ArgType::StringType(Some(String::from("data.csv")))
+        );
+        assert_eq!(conf["-k"], ArgType::SizeType(Some(3)));
+        // after a command as well
+        assert_eq!(
+            parsed(&["scan", "data.csv", "--scan-k", "2..4"])["-i"],
+            ArgType::StringType(Some(String::from("data.csv")))
+        );
+    }
+
+    #[test]
+    fn positional_and_option_input_conflict() {
+        assert!(fails(&["data.csv", "-i", "other.csv"]));
+        assert!(fails(&["data.csv", "other.csv"]));
+    }
+
+    #[test]
+    fn positional_that_looks_like_a_number() {
+        // a number is a file name where no option takes it
+        assert_eq!(
+            parsed(&["-k", "3", "-1.5"])["-i"],
+            ArgType::StringType(Some(String::from("-1.5")))
+        );
+        assert_eq!(
+            parsed(&["2024", "-k", "3"])["-i"],
+            ArgType::StringType(Some(String::from("2024")))
+        );
+        // but the value of an option it follows
+        let conf = parsed(&["-k", "3", "data.csv"]);
+        assert_eq!(conf["-k"], ArgType::SizeType(Some(3)));
+        assert_eq!(
+            conf["-i"],
+            ArgType::StringType(Some(String::from("data.csv")))
+        );
+    }
+
+    /// Summary of two clusters with centers in three features
+    fn summary_table(term_width: usize) -> String {
+        let summary = [
+            ClusterSummary {
+                size: 12,
+                fuzzy_size: 11.456,
+                mean_distance: 0.75,
+            },
+            ClusterSummary {
+                size: 3,
+                fuzzy_size: 3.544,
+                mean_distance: f64::NAN,
+            },
+        ];
+        let centers = ndarray::array![[1.0, -2.5, 100.0], [0.125, 3.0, -7.75]];
+        let names = ["width", "height", "depth"].map(String::from);
+        cluster_summary_table(&summary, &centers, &names, term_width)
+    }
+
+    #[test]
+    fn cluster_summary_at_fixed_widths() {
+        assert_eq!(
+            summary_table(100),
+            concat!(
+                " cluster    size  fuzzy size  mean dist   width   height     depth\n",
+                "------------------------------------------------------------------\n",
+                " 0            12       11.46     0.7500  1.0000  -2.5000  100.0000\n",
+                " 1             3        3.54        NaN  0.1250   3.0000   -7.7500\n",
+            )
+        );
+        // center coordinates that don't fit are left out
+        assert_eq!(
+            summary_table(52),
+            concat!(
+                " cluster    size  fuzzy size  mean dist   width  ...\n",
+                "----------------------------------------------------\n",
+                " 0            12       11.46     0.7500  1.0000  ...\n",
+                " 1             3        3.54        NaN  0.1250  ...\n",
+            )
+        );
+        // the cluster column is always there
+        assert_eq!(summary_table(5).lines().next(), Some(" cluster    ..."));
+    }
+
+    /// Parse `args` with the environment variables `vars` set, which are removed again after
+    fn parse_with_env(
+        vars: &[(&str, &str)],
+        args: &[&str],
+    ) -> Result<(Command, ArgConfig, bool), (Command, ArgConfig, bool)> {
+        let _env = lock_env();
+        for (var, value) in vars {
+            env::set_var(var, value);
+        }
+        let result = parse_args(&cmdline(args));
+        for (var, _) in vars {
+            env::remove_var(var);
+        }
+        result
+    }
+
+    #[test]
+    fn environment_fills_in_defaults() {
+        let (_, conf, _) = parse_with_env(
+            &[
+                ("FZY_KMEANS_INPUT", "env.csv"),
+                ("FZY_KMEANS_K", "7"),
+                ("FZY_KMEANS_HARD", "yes"),
+            ],
+            &[],
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(
+            conf["-i"].value,
+            ArgType::StringType(Some(String::from("env.csv")))
+        );
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(7)));
+        assert_eq!(
+            conf["-k"].source,
+            ValueSource::Env(String::from("FZY_KMEANS_K"))
+        );
+        assert_eq!(conf["--hard"].value, ArgType::Flag(Some(true)));
+        // left alone without a variable
+        assert_eq!(conf["-n"].source, ValueSource::Default);
+    }
+
+    #[test]
+    fn environment_names_follow_aliases() {
+        let (_, conf, _) = parse_with_env(&[("FZY_KMEANS_CLUSTERS", "4")], &["-i", "in.csv"])
+            .ok()
+            .unwrap();
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(4)));
+        // empty variables count as unset
+        let (_, conf, _) = parse_with_env(&[("FZY_KMEANS_K", "")], &["-i", "in.csv"])
+            .ok()
+            .unwrap();
+        assert_eq!(conf["-k"].source, ValueSource::Default);
+    }
+
+    #[test]
+    fn command_line_overrides_environment() {
+        let (_, conf, _) = parse_with_env(
+            &[("FZY_KMEANS_K", "7"), ("FZY_KMEANS_SEED", "11")],
+            &["-i", "in.csv", "-k", "3"],
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(3)));
+        assert_eq!(conf["-k"].source, ValueSource::CommandLine);
+        assert_eq!(conf["-s"].value, ArgType::SeedType(Some(11)));
+    }
+
+    #[test]
+    fn config_file_overrides_environment() {
+        let config = config_file("config-env", "clusters = 5\n");
+        let env_config = parse_with_env(
+            &[("FZY_KMEANS_K", "7"), ("FZY_KMEANS_N", "4")],
+            &["-i", "in.csv", "--config", &config],
+        );
+        let all_three = parse_with_env(
+            &[("FZY_KMEANS_K", "7")],
+            &["-i", "in.csv", "--config", &config, "-k", "2"],
+        );
+        std::fs::remove_file(&config).unwrap();
+
+        let (_, conf, _) = env_config.ok().unwrap();
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(5)));
+        assert_eq!(conf["-k"].source, ValueSource::Config);
+        assert_eq!(conf["-n"].value, ArgType::SizeType(Some(4)));
+        let (_, conf, _) = all_three.ok().unwrap();
+        assert_eq!(conf["-k"].value, ArgType::SizeType(Some(2)));
+        assert_eq!(conf["-k"].source, ValueSource::CommandLine);
+    }
+
+    #[test]
+    fn invalid_environment_value_is_an_error() {
+        assert!(matches!(
+            parse_with_env(&[("FZY_KMEANS_K", "many")], &["-i", "in.csv"]),
+            Err((_, _, false))
+        ));
+        assert!(matches!(
+            parse_with_env(&[("FZY_KMEANS_HARD", "maybe")], &["-i", "in.csv"]),
+            Err((_, _, false))
+        ));
+        let conf = arg_config(Command::Fit);
+        assert_eq!(
+            conf["-k"].parse_env("FZY_KMEANS_K", "many"),
+            Err(String::from(
+                "Invalid value \"many\" in FZY_KMEANS_K for -k, expected a whole number of at \
+                 least 0"
+            ))
+        );
+    }
+
+    /// Every name of every parameter of `command`
+    fn all_names(command: Command) -> Vec<&'static str> {
+        arg_config(command)
+            .values()
+            .flat_map(|arg| std::iter::once(arg.cmdline_expr).chain(arg.aliases.iter().copied()))
+            .collect()
+    }
+
+    #[test]
+    fn unknown_argument_is_an_error() {
+        assert!(fails(&["-i", "in.csv", "-K", "5"]));
+        assert!(fails(&["-i", "in.csv", "--no-such-option"]));
+        assert!(fails(&["-i", "in.csv", "--clusters-count=5"]));
+    }
+
+    #[test]
+    fn unknown_argument_suggests_near_miss() {
+        let names = all_names(Command::Fit);
+        let suggest = |arg| closest_name(arg, names.iter().copied());
+        assert_eq!(suggest("-K"), Some("-k"));
+        assert_eq!(suggest("--clusers"), Some("--clusters"));
+        assert_eq!(suggest("--metirc"), Some("--metric"));
+        assert_eq!(suggest("--something-else-entirely"), None);
+    }
+
+    #[test]
+    fn bash_completion_mentions_every_long_option() {
+        let script = completion_script("bash").unwrap();
+        for command in Command::ALL {
+            // the options of a command are listed on the first line after its case label
+            let label = format!("        {})\n", command.name());
+            let section = &script[script.find(&label).unwrap() + label.len()..];
+            let opts_line = section.lines().next().unwrap().trim();
+            let opts: Vec<&str> = opts_line
+                .trim_start_matches("opts=\"")
+                .trim_end_matches('"')
+                .split(' ')
+                .collect();
+            for param in command_params(command) {
+                let names =
+                    std::iter::once(param.cmdline_expr).chain(param.aliases.iter().copied());
+                for name in names.filter(|name| name.starts_with("--")) {
+                    assert!(
+                        opts.contains(&name),
+                        "{} missing for {}",
+                        name,
+                        command.name()
+                    );
+                }
+            }
+        }
+        // every registered long option is completed for some command
+        let words: Vec<&str> = script
+            .split(|c: char| c.is_whitespace() || "\"|)".contains(c))
+            .collect();
+        for name in all_names(Command::Fit) {
+            if name.starts_with("--") {
+                assert!(words.contains(&name), "{} missing", name);
+            }
+        }
+    }
+
+    #[test]
+    fn negative_value_is_not_an_unknown_argument() {
+        let conf = parsed(&["-i", "in.csv", "--tol", "-1e-3", "-q", "-2"]);
+        assert_eq!(conf["--tol"], ArgType::FloatingNumber(Some(-1e-3)));
+        assert_eq!(conf["-q"], ArgType::FloatingNumber(Some(-2.0)));
+        assert_eq!(
+            parsed(&["-i", "in.csv", "--tol=-0.5"])["--tol"],
+            ArgType::FloatingNumber(Some(-0.5))
+        );
+        // a negative number where an integer is expected has the wrong type
+        assert!(fails(&["-i", "in.csv", "-k", "-3"]));
+    }
 }
@@ -0,0 +1,498 @@
+//! Clustering through the public API of the library
+
+use k_means_rs::algo::{
+    cluster_k_means_fuzzy, cluster_k_means_fuzzy_density_weighted, cluster_k_means_fuzzy_f32,
+    cluster_k_means_fuzzy_weighted, compute_memberships, compute_memberships_with_scratch,
+    estimate_runtime, euclidean_dist_sq_matrix, fcm_objective, multi_labels, per_cluster_stability,
+    predict_memberships, select_k_by_validity, weighted_median, DistanceMetric, Euclidean,
+    StopReason, ValidityMeasure, WeightedData,
+};
+use k_means_rs::{AlgoError, ClusteringAlgorithm, FuzzyKMeansConfig};
+use ndarray::{Array2, Axis};
+use rand::{rngs::StdRng, SeedableRng};
+use std::time::Duration;
+
+/// `per_blob` points around each of `centers`, spread deterministically over a small square
+fn blobs(centers: &[[f64; 2]], per_blob: usize) -> Array2<f64> {
+    let mut data = Array2::<f64>::zeros((centers.len() * per_blob, 2));
+    for (i, mut row) in data.outer_iter_mut().enumerate() {
+        let center = centers[i % centers.len()];
+        let offset = (i / centers.len()) as f64;
+        row[0] = center[0] + 0.5 * (offset * 0.618_034).fract() - 0.25;
+        row[1] = center[1] + 0.5 * (offset * 0.414_214).fract() - 0.25;
+    }
+    data
+}
+
+#[test]
+fn time_budget_stops_after_first_iteration() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0], [0.0, 5.0]], 2000);
+    let config = FuzzyKMeansConfig {
+        max_duration: Some(Duration::from_nanos(1)),
+        seed: Some(1),
+        ..FuzzyKMeansConfig::new(3, 1000, 2.0)
+    };
+    let result = config.fit(&data).unwrap();
+
+    assert_eq!(result.n_iter, 1);
+    assert_eq!(result.stopped_by, StopReason::TimeBudget);
+    assert!(!result.converged);
+    // memberships belong to the returned centers
+    for sum in result.memberships.sum_axis(Axis(1)).iter() {
+        assert!((sum - 1.0).abs() < 1e-12);
+    }
+    let refit = predict_memberships(&data, &result.centers, 2.0, &Euclidean).unwrap();
+    assert!((&refit - &result.memberships)
+        .iter()
+        .all(|diff| diff.abs() < 1e-12));
+}
+
+#[test]
+fn multi_labels_sorted_by_descending_membership() {
+    let memberships = ndarray::array![[0.2, 0.5, 0.3], [0.4, 0.2, 0.4], [f64::NAN, 0.9, 0.1]];
+    assert_eq!(
+        multi_labels(&memberships, 0.3),
+        vec![
+            vec![(1, 0.5), (2, 0.3)],
+            vec![(0, 0.4), (2, 0.4)],
+            vec![(1, 0.9)]
+        ]
+    );
+}
+
+#[test]
+fn weighted_median_orders_nan_last() {
+    assert_eq!(
+        weighted_median(&[3.0, f64::NAN, 1.0, 2.0], &[1.0, 1.0, 1.0, 1.0]),
+        2.5
+    );
+    assert_eq!(weighted_median(&[f64::NAN, 1.0], &[1.0, 3.0]), 1.0);
+}
+
+#[test]
+fn display_three_clusters() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0], [0.0, 5.0]], 20);
+    let result = FuzzyKMeansConfig {
+        seed: Some(3),
+        ..FuzzyKMeansConfig::new(3, 50, 2.0)
+    }
+    .fit(&data)
+    .unwrap();
+    let table = format!("{}", result);
+    let lines: Vec<&str> = table.lines().collect();
+    // status, objective, header and one row per cluster
+    assert_eq!(lines.len(), 6);
+    for header in ["cluster", "size", "fuzzy_size", "mean_u"] {
+        assert!(
+            lines[2].contains(header),
+            "missing {} in {}",
+            header,
+            lines[2]
+        );
+    }
+    // feature columns depend on the terminal width
+    assert!(lines[2].contains("center_0") || lines[2].contains("..."));
+    assert!(lines[3..].iter().all(|line| line.contains("20")));
+}
+
+/// Largest distance between a center of `a` and the nearest center of `b`
+fn center_distance(a: &Array2<f64>, b: &Array2<f64>) -> f64 {
+    a.outer_iter()
+        .map(|ca| {
+            b.outer_iter()
+                .map(|cb| (&ca - &cb).mapv(|val| val * val).sum().sqrt())
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[test]
+fn trimming_ignores_planted_outliers() {
+    let clean = blobs(&[[0.0, 0.0], [5.0, 5.0]], 95);
+    let mut data = clean.clone();
+    for i in 0..10 {
+        // 5% far away outliers
+        let outlier = ndarray::array![60.0 + i as f64, -40.0];
+        data.push_row(outlier.view()).unwrap();
+    }
+    let config = FuzzyKMeansConfig {
+        seed: Some(5),
+        ..FuzzyKMeansConfig::new(2, 100, 2.0)
+    };
+    let clean_fit = config.fit(&clean).unwrap();
+    let trimmed_fit = FuzzyKMeansConfig {
+        trim_fraction: 0.05,
+        ..config.clone()
+    }
+    .fit(&data)
+    .unwrap();
+    let untrimmed_fit = config.fit(&data).unwrap();
+
+    assert!(center_distance(&trimmed_fit.centers, &clean_fit.centers) < 0.05);
+    assert!(center_distance(&untrimmed_fit.centers, &clean_fit.centers) > 0.5);
+    assert_eq!(trimmed_fit.trimmed.iter().filter(|t| **t).count(), 10);
+    assert!(trimmed_fit.trimmed.iter().skip(190).all(|t| *t));
+}
+
+#[test]
+fn invalid_trim_fraction_is_an_error() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 5);
+    for trim_fraction in [-0.1, 1.0, f64::NAN] {
+        let config = FuzzyKMeansConfig {
+            trim_fraction,
+            ..FuzzyKMeansConfig::new(2, 10, 2.0)
+        };
+        match config.fit(&data) {
+            Err(AlgoError::InvalidTrimFraction(_)) => {}
+            other => panic!("expected InvalidTrimFraction, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn memberships_of_points_on_centers() {
+    let data = ndarray::array![[0.0, 0.0], [1.0, 1.0], [0.5, 0.5], [2.0, 2.0]];
+    let centers = ndarray::array![[0.0, 0.0], [1.0, 1.0], [1.0, 1.0]];
+    let mut memberships = Array2::<f64>::zeros((4, 3));
+    let mut scratch = ndarray::Array1::<f64>::zeros(4);
+    compute_memberships_with_scratch(2.0, &data, &centers, &mut memberships, &mut scratch).unwrap();
+    assert_eq!(memberships.row(0).to_vec(), vec![1.0, 0.0, 0.0]);
+    // shared equally between coinciding centers
+    assert_eq!(memberships.row(1).to_vec(), vec![0.0, 0.5, 0.5]);
+    assert!(memberships.iter().all(|u| u.is_finite()));
+    assert!(scratch[0].is_infinite() && scratch[2].is_finite());
+}
+
+#[test]
+fn memberships_with_and_without_scratch_agree_up_to_rounding() {
+    // off the origin, with points on and right next to the centers
+    let mut data =
+        Array2::from_shape_fn((40, 3), |(i, j)| 1e3 + ((i * 7 + j * 3) % 11) as f64 - 5.0);
+    let centers = data.slice(ndarray::s![..4, ..]).to_owned();
+    data.row_mut(4).assign(&(&centers.row(1) + 1e-7));
+    let mut allocating = Array2::<f64>::zeros((40, 4));
+    compute_memberships(1.5, &data, &centers, &mut allocating).unwrap();
+    let mut with_scratch = Array2::<f64>::zeros((40, 4));
+    let mut scratch = ndarray::Array1::<f64>::zeros(40);
+    compute_memberships_with_scratch(1.5, &data, &centers, &mut with_scratch, &mut scratch)
+        .unwrap();
+    for (a, b) in allocating.iter().zip(with_scratch.iter()) {
+        assert!((a - b).abs() < 1e-9, "{} differs from {}", a, b);
+    }
+}
+
+#[test]
+fn f32_data_accumulates_in_f64() {
+    // 1e7 small values
+    const ROWS: usize = 1_000_000;
+    const COLS: usize = 10;
+    let data = Array2::from_shape_fn((ROWS, COLS), |(i, j)| 0.1f32 + ((i + j) % 7) as f32 * 1e-3);
+
+    let naive_sum = data.iter().fold(0.0f32, |sum, val| sum + val);
+    let reference_sum = data.iter().map(|&val| f64::from(val)).sum::<f64>();
+    // naive f32 accumulation loses several digits at this size
+    assert!((f64::from(naive_sum) - reference_sum).abs() / reference_sum > 1e-2);
+
+    // a single cluster's center is the mean and its objective the sum of squared deviations
+    let result = FuzzyKMeansConfig {
+        seed: Some(0),
+        ..FuzzyKMeansConfig::new(1, 1, 2.0)
+    }
+    .fit(&data)
+    .unwrap();
+    let reference_mean = data.mapv(f64::from).mean_axis(Axis(0)).unwrap();
+    for (center, mean) in result.centers.iter().zip(reference_mean.iter()) {
+        assert!((f64::from(*center) - mean).abs() / mean < 1e-6);
+    }
+    let reference_objective = data
+        .outer_iter()
+        .map(|row| {
+            row.iter()
+                .zip(result.centers.iter())
+                .map(|(&val, &center)| (f64::from(val) - f64::from(center)).powi(2))
+                .sum::<f64>()
+        })
+        .sum::<f64>();
+    assert!((result.objective - reference_objective).abs() / reference_objective < 1e-6);
+}
+
+#[test]
+fn f32_specialization_matches_f64() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0], [0.0, 5.0]], 200);
+    let centers = cluster_k_means_fuzzy(3, 30, 2.0, &data, &mut StdRng::seed_from_u64(4)).unwrap();
+    let result = cluster_k_means_fuzzy_f32(
+        3,
+        30,
+        2.0,
+        &data.mapv(|val| val as f32),
+        &mut StdRng::seed_from_u64(4),
+    )
+    .unwrap();
+    for (a, b) in centers.iter().zip(result.centers.iter()) {
+        assert!((a - f64::from(*b)).abs() < 1e-3, "{} differs from {}", a, b);
+    }
+}
+
+#[test]
+fn weighted_data_validates_weights() {
+    let data = ndarray::array![[0.0, 0.1], [0.2, 0.0], [0.9, 1.0]];
+    assert!(WeightedData::new(data.clone(), ndarray::array![1.0, 2.0, 0.5]).is_ok());
+    assert_eq!(
+        WeightedData::new(data.clone(), ndarray::array![1.0, 2.0]).unwrap_err(),
+        AlgoError::DimensionMismatch {
+            expected: (3, 1),
+            got: (2, 1)
+        }
+    );
+    for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        match WeightedData::new(data.clone(), ndarray::array![1.0, bad, 1.0]) {
+            Err(AlgoError::InvalidWeight(_)) => {}
+            other => panic!("expected InvalidWeight for {}, got {:?}", bad, other),
+        }
+    }
+}
+
+#[test]
+fn weight_two_counts_like_a_duplicate() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 10);
+    let mut weights = ndarray::Array1::<f64>::ones(data.nrows());
+    weights[3] = 2.0;
+    let mut duplicated = data.clone();
+    duplicated.push_row(data.row(3)).unwrap();
+
+    let weighted = cluster_k_means_fuzzy_weighted(
+        2,
+        30,
+        2.0,
+        &WeightedData::new(data, weights).unwrap(),
+        Some(9),
+    )
+    .unwrap();
+    let unweighted =
+        cluster_k_means_fuzzy_weighted(2, 30, 2.0, &WeightedData::uniform(duplicated), Some(9))
+            .unwrap();
+    assert!(center_distance(&weighted.centers, &unweighted.centers) < 1e-9);
+}
+
+#[test]
+fn distance_matrix_precise_far_from_origin() {
+    for n_features in [2, 64] {
+        let data = Array2::from_shape_fn((50, n_features), |(i, j)| {
+            1e8 + ((i * 31 + j * 17) % 13) as f64 * 1e-3
+        });
+        let centers = data.slice(ndarray::s![..3, ..]).to_owned() + 2e-3;
+        let direct = Array2::from_shape_fn((data.nrows(), centers.nrows()), |(i, j)| {
+            Euclidean.distance(data.row(i), centers.row(j)).powi(2)
+        });
+        for dists in [
+            euclidean_dist_sq_matrix(&data, &centers).unwrap(),
+            Euclidean.squared_distances(data.view(), &centers),
+        ] {
+            for (a, b) in dists.iter().zip(direct.iter()) {
+                assert!(
+                    (a - b).abs() <= 1e-9 * (1.0 + b),
+                    "{} differs from {}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn fit_without_tolerance_converges_despite_rounding() {
+    let data = ndarray::array![
+        [0.0, 0.1],
+        [0.2, 0.0],
+        [0.1, 0.1],
+        [0.0, 0.2],
+        [5.0, 5.1],
+        [5.2, 4.9],
+        [4.9, 5.0]
+    ];
+    let config = FuzzyKMeansConfig {
+        seed: Some(1),
+        ..FuzzyKMeansConfig::new(2, 100, 2.0)
+    };
+    let result = config.fit(&data).unwrap();
+    assert!(result.converged);
+    assert!(result.n_iter < 100);
+}
+
+#[test]
+fn compute_memberships_of_points_on_centers() {
+    let data = ndarray::array![[1e6, 1e6], [1e6 + 1.0, 1e6], [1e6 + 0.5, 1e6]];
+    let centers = data.slice(ndarray::s![..2, ..]).to_owned();
+    let mut memberships = Array2::<f64>::zeros((3, 2));
+    compute_memberships(2.0, &data, &centers, &mut memberships).unwrap();
+    assert_eq!(memberships.row(0).to_vec(), vec![1.0, 0.0]);
+    assert_eq!(memberships.row(1).to_vec(), vec![0.0, 1.0]);
+    assert!((memberships[[2, 0]] - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn runtime_estimate_of_a_large_input() {
+    let estimate = estimate_runtime(10_000_000, 50, 20, 100);
+    assert!(estimate > Duration::ZERO);
+    assert!(estimate > Duration::from_secs(60), "{:?}", estimate);
+    // linear in every dimension
+    let half = estimate_runtime(5_000_000, 50, 20, 100);
+    assert!((estimate.as_secs_f64() / half.as_secs_f64() - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn labeled_csv_names_three_clusters() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0], [0.0, 5.0]], 5);
+    let result = FuzzyKMeansConfig {
+        seed: Some(2),
+        ..FuzzyKMeansConfig::new(3, 30, 2.0)
+    }
+    .fit(&data)
+    .unwrap();
+    let fname = std::env::temp_dir().join(format!("k_means_rs-labeled-{}.csv", std::process::id()));
+    let names = std::collections::HashMap::from([(0, "low"), (2, "high")]);
+    result
+        .to_labeled_csv(
+            &data,
+            fname.to_str().unwrap(),
+            &[String::from("x"), String::from("y")],
+            &names,
+            b';',
+        )
+        .unwrap();
+    let text = std::fs::read_to_string(&fname).unwrap();
+    std::fs::remove_file(&fname).unwrap();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "x;y;cluster");
+    assert_eq!(lines.len(), data.nrows() + 1);
+    for (line, label) in lines[1..].iter().zip(result.labels.iter()) {
+        let expected = match label {
+            0 => "low",
+            2 => "high",
+            // clusters without a name fall back to their number
+            _ => "cluster_1",
+        };
+        assert_eq!(line.rsplit(';').next(), Some(expected));
+    }
+}
+
+#[test]
+fn empty_cluster_range_is_an_error() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 5);
+    #[allow(clippy::reversed_empty_ranges)]
+    for k_range in [3..3, 4..2] {
+        match select_k_by_validity(&data, k_range, ValidityMeasure::XieBeni, 10, 2.0, 1) {
+            Err(AlgoError::EmptyClusterRange) => {}
+            other => panic!(
+                "expected EmptyClusterRange, got {:?}",
+                other.map(|(k, _)| k)
+            ),
+        }
+    }
+}
+
+#[test]
+fn stability_without_bootstrap_fits_is_an_error() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 5);
+    match per_cluster_stability(&data, 2, 0, 10, 2.0, Some(1)) {
+        Err(AlgoError::NoBootstrapFits) => {}
+        other => panic!(
+            "expected NoBootstrapFits, got {:?}",
+            other.map(|report| report.recommendations)
+        ),
+    }
+}
+
+#[test]
+fn fcm_objective_decreases_across_iterations() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0], [0.0, 5.0]], 20);
+    let objectives: Vec<f64> = (1..8)
+        .map(|n_iter| {
+            let config = FuzzyKMeansConfig {
+                seed: Some(5),
+                ..FuzzyKMeansConfig::new(3, n_iter, 2.0)
+            };
+            let result = config.fit(&data).unwrap();
+            fcm_objective(&data, &result.centers, &result.memberships, 2.0, &Euclidean).unwrap()
+        })
+        .collect();
+    assert!(objectives.windows(2).all(|pair| pair[1] <= pair[0] + 1e-9));
+    assert!(objectives[objectives.len() - 1] < objectives[0]);
+}
+
+#[test]
+fn fcm_objective_of_mismatched_shapes_is_an_error() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 5);
+    let centers = Array2::<f64>::zeros((2, 2));
+    let memberships = Array2::from_elem((10, 2), 0.5);
+    assert!(fcm_objective(&data, &centers, &memberships, 2.0, &Euclidean).is_ok());
+    assert_eq!(
+        fcm_objective(&data, &Array2::zeros((2, 3)), &memberships, 2.0, &Euclidean),
+        Err(AlgoError::DimensionMismatch {
+            expected: (2, 2),
+            got: (2, 3),
+        })
+    );
+    assert_eq!(
+        fcm_objective(&data, &centers, &Array2::zeros((10, 3)), 2.0, &Euclidean),
+        Err(AlgoError::DimensionMismatch {
+            expected: (10, 2),
+            got: (10, 3),
+        })
+    );
+}
+
+#[test]
+fn density_weighting_keeps_outliers_from_pulling_centers() {
+    let mut data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 30);
+    // a few scattered points far from both blobs
+    for point in [[20.0, -10.0], [-12.0, 18.0], [25.0, 25.0]] {
+        data.push_row(ndarray::aview1(&point)).unwrap();
+    }
+    let plain = FuzzyKMeansConfig {
+        seed: Some(4),
+        ..FuzzyKMeansConfig::new(2, 100, 2.0)
+    }
+    .fit(&data)
+    .unwrap();
+    let weighted =
+        cluster_k_means_fuzzy_density_weighted(2, 100, 2.0, &data, None, Some(4)).unwrap();
+    let error = |centers: &Array2<f64>| {
+        centers
+            .outer_iter()
+            .map(|center| {
+                [[0.0, 0.0], [5.0, 5.0]]
+                    .iter()
+                    .map(|mean| {
+                        ((center[0] - mean[0]).powi(2) + (center[1] - mean[1]).powi(2)).sqrt()
+                    })
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum::<f64>()
+    };
+    assert!(error(&weighted.centers) < 0.5, "{}", weighted.centers);
+    assert!(error(&weighted.centers) < error(&plain.centers));
+}
+
+#[test]
+fn density_weighting_needs_a_valid_bandwidth() {
+    let data = blobs(&[[0.0, 0.0], [5.0, 5.0]], 5);
+    match cluster_k_means_fuzzy_density_weighted(2, 10, 2.0, &data, Some(0.0), Some(1)) {
+        Err(AlgoError::InvalidBandwidth(_)) => {}
+        other => panic!(
+            "expected InvalidBandwidth, got {:?}",
+            other.map(|r| r.centers)
+        ),
+    }
+    // Silverman's bandwidth of constant data is 0
+    let constant = Array2::from_elem((10, 2), 1.0);
+    match cluster_k_means_fuzzy_density_weighted(2, 10, 2.0, &constant, None, Some(1)) {
+        Err(AlgoError::InvalidBandwidth(_)) => {}
+        other => panic!(
+            "expected InvalidBandwidth, got {:?}",
+            other.map(|r| r.centers)
+        ),
+    }
+}
@@ -0,0 +1,1274 @@
+//! The binary run end to end on the fixtures in tests/fixtures
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Path of a file in tests/fixtures
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Directory for the outputs of one test, removed again when dropped
+struct OutDir(PathBuf);
+
+impl OutDir {
+    fn new(test: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("k_means_rs-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        OutDir(dir)
+    }
+
+    /// Path of `name` in the directory
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_str().unwrap().to_string()
+    }
+}
+
+impl Drop for OutDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run the binary with `args`
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_k_means_rs"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Run the binary with `args` and panic with its stderr unless it succeeds
+fn run_ok(args: &[&str]) -> Output {
+    let output = run(args);
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// Lines of a file split at `delimiter`
+fn read_rows(path: impl AsRef<Path>, delimiter: char) -> Vec<Vec<String>> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| line.split(delimiter).map(str::to_string).collect())
+        .collect()
+}
+
+#[test]
+fn comma_delimited_input_and_output() {
+    let out = OutDir::new("delimiter");
+    let labeled = out.path("labeled.csv");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "-o",
+        &labeled,
+        "--quiet",
+    ]);
+    // the output delimiter defaults to the input delimiter
+    let rows = read_rows(&labeled, ',');
+    assert_eq!(rows[0], ["index", "x", "y", "id", "noise", "cluster"]);
+    assert_eq!(rows.len(), 61);
+    assert!(rows.iter().all(|row| row.len() == 6));
+
+    let semicolons = out.path("semicolons.csv");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "--out-delimiter",
+        ";",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "-o",
+        &semicolons,
+        "--quiet",
+    ]);
+    let rows = read_rows(&semicolons, ';');
+    assert!(rows.iter().all(|row| row.len() == 6));
+}
+
+#[test]
+fn invalid_delimiter_is_an_argument_error() {
+    let out = OutDir::new("bad-delimiter");
+    let output = run(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        "ab",
+        "-o",
+        &out.path("labeled.csv"),
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid delimiter \"ab\""));
+    assert!(!Path::new(&out.path("labeled.csv")).exists());
+}
+
+#[test]
+fn headerless_input_gives_headerless_output() {
+    let out = OutDir::new("no-header");
+    let labeled = out.path("labeled.csv");
+    let args = [
+        "-i",
+        &fixture("blobs_noheader.csv"),
+        "-d",
+        ",",
+        "--no-header",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "--quiet",
+    ];
+    run_ok(&[&args[..], &["-o", &labeled]].concat());
+    let rows = read_rows(&labeled, ',');
+    // every line is data: index, the two features and the cluster
+    assert_eq!(rows.len(), 60);
+    assert_eq!(rows[0][..3], ["0", "-0.2819", "-0.5586"]);
+
+    let with_header = out.path("with_header.csv");
+    run_ok(&[&args[..], &["--write-header", "-o", &with_header]].concat());
+    let header_rows = read_rows(&with_header, ',');
+    assert_eq!(
+        header_rows[0],
+        ["index", "feature_0", "feature_1", "cluster"]
+    );
+    assert_eq!(header_rows[1..], rows[..]);
+}
+
+#[test]
+fn header_is_reproduced_with_cluster_column() {
+    let out = OutDir::new("header");
+    let labeled = out.path("labeled.csv");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "-o",
+        &labeled,
+        "--quiet",
+    ]);
+    let rows = read_rows(&labeled, ',');
+    assert_eq!(rows.len(), 61);
+    assert_eq!(rows[0], ["index", "x", "y", "id", "noise", "cluster"]);
+}
+
+#[test]
+fn selected_columns_are_clustered_and_others_copied() {
+    let out = OutDir::new("columns");
+    let input = read_rows(fixture("blobs.csv"), ',');
+    for columns in ["x,y", "0,1"] {
+        let labeled = out.path("labeled.csv");
+        run_ok(&[
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "--columns",
+            columns,
+            "-k",
+            "3",
+            "-s",
+            "1",
+            "-o",
+            &labeled,
+            "--quiet",
+            "--force",
+        ]);
+        let rows = read_rows(&labeled, ',');
+        assert_eq!(rows[0], ["index", "x", "y", "id", "noise", "cluster"]);
+        for (row, input_row) in rows.iter().zip(input.iter()).skip(1) {
+            assert_eq!(row[1..5], input_row[..]);
+        }
+        // the fixture cycles through the blobs, the noise column must not have mattered
+        for row in rows[1..].iter() {
+            let blob = row[3].parse::<usize>().unwrap() % 3;
+            assert_eq!(row[5], rows[1 + blob][5]);
+        }
+    }
+}
+
+#[test]
+fn unresolved_column_is_an_argument_error() {
+    let out = OutDir::new("bad-columns");
+    for (columns, message) in [
+        ("x,q", "No column named \"q\""),
+        ("0,7", "Column index 7 out of range"),
+    ] {
+        let output = run(&[
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "--columns",
+            columns,
+            "-o",
+            &out.path("labeled.csv"),
+        ]);
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "{}", stderr);
+    }
+}
+
+/// Number of iterations in the "converged after N iterations" report on stderr
+fn converged_after(output: &Output) -> Option<usize> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report = stderr
+        .lines()
+        .find_map(|line| line.split("converged after ").nth(1))?;
+    report.split(' ').next()?.parse().ok()
+}
+
+#[test]
+fn tol_stops_before_the_iteration_cap() {
+    let out = OutDir::new("tol");
+    let args = [
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "-o",
+        &out.path("labeled.csv"),
+        "--force",
+    ];
+    // well separated blobs converge long before the cap
+    let output = run_ok(
+        &[
+            &args[..],
+            &["--columns", "x,y", "-n", "100", "--tol", "1e-6"],
+        ]
+        .concat(),
+    );
+    let n_iter = converged_after(&output).expect("no convergence report");
+    assert!(n_iter < 100, "{}", n_iter);
+
+    // unstructured data with a huge tolerance stops after the first iteration
+    let output = run_ok(
+        &[
+            &args[..],
+            &["--columns", "noise", "-n", "100", "--tol", "1e9"],
+        ]
+        .concat(),
+    );
+    assert_eq!(converged_after(&output), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("converged after 1 iteration "));
+
+    // without a tolerance the cap is reached
+    let output = run_ok(&[&args[..], &["--columns", "noise", "-n", "3"]].concat());
+    assert_eq!(converged_after(&output), None);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("hit iteration cap 3 without converging")
+    );
+}
+
+/// Cluster column of a labeled output written with `,`
+fn cluster_column(path: &str) -> Vec<String> {
+    read_rows(path, ',')
+        .into_iter()
+        .skip(1)
+        .map(|row| row.last().unwrap().clone())
+        .collect()
+}
+
+#[test]
+fn cosine_metric_clusters_by_direction() {
+    let out = OutDir::new("metric");
+    let labels = |metric: &str| {
+        let labeled = out.path(&format!("{}.csv", metric));
+        run_ok(&[
+            "-i",
+            &fixture("directions.csv"),
+            "-d",
+            ",",
+            "-k",
+            "2",
+            "-s",
+            "1",
+            "-n",
+            "50",
+            "--metric",
+            metric,
+            "-o",
+            &labeled,
+            "--quiet",
+        ]);
+        cluster_column(&labeled)
+    };
+    // points alternate between two directions, the last one points along the first direction
+    // but is far from every other point
+    let euclidean = labels("euclidean");
+    let cosine = labels("cosine");
+    let outlier = euclidean.len() - 1;
+    assert!(euclidean[..outlier]
+        .iter()
+        .all(|l| *l != euclidean[outlier]));
+    for (i, label) in cosine.iter().enumerate() {
+        assert_eq!(*label, cosine[i % 2]);
+    }
+    assert_ne!(cosine[0], cosine[1]);
+}
+
+#[test]
+fn unknown_metric_lists_the_choices() {
+    let out = OutDir::new("bad-metric");
+    let output = run(&[
+        "-i",
+        &fixture("directions.csv"),
+        "--metric",
+        "foo",
+        "-o",
+        &out.path("labeled.csv"),
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Unknown metric \"foo\", expected one of \"euclidean\", \"manhattan\", \"cosine\" or \
+         \"chebyshev\""
+    ));
+}
+
+#[test]
+fn cosine_metric_warns_about_zero_rows() {
+    let out = OutDir::new("cosine-zero");
+    let input = out.path("zero.csv");
+    fs::write(&input, "x,y\n0,0\n1,1\n2,0\n").unwrap();
+    let output = run_ok(&[
+        "-i",
+        &input,
+        "-d",
+        ",",
+        "-k",
+        "2",
+        "-s",
+        "1",
+        "--metric",
+        "cosine",
+        "-o",
+        &out.path("labeled.csv"),
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("1 data points are all zeros and have no direction"));
+}
+
+#[test]
+fn verbosity_levels() {
+    let out = OutDir::new("verbosity");
+    let labeled = out.path("labeled.csv");
+    let stderr = |verbosity: &[&str]| {
+        let args = [
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "--columns",
+            "x,y",
+            "-k",
+            "3",
+            "-s",
+            "1",
+            "-n",
+            "3",
+            "-o",
+            &labeled,
+            "--force",
+        ];
+        let output = run_ok(&[&args[..], verbosity].concat());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let iteration_lines = |stderr: &str| {
+        stderr
+            .lines()
+            .filter(|line| line.starts_with("[INFO] iteration "))
+            .count()
+    };
+
+    let default = stderr(&[]);
+    assert_eq!(iteration_lines(&default), 0);
+    assert!(default.contains("hit iteration cap 3"));
+
+    let info = stderr(&["-v"]);
+    assert_eq!(iteration_lines(&info), 3);
+    assert!(!info.contains("[DEBUG]"));
+
+    let debug = stderr(&["-v", "-v"]);
+    assert_eq!(iteration_lines(&debug), 3);
+    assert!(debug.contains("[DEBUG]"));
+    assert!(!debug.contains("[TRACE]"));
+
+    assert!(stderr(&["-v", "-v", "-v"]).contains("[TRACE]"));
+    assert_eq!(stderr(&["--quiet"]), "");
+}
+
+#[test]
+fn trailing_option_without_value_exits_with_argument_error() {
+    let output = run(&["-i", &fixture("blobs.csv"), "-d", ",", "-k"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Parameter -k needs a value"));
+}
+
+#[test]
+fn exit_codes_tell_the_kind_of_failure() {
+    let out = OutDir::new("exit-codes");
+    let labeled = out.path("labeled.csv");
+    // argument errors
+    let output = run(&["-d", ",", "-k", "3", "-o", &labeled]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Parameter -i needs to be provided"));
+
+    // input and output errors
+    let missing = out.path("missing.csv");
+    let output = run(&["-i", &missing, "-o", &labeled]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&missing));
+
+    // fit errors
+    for k in ["0", "100"] {
+        let output = run(&[
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "-k",
+            k,
+            "-o",
+            &labeled,
+        ]);
+        assert_eq!(output.status.code(), Some(4), "-k {}", k);
+    }
+    assert!(!Path::new(&labeled).exists());
+}
+
+#[test]
+fn stdin_to_stdout_matches_file_result() {
+    let out = OutDir::new("stdio");
+    let labeled = out.path("labeled.csv");
+    let args = ["-d", ",", "-k", "3", "-s", "1"];
+    run_ok(
+        &[
+            &args[..],
+            &["-i", &fixture("blobs.csv"), "-o", &labeled, "--quiet"],
+        ]
+        .concat(),
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_k_means_rs"))
+        .args([&args[..], &["-i", "-", "-o", "-"]].concat())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&fs::read(fixture("blobs.csv")).unwrap())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    // the reports go to stderr and leave the data alone
+    assert!(!output.stderr.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        fs::read_to_string(&labeled).unwrap()
+    );
+}
+
+#[test]
+fn two_outputs_on_stdout_are_an_argument_error() {
+    for second in ["--centers", "--memberships"] {
+        let output = run(&[
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "-o",
+            "-",
+            second,
+            "-",
+        ]);
+        assert_eq!(output.status.code(), Some(2));
+        assert!(output.stdout.is_empty());
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("Only one output can go to stdout")
+        );
+    }
+}
+
+#[test]
+fn progress_bar_is_off_without_a_terminal() {
+    let out = OutDir::new("progress");
+    // stderr is a pipe here, the bars for restarts and iterations must stay hidden
+    let output = run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "-n",
+        "20",
+        "--restarts",
+        "3",
+        "-o",
+        &out.path("labeled.csv"),
+    ]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains('\r'), "{}", stderr);
+    assert!(!stderr.contains("\x1b["), "{}", stderr);
+    assert!(!stderr.contains(" restarts"), "{}", stderr);
+    assert!(!stderr.contains(" iterations, objective"), "{}", stderr);
+}
+
+#[test]
+fn zscore_normalization_balances_feature_scales() {
+    let out = OutDir::new("normalize");
+    // the clusters differ in y, x is noise on a thousand times larger scale
+    let input = out.path("scaled.csv");
+    let mut text = String::from("x,y\n");
+    for i in 0..60 {
+        let y = (i % 2) as f64 + 0.02 * ((i * 7) % 5) as f64;
+        text.push_str(&format!("{},{}\n", (i * 379) % 1000, y));
+    }
+    fs::write(&input, text).unwrap();
+    let separates_y = |normalize: &str| {
+        let labeled = out.path(&format!("{}.csv", normalize));
+        run_ok(&[
+            "-i",
+            &input,
+            "-d",
+            ",",
+            "-k",
+            "2",
+            "-s",
+            "1",
+            "-n",
+            "50",
+            "--normalize",
+            normalize,
+            "-o",
+            &labeled,
+            "--quiet",
+        ]);
+        let labels = cluster_column(&labeled);
+        labels
+            .iter()
+            .enumerate()
+            .all(|(i, label)| *label == labels[i % 2])
+            && labels[0] != labels[1]
+    };
+    assert!(separates_y("zscore"));
+    assert!(!separates_y("none"));
+
+    // centers are written in the units of the input
+    let centers = out.path("centers.csv");
+    run_ok(&[
+        "-i",
+        &input,
+        "-d",
+        ",",
+        "-k",
+        "2",
+        "-s",
+        "1",
+        "--normalize",
+        "zscore",
+        "--centers",
+        &centers,
+        "-o",
+        &out.path("centers-run.csv"),
+        "--quiet",
+    ]);
+    for row in read_rows(&centers, ',').iter().skip(1) {
+        let x: f64 = row[1].parse().unwrap();
+        let y: f64 = row[2].parse().unwrap();
+        assert!((400.0..600.0).contains(&x), "{:?}", row);
+        assert!((0.0..1.1).contains(&y), "{:?}", row);
+    }
+}
+
+#[test]
+fn scan_k_suggests_four_clusters_for_four_blobs() {
+    let out = OutDir::new("scan-k");
+    let report = out.path("scan.csv");
+    let output = run_ok(&[
+        "-i",
+        &fixture("four_blobs.csv"),
+        "-d",
+        ",",
+        "--scan-k",
+        "2..7",
+        "-s",
+        "1",
+        "-o",
+        &report,
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("suggested k: 4"));
+    let rows = read_rows(&report, ',');
+    assert_eq!(rows[0], ["k", "objective", "fpc", "xie_beni"]);
+    let ks: Vec<&str> = rows[1..].iter().map(|row| row[0].as_str()).collect();
+    assert_eq!(ks, ["2", "3", "4", "5", "6", "7"]);
+    let best = rows[1..]
+        .iter()
+        .min_by(|a, b| {
+            let xb = |row: &Vec<String>| row[3].parse::<f64>().unwrap();
+            xb(a).total_cmp(&xb(b))
+        })
+        .unwrap();
+    assert_eq!(best[0], "4");
+
+    let output = run(&[
+        "-i",
+        &fixture("four_blobs.csv"),
+        "-d",
+        ",",
+        "--scan-k",
+        "7..2",
+        "-o",
+        &out.path("bad.csv"),
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Invalid --scan-k: end 2 is smaller than start 7"));
+}
+
+#[test]
+fn fit_best_chooses_k_by_validity() {
+    let out = OutDir::new("fit-best");
+    let labeled = out.path("labeled.csv");
+    let output = run_ok(&[
+        "-i",
+        &fixture("four_blobs.csv"),
+        "-d",
+        ",",
+        "--scan-k",
+        "2..7",
+        "--validity",
+        "calinski-harabasz",
+        "--fit-best",
+        "-s",
+        "1",
+        "-o",
+        &labeled,
+    ]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("suggested k: 4"));
+    let mut clusters = cluster_column(&labeled);
+    assert_eq!(clusters.len(), 80);
+    clusters.sort();
+    clusters.dedup();
+    assert_eq!(clusters.len(), 4);
+}
+
+#[test]
+fn json_output_matches_csv_run() {
+    let out = OutDir::new("json");
+    let args = ["-i", &fixture("blobs.csv"), "-d", ",", "-k", "3", "-s", "1"];
+    let labeled = out.path("labeled.csv");
+    run_ok(&[&args[..], &["-o", &labeled, "--quiet"]].concat());
+    let result = out.path("result.json");
+    run_ok(
+        &[
+            &args[..],
+            &["--output-format", "json", "-o", &result, "--quiet"],
+        ]
+        .concat(),
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&result).unwrap()).unwrap();
+    let rows = |key: &str| json[key].as_array().unwrap().clone();
+    assert_eq!(rows("labels").len(), 60);
+    assert_eq!(rows("memberships").len(), 60);
+    assert!(rows("memberships")
+        .iter()
+        .all(|row| row.as_array().unwrap().len() == 3));
+    assert_eq!(rows("centers").len(), 3);
+    assert!(rows("centers")
+        .iter()
+        .all(|row| row.as_array().unwrap().len() == 4));
+    assert_eq!(
+        json["feature_names"],
+        serde_json::json!(["x", "y", "id", "noise"])
+    );
+
+    let json_labels: Vec<String> = rows("labels").iter().map(|l| l.to_string()).collect();
+    assert_eq!(json_labels, cluster_column(&labeled));
+}
+
+#[test]
+fn output_format_errors_and_warnings() {
+    let out = OutDir::new("json-errors");
+    let args = ["-i", &fixture("blobs.csv"), "-d", ",", "-k", "3", "-s", "1"];
+    let output = run(&[
+        &args[..],
+        &["--output-format", "xml", "-o", &out.path("r.xml")],
+    ]
+    .concat());
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Unknown output format \"xml\", expected \"csv\", \"npy\" or \"json\""));
+
+    let output = run_ok(
+        &[
+            &args[..],
+            &[
+                "--output-format",
+                "json",
+                "-o",
+                &out.path("r.json"),
+                "--centers",
+                &out.path("centers.csv"),
+            ],
+        ]
+        .concat(),
+    );
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--centers is redundant with --output-format json"));
+}
+
+#[test]
+fn precision_matches_golden_files() {
+    let out = OutDir::new("precision");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "--precision",
+        "4",
+        "-o",
+        &out.path("labeled.csv"),
+        "--centers",
+        &out.path("centers.csv"),
+        "--memberships",
+        &out.path("memberships.csv"),
+        "--quiet",
+    ]);
+    for name in ["labeled", "centers", "memberships"] {
+        assert_eq!(
+            fs::read(out.path(&format!("{}.csv", name))).unwrap(),
+            fs::read(fixture(&format!("precision4_{}.csv", name))).unwrap(),
+            "{} differs from the golden file",
+            name
+        );
+    }
+
+    for precision in ["18", "-1"] {
+        let output = run(&[
+            "-i",
+            &fixture("blobs.csv"),
+            "-d",
+            ",",
+            "--precision",
+            precision,
+            "-o",
+            &out.path("rejected.csv"),
+        ]);
+        assert_eq!(output.status.code(), Some(2), "--precision {}", precision);
+    }
+}
+
+#[test]
+fn subcommands_and_legacy_invocation() {
+    let out = OutDir::new("subcommands");
+    let data = ["-i", &fixture("blobs.csv"), "-d", ","];
+    let model = out.path("model.json");
+    let fitted = out.path("fitted.csv");
+    run_ok(
+        &[
+            &["fit"][..],
+            &data,
+            &[
+                "-k",
+                "3",
+                "-s",
+                "1",
+                "--export-model",
+                &model,
+                "-o",
+                &fitted,
+                "--quiet",
+            ],
+        ]
+        .concat(),
+    );
+
+    // without a command the parameters are those of fit
+    let legacy = out.path("legacy.csv");
+    run_ok(&[&data[..], &["-k", "3", "-s", "1", "-o", &legacy, "--quiet"]].concat());
+    assert_eq!(fs::read(&legacy).unwrap(), fs::read(&fitted).unwrap());
+
+    let predicted = out.path("predicted.csv");
+    run_ok(
+        &[
+            &["predict"][..],
+            &data,
+            &["--model", &model, "-o", &predicted, "--quiet"],
+        ]
+        .concat(),
+    );
+    assert_eq!(cluster_column(&predicted), cluster_column(&fitted));
+
+    let report = out.path("scan.csv");
+    run_ok(
+        &[
+            &["scan"][..],
+            &data,
+            &["--k-range", "2..4", "-s", "1", "-o", &report, "--quiet"],
+        ]
+        .concat(),
+    );
+    assert_eq!(read_rows(&report, ',').len(), 4);
+
+    // every command has its own parameters and help
+    for (command, rejected) in [("predict", "-k"), ("scan", "--model"), ("fit", "--model")] {
+        let output = run(&[&[command][..], &data, &[rejected, "3"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{} {}", command, rejected);
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains(&format!("unknown argument '{}'", rejected)));
+    }
+    // fit is the default command, its help is the general one
+    let output = run_ok(&["fit", "-h"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Parameters of fit:"));
+    for command in ["predict", "scan"] {
+        let output = run_ok(&[command, "-h"]);
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .starts_with(&format!("USAGE: k_means_rs {} ", command)));
+    }
+}
+
+#[test]
+fn cluster_labels_name_the_cluster_column() {
+    let out = OutDir::new("cluster-labels");
+    let args = [
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "--quiet",
+    ];
+    let numeric = out.path("numeric.csv");
+    run_ok(&[&args[..], &["-o", &numeric]].concat());
+    let numeric_rows = read_rows(&numeric, ',');
+    let names = ["idle", "load", "spike"];
+
+    // a list names every cluster in order, pairs may leave clusters unnamed
+    for (spec, expected) in [
+        ("idle,load,spike", names),
+        ("0=idle,one=load,2=spike", names),
+        ("1=load", ["cluster_0", "load", "cluster_2"]),
+    ] {
+        let named = out.path("named.csv");
+        run_ok(
+            &[
+                &args[..],
+                &["--cluster-labels", spec, "-o", &named, "--force"],
+            ]
+            .concat(),
+        );
+        let rows = read_rows(&named, ',');
+        assert_eq!(rows.len(), numeric_rows.len());
+        assert_eq!(rows[0], numeric_rows[0]);
+        for (row, numeric_row) in rows.iter().zip(numeric_rows.iter()).skip(1) {
+            assert_eq!(row[..5], numeric_row[..5]);
+            let label: usize = numeric_row[5].parse().unwrap();
+            assert_eq!(row[5], expected[label], "{}", spec);
+        }
+    }
+
+    // names containing the delimiter are quoted
+    let quoted = out.path("quoted.csv");
+    run_ok(
+        &[
+            &args[..],
+            &[
+                "--label-names",
+                "idle;cpu,load,spike",
+                "--out-delimiter",
+                ";",
+                "-o",
+                &quoted,
+            ],
+        ]
+        .concat(),
+    );
+    let text = fs::read_to_string(&quoted).unwrap();
+    for (line, numeric_row) in text.lines().zip(numeric_rows.iter()).skip(1) {
+        assert_eq!(
+            line.ends_with(";\"idle;cpu\""),
+            numeric_row[5] == "0",
+            "{}",
+            line
+        );
+    }
+
+    let output = run(&[
+        &args[..],
+        &["--cluster-labels", "a,b", "-o", &out.path("few.csv")],
+    ]
+    .concat());
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Parameter --cluster-labels gives 2 names, but there are 3 clusters"));
+}
+
+#[test]
+fn timings_table_lists_every_phase() {
+    let out = OutDir::new("timings");
+    let args = [
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "--timings",
+    ];
+    let output = run_ok(
+        &[
+            &args[..],
+            &["--restarts", "2", "-o", &out.path("labeled.csv")],
+        ]
+        .concat(),
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let table: Vec<&str> = stderr
+        .lines()
+        .skip_while(|line| !line.starts_with("phase "))
+        .collect();
+    assert!(table[0].ends_with("seconds"), "{}", stderr);
+    let seconds = |phase: &str| -> f64 {
+        let line = table
+            .iter()
+            .find(|line| line.trim_start().starts_with(&format!("{} ", phase)))
+            .unwrap_or_else(|| panic!("no {} in {}", phase, stderr));
+        line.split_whitespace().last().unwrap().parse().unwrap()
+    };
+    for phase in [
+        "read",
+        "normalize",
+        "fit",
+        "restart 1",
+        "restart 2",
+        "assign",
+        "write",
+        "total",
+    ] {
+        assert!(seconds(phase) >= 0.0, "{}", phase);
+    }
+    assert!(seconds("total") >= seconds("fit"));
+
+    // embedded in the result with json output
+    let result = out.path("result.json");
+    run_ok(
+        &[
+            &args[..],
+            &["--output-format", "json", "-o", &result, "--quiet"],
+        ]
+        .concat(),
+    );
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&result).unwrap()).unwrap();
+    let phases = json["timings"]["phases"].as_array().unwrap();
+    assert!(phases.iter().any(|phase| phase["phase"] == "fit"));
+    assert!(phases
+        .iter()
+        .all(|phase| phase["seconds"].as_f64().unwrap() >= 0.0));
+}
+
+#[test]
+fn several_output_formats_in_one_run() {
+    let out = OutDir::new("formats");
+    let base = out.path("result");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "3",
+        "-s",
+        "1",
+        "--output-format",
+        "csv,npy,json",
+        "-o",
+        &base,
+        "--quiet",
+    ]);
+    // the -o path is the base of every file
+    assert!(!Path::new(&base).exists());
+    let csv = format!("{}.csv", base);
+    assert_eq!(read_rows(&csv, ',').len(), 61);
+    assert!(fs::read(format!("{}.npy", base))
+        .unwrap()
+        .starts_with(b"\x93NUMPY"));
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(format!("{}.json", base)).unwrap()).unwrap();
+    let json_labels: Vec<String> = json["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|label| label.to_string())
+        .collect();
+    assert_eq!(json_labels, cluster_column(&csv));
+}
+
+#[test]
+fn hard_and_fuzzy_labels_agree_on_blobs() {
+    let out = OutDir::new("hard");
+    let input = fixture("four_blobs.csv");
+    let labels = |hard: bool| {
+        let labeled = out.path(if hard { "hard.csv" } else { "fuzzy.csv" });
+        // random centers within the data range can end up without points in crisp k means
+        let mut args = vec![
+            "-i", &input, "-d", ",", "-k", "4", "-s", "1", "--init", "kmeans++", "-o", &labeled,
+            "--quiet",
+        ];
+        if hard {
+            args.push("--hard");
+        }
+        run_ok(&args);
+        cluster_column(&labeled)
+    };
+    let fuzzy = labels(false);
+    let hard = labels(true);
+    assert_eq!(fuzzy.len(), hard.len());
+    // cluster numbers may differ, but they must map one to one
+    let mut mapping = std::collections::HashMap::new();
+    for (h, f) in hard.iter().zip(&fuzzy) {
+        assert_eq!(mapping.entry(h).or_insert(f), &f);
+    }
+    let mut targets: Vec<_> = mapping.values().collect();
+    targets.sort();
+    targets.dedup();
+    assert_eq!(targets.len(), 4);
+}
+
+/// Run the binary with `args` and the most verbose log, check that it fails with the io error
+/// exit code before reading the input, and return its stderr
+fn refused_before_reading(args: &[&str]) -> String {
+    let output = run(&[args, &["-d", ",", "-vv"]].concat());
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    assert_eq!(output.status.code(), Some(3), "{}", stderr);
+    assert!(!stderr.contains("Found "), "{}", stderr);
+    stderr
+}
+
+#[test]
+fn unreadable_input_is_refused_before_reading() {
+    let out = OutDir::new("paths-input");
+    let labeled = out.path("labeled.csv");
+    let missing = out.path("missing.csv");
+    let stderr = refused_before_reading(&["-i", &missing, "-o", &labeled]);
+    assert!(stderr.contains(&format!("Can't read -i {}", missing)));
+    let stderr = refused_before_reading(&["-i", &out.path(""), "-o", &labeled]);
+    assert!(stderr.contains("not a file"));
+}
+
+#[test]
+fn missing_output_directory_is_refused_or_created() {
+    let out = OutDir::new("paths-dir");
+    let labeled = out.path("nested/labeled.csv");
+    let stderr = refused_before_reading(&["-i", &fixture("blobs.csv"), "-o", &labeled]);
+    assert!(stderr.contains(&format!("directory {} does not exist", out.path("nested"))));
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-o",
+        &labeled,
+        "--create-dirs",
+        "--quiet",
+    ]);
+    assert!(Path::new(&labeled).is_file());
+}
+
+#[test]
+fn existing_output_needs_force() {
+    let out = OutDir::new("paths-force");
+    let labeled = out.path("labeled.csv");
+    fs::write(&labeled, "keep me").unwrap();
+    let stderr = refused_before_reading(&["-i", &fixture("blobs.csv"), "-o", &labeled]);
+    assert!(stderr.contains("already exists, use --force to overwrite it"));
+    assert_eq!(fs::read_to_string(&labeled).unwrap(), "keep me");
+    run_ok(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-d",
+        ",",
+        "-o",
+        &labeled,
+        "--force",
+        "--quiet",
+    ]);
+    assert_ne!(fs::read_to_string(&labeled).unwrap(), "keep me");
+}
+
+#[test]
+fn output_on_a_directory_is_refused() {
+    let out = OutDir::new("paths-is-dir");
+    let stderr = refused_before_reading(&["-i", &fixture("blobs.csv"), "-o", &out.path("")]);
+    assert!(stderr.contains("is a directory"));
+}
+
+// permission bits don't stop root, but nobody may create files in /proc or write a read-only
+// sysctl
+#[cfg(target_os = "linux")]
+#[test]
+fn unwritable_output_is_refused() {
+    let stderr = refused_before_reading(&["-i", &fixture("blobs.csv"), "-o", "/proc/labeled.csv"]);
+    assert!(stderr.contains("directory /proc is not writable"));
+    let stderr = refused_before_reading(&[
+        "-i",
+        &fixture("blobs.csv"),
+        "-o",
+        "/proc/sys/kernel/osrelease",
+        "--force",
+    ]);
+    assert!(stderr.contains("/proc/sys/kernel/osrelease is not writable"));
+}
+
+#[test]
+fn sampled_fit_labels_every_row_like_the_full_fit() {
+    let out = OutDir::new("sample");
+    let input = fixture("four_blobs.csv");
+    let labels = |sample: Option<&str>| {
+        let labeled = out.path(if sample.is_some() {
+            "sampled.csv"
+        } else {
+            "full.csv"
+        });
+        let mut args = vec![
+            "-i", &input, "-d", ",", "-k", "4", "-s", "1", "-o", &labeled, "--quiet",
+        ];
+        if let Some(fraction) = sample {
+            args.extend(["--sample", fraction]);
+        }
+        run_ok(&args);
+        cluster_column(&labeled)
+    };
+    let full = labels(None);
+    let sampled = labels(Some("0.5"));
+    assert_eq!(full.len(), sampled.len());
+    // cluster numbers may differ, so count the points of every sampled cluster that share its
+    // most common cluster of the full fit
+    let mut pairs = std::collections::HashMap::new();
+    for pair in sampled.iter().zip(&full) {
+        *pairs.entry(pair).or_insert(0) += 1;
+    }
+    let mut best = std::collections::HashMap::new();
+    for ((s, f), count) in pairs {
+        let entry = best.entry(s).or_insert((f, 0));
+        if count > entry.1 {
+            *entry = (f, count);
+        }
+    }
+    let mut targets: Vec<_> = best.values().map(|(f, _)| f).collect();
+    targets.sort();
+    targets.dedup();
+    assert_eq!(targets.len(), best.len());
+    let agreeing: usize = best.values().map(|(_, count)| count).sum();
+    assert!(
+        agreeing * 100 >= full.len() * 99,
+        "{} of {}",
+        agreeing,
+        full.len()
+    );
+}
+
+#[test]
+fn memberships_come_from_the_fit_without_another_pass() {
+    let out = OutDir::new("membership-passes");
+    let input = fixture("four_blobs.csv");
+    let labeled = out.path("labeled.csv");
+    let passes = |extra: &[&str]| {
+        let args = [
+            &[
+                "-i", &input, "-d", ",", "-k", "4", "-s", "1", "-o", &labeled,
+            ][..],
+            &["--force", "-vv"],
+            extra,
+        ]
+        .concat();
+        let stderr = String::from_utf8(run_ok(&args).stderr).unwrap();
+        stderr.matches("membership pass over").count()
+    };
+    let memberships = out.path("memberships.csv");
+    assert_eq!(passes(&[]), 0);
+    assert_eq!(passes(&["--memberships", &memberships]), 0);
+    // a fit on a sample needs the memberships of the other points, once
+    assert_eq!(passes(&["--sample", "0.5"]), 1);
+    assert_eq!(
+        passes(&["--sample", "0.5", "--memberships", &memberships]),
+        1
+    );
+    assert_eq!(read_rows(&memberships, ',').len(), 81);
+}
+
+#[test]
+fn density_weighted_fit_finds_the_blobs() {
+    let out = OutDir::new("density");
+    let labeled = out.path("labeled.csv");
+    run_ok(&[
+        "-i",
+        &fixture("four_blobs.csv"),
+        "-d",
+        ",",
+        "-k",
+        "4",
+        "-s",
+        "1",
+        "--density-weighted",
+        "-o",
+        &labeled,
+        "--quiet",
+    ]);
+    let labels = cluster_column(&labeled);
+    // the fixture lists the blobs in turn, one point of each after the other
+    for (i, label) in labels.iter().enumerate() {
+        assert_eq!(*label, labels[i % 4]);
+    }
+    let mut distinct = labels[..4].to_vec();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 4);
+}
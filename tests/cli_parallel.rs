@@ -0,0 +1,50 @@
+//! The binary built with the parallel feature writes the same results for every --threads
+#![cfg(feature = "parallel")]
+
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn thread_count_does_not_change_the_output() {
+    let dir = std::env::temp_dir().join(format!("k_means_rs-threads-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = format!(
+        "{}/tests/fixtures/four_blobs.csv",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let outputs: Vec<(String, String)> = ["1", "4"]
+        .iter()
+        .map(|threads| {
+            let labeled = dir.join(format!("labeled-{}.csv", threads));
+            let memberships = dir.join(format!("memberships-{}.csv", threads));
+            let output = Command::new(env!("CARGO_BIN_EXE_k_means_rs"))
+                .args(["-i", &input, "-d", ",", "-k", "4", "-s", "3", "--threads"])
+                .arg(threads)
+                .arg("-o")
+                .arg(&labeled)
+                .arg("--memberships")
+                .arg(&memberships)
+                .args(["--restarts", "3", "-v", "--force"])
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            assert!(String::from_utf8_lossy(&output.stderr)
+                .contains(&format!("using {} threads", threads)));
+            (
+                fs::read_to_string(labeled).unwrap(),
+                fs::read_to_string(memberships).unwrap(),
+            )
+        })
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+#[test]
+fn zero_threads_is_an_argument_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_k_means_rs"))
+        .args(["-i", "in.csv", "--threads", "0"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
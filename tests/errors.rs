@@ -0,0 +1,116 @@
+//! Errors of the library, their messages and how they combine into `Error`
+
+use k_means_rs::io::{read_csv, read_csv_delimited, read_csv_header, write_csv};
+use k_means_rs::{AlgoError, ClusteringAlgorithm, ConfigError, CsvError, Error, FuzzyKMeansConfig};
+use ndarray::{array, Array2};
+
+/// Path of a temporary csv file named after `test` with `text` in it
+fn csv_file(test: &str, text: &str) -> String {
+    let path = std::env::temp_dir().join(format!("k_means_rs-{}-{}.csv", test, std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn errors_are_thread_safe() {
+    fn is_thread_safe<E: std::error::Error + Send + Sync + 'static>() {}
+    is_thread_safe::<Error>();
+    is_thread_safe::<CsvError>();
+    is_thread_safe::<AlgoError>();
+    is_thread_safe::<ConfigError>();
+}
+
+#[test]
+fn missing_file_is_an_io_error() {
+    let err = read_csv::<f64>(String::from("does/not/exist.csv")).unwrap_err();
+    assert!(matches!(err, CsvError::Csv(_)));
+    let err = Error::from(err);
+    assert!(matches!(err, Error::Io(_)));
+    assert!(err.to_string().contains("No such file"), "{}", err);
+}
+
+#[test]
+fn unparsable_value_names_its_position() {
+    let fname = csv_file("parse-error", "x,y\n1,2\n3,four\n");
+    let err = read_csv_delimited::<f64>(fname.clone(), b',', true).unwrap_err();
+    std::fs::remove_file(&fname).unwrap();
+    assert!(matches!(err, CsvError::Parse { row: 1, col: 1, .. }));
+    assert!(err
+        .to_string()
+        .starts_with("Error trying to parse value in (1, 1): "));
+}
+
+#[test]
+fn missing_header_file_is_an_error() {
+    let err = read_csv_header(String::from("does/not/exist.csv"), b',').unwrap_err();
+    assert!(matches!(err, CsvError::Csv(_)));
+}
+
+#[test]
+fn mismatched_shapes_are_errors_when_writing() {
+    let data = array![[0.0, 0.0], [1.0, 1.0], [9.0, 9.0]];
+    let header = [String::from("x")];
+    let err = write_csv(&data, Vec::new(), b',', Some(&header), None).unwrap_err();
+    assert!(matches!(
+        err,
+        CsvError::ShapeMismatch {
+            expected: (3, 1),
+            got: (3, 2)
+        }
+    ));
+
+    let result = FuzzyKMeansConfig::new(2, 10, 2.0).fit(&data).unwrap();
+    let fname = std::env::temp_dir().join(format!("k_means_rs-labeled-{}.csv", std::process::id()));
+    let fname = fname.to_str().unwrap();
+    let headers = [String::from("x"), String::from("y")];
+    let names = Default::default();
+    let err = result
+        .to_labeled_csv(
+            &data.slice(ndarray::s![..2, ..]).to_owned(),
+            fname,
+            &headers,
+            &names,
+            b',',
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        CsvError::ShapeMismatch {
+            expected: (3, 2),
+            got: (2, 2)
+        }
+    ));
+    let err = result
+        .to_labeled_csv(&data, fname, &headers[..1], &names, b',')
+        .unwrap_err();
+    assert!(matches!(err, CsvError::ShapeMismatch { .. }));
+    assert!(!std::path::Path::new(fname).exists());
+}
+
+#[test]
+fn fit_errors_explain_the_parameters() {
+    let data = array![[0.0, 0.0], [1.0, 1.0]];
+    let fit =
+        |k: usize, q: f64| -> Result<_, Error> { Ok(FuzzyKMeansConfig::new(k, 10, q).fit(&data)?) };
+    let message = |k, q| fit(k, q).unwrap_err().to_string();
+    assert_eq!(message(3, 2.0), "Can't form 3 clusters from 2 data points");
+    assert_eq!(message(0, 2.0), "Number of clusters needs to be at least 1");
+    assert_eq!(
+        message(2, 1.0),
+        "Fuzzifier must be finite and greater than 1, got 1"
+    );
+    let empty = Array2::<f64>::zeros((0, 2));
+    assert_eq!(
+        Error::from(FuzzyKMeansConfig::new(2, 10, 2.0).fit(&empty).unwrap_err()).to_string(),
+        "No data points to cluster"
+    );
+}
+
+#[test]
+fn config_error_keeps_its_message() {
+    let err = ConfigError::new("Parameter -i needs to be provided");
+    assert_eq!(err.message(), "Parameter -i needs to be provided");
+    let err = Error::from(err);
+    assert!(matches!(err, Error::Config(_)));
+    assert_eq!(err.to_string(), "Parameter -i needs to be provided");
+}
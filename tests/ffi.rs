@@ -0,0 +1,103 @@
+//! The C API called through its own declarations, as a C program sees it in k_means_rs.h
+#![cfg(feature = "ffi")]
+
+// linked for its exported symbols
+use k_means_rs as _;
+use std::ffi::{c_char, c_int, CStr};
+
+#[repr(C)]
+struct FzyResult {
+    centers: *mut f64,
+    centers_len: usize,
+    labels: *mut usize,
+    labels_len: usize,
+    memberships: *mut f64,
+    memberships_len: usize,
+    n_iter: usize,
+    converged: bool,
+}
+
+const FZY_OK: c_int = 0;
+const FZY_ERR_NULL_POINTER: c_int = 1;
+const FZY_ERR_FIT: c_int = 3;
+
+extern "C" {
+    fn fzy_fit(
+        data: *const f64,
+        rows: usize,
+        cols: usize,
+        k: usize,
+        q: f64,
+        max_iter: usize,
+        seed: u64,
+        out: *mut FzyResult,
+    ) -> c_int;
+    fn fzy_result_free(result: *mut FzyResult);
+    fn fzy_last_error_message() -> *const c_char;
+}
+
+fn empty_result() -> FzyResult {
+    FzyResult {
+        centers: std::ptr::null_mut(),
+        centers_len: 0,
+        labels: std::ptr::null_mut(),
+        labels_len: 0,
+        memberships: std::ptr::null_mut(),
+        memberships_len: 0,
+        n_iter: 0,
+        converged: false,
+    }
+}
+
+fn last_error() -> String {
+    let message = unsafe { fzy_last_error_message() };
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn fit_two_blobs() {
+    let data = [
+        0.0, 0.1, 0.2, 0.0, 0.1, 0.1, 0.0, 0.2, 5.0, 5.1, 5.2, 4.9, 4.9, 5.0,
+    ];
+    let mut result = empty_result();
+    let code = unsafe { fzy_fit(data.as_ptr(), 7, 2, 2, 2.0, 100, 1, &mut result) };
+    assert_eq!(code, FZY_OK);
+    assert_eq!(result.centers_len, 4);
+    assert_eq!(result.labels_len, 7);
+    assert_eq!(result.memberships_len, 14);
+    assert!(result.converged);
+
+    let labels = unsafe { std::slice::from_raw_parts(result.labels, result.labels_len) };
+    assert!(labels[..4].iter().all(|&label| label == labels[0]));
+    assert!(labels[4..].iter().all(|&label| label == labels[4]));
+    assert_ne!(labels[0], labels[4]);
+    let memberships =
+        unsafe { std::slice::from_raw_parts(result.memberships, result.memberships_len) };
+    for row in memberships.chunks(2) {
+        assert!((row[0] + row[1] - 1.0).abs() < 1e-12);
+    }
+
+    unsafe { fzy_result_free(&mut result) };
+    assert!(result.centers.is_null() && result.labels.is_null() && result.memberships.is_null());
+    // freeing twice and freeing null are no-ops
+    unsafe { fzy_result_free(&mut result) };
+    unsafe { fzy_result_free(std::ptr::null_mut()) };
+}
+
+#[test]
+fn errors_are_reported() {
+    let data = [0.0, 1.0, 2.0, 3.0];
+    let mut result = empty_result();
+    let code = unsafe { fzy_fit(data.as_ptr(), 2, 2, 3, 2.0, 100, 1, &mut result) };
+    assert_eq!(code, FZY_ERR_FIT);
+    assert_eq!(last_error(), "Can't form 3 clusters from 2 data points");
+    assert!(result.centers.is_null());
+    unsafe { fzy_result_free(&mut result) };
+
+    let code = unsafe { fzy_fit(std::ptr::null(), 2, 2, 1, 2.0, 100, 1, &mut result) };
+    assert_eq!(code, FZY_ERR_NULL_POINTER);
+    assert_eq!(last_error(), "data is null");
+}
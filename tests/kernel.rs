@@ -0,0 +1,50 @@
+//! Kernel functions through the public API of the library
+
+use k_means_rs::algo::kernel::{gaussian_density_estimate, rbf_kernel_matrix};
+use k_means_rs::AlgoError;
+use ndarray::{array, Array2};
+
+#[test]
+fn rbf_kernel_has_unit_diagonal() {
+    let data = Array2::from_shape_fn((30, 3), |(i, j)| (i * 3 + j) as f64 * 0.37 - 5.0);
+    let kernel = rbf_kernel_matrix(&data, 0.8).unwrap();
+    assert_eq!(kernel.dim(), (30, 30));
+    assert!(kernel.diag().iter().all(|&val| val == 1.0));
+    assert!(kernel.iter().all(|&val| (0.0..=1.0).contains(&val)));
+    assert_eq!(kernel, kernel.t());
+}
+
+#[test]
+fn rbf_kernel_rejects_invalid_width() {
+    let data = array![[0.0, 1.0], [1.0, 0.0]];
+    for sigma in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        match rbf_kernel_matrix(&data, sigma) {
+            Err(AlgoError::InvalidBandwidth(_)) => {}
+            other => panic!("expected InvalidBandwidth for {}, got {:?}", sigma, other),
+        }
+    }
+}
+
+#[test]
+fn density_estimate_integrates_to_about_one() {
+    // evenly spaced points, whose density is about 1 / 10 in the middle of [0, 10]
+    let data = Array2::from_shape_fn((1001, 1), |(i, _)| i as f64 / 100.0);
+    let density = gaussian_density_estimate(&data, 0.5).unwrap();
+    assert!((density[500] - 0.1).abs() < 1e-3, "{}", density[500]);
+    // half the kernel mass falls outside at the ends
+    assert!((density[0] - 0.05).abs() < 1e-3, "{}", density[0]);
+}
+
+#[test]
+fn density_estimate_rejects_invalid_bandwidth() {
+    let data = array![[0.0, 1.0], [1.0, 0.0]];
+    for bandwidth in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        match gaussian_density_estimate(&data, bandwidth) {
+            Err(AlgoError::InvalidBandwidth(_)) => {}
+            other => panic!(
+                "expected InvalidBandwidth for {}, got {:?}",
+                bandwidth, other
+            ),
+        }
+    }
+}
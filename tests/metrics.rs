@@ -0,0 +1,87 @@
+//! Cluster quality measures through the public API of the library
+
+use k_means_rs::algo::metrics::{
+    calinski_harabasz_index, cluster_sizes, confusion_matrix, davies_bouldin_index,
+    mean_distance_to_center, modified_partition_coefficient, row_normalize, silhouette_score,
+};
+use k_means_rs::AlgoError;
+use ndarray::array;
+
+#[test]
+fn confusion_matrix_diagonal_for_perfect_labeling() {
+    let labels = array![0, 1, 2, 2, 1, 0, 0];
+    let cm = confusion_matrix(&labels, &labels, 3, 3).unwrap();
+    assert_eq!(cm, array![[3, 0, 0], [0, 2, 0], [0, 0, 2]]);
+    let normalized = row_normalize(&cm);
+    for (i, row) in normalized.outer_iter().enumerate() {
+        let best = (0..3).max_by(|&a, &b| row[a].total_cmp(&row[b])).unwrap();
+        assert_eq!(best, i);
+        assert_eq!(row[i], 1.0);
+    }
+}
+
+#[test]
+fn confusion_matrix_rejects_bad_labels() {
+    assert_eq!(
+        confusion_matrix(&array![0, 1], &array![0], 2, 2),
+        Err(AlgoError::DimensionMismatch {
+            expected: (2, 1),
+            got: (1, 1)
+        })
+    );
+    assert_eq!(
+        confusion_matrix(&array![0, 3], &array![0, 1], 2, 2),
+        Err(AlgoError::LabelOutOfRange {
+            label: 3,
+            n_labels: 2
+        })
+    );
+    let err = confusion_matrix(&array![0, 1], &array![0, 5], 2, 2).unwrap_err();
+    assert_eq!(err.to_string(), "Label 5 is out of range for 2 labels");
+}
+
+#[test]
+fn hard_cluster_measures_reject_labels_out_of_range() {
+    let data = array![[0.0], [2.0], [9.0], [11.0]];
+    let centers = array![[1.0], [10.0]];
+    let labels = array![0, 0, 1, 2];
+    let out_of_range = Err(AlgoError::LabelOutOfRange {
+        label: 2,
+        n_labels: 2,
+    });
+    assert_eq!(
+        cluster_sizes(&labels, 2),
+        out_of_range.clone().map(|_| array![])
+    );
+    let dists = array![1.0, 1.0, 1.0, 1.0];
+    assert_eq!(
+        mean_distance_to_center(&dists, &labels, 2),
+        out_of_range.clone().map(|_| array![])
+    );
+    assert_eq!(davies_bouldin_index(&data, &centers, &labels), out_of_range);
+    assert_eq!(
+        calinski_harabasz_index(&data, &centers, &labels),
+        out_of_range
+    );
+    assert_eq!(silhouette_score(&data, &labels, 2), out_of_range);
+    // a single cluster has no index, but its labels are still checked
+    assert_eq!(
+        calinski_harabasz_index(
+            &data,
+            &centers.slice(ndarray::s![..1, ..]).to_owned(),
+            &labels
+        ),
+        Err(AlgoError::LabelOutOfRange {
+            label: 1,
+            n_labels: 1
+        })
+    );
+}
+
+#[test]
+fn modified_partition_coefficient_of_a_single_cluster() {
+    let memberships = array![[1.0], [1.0], [1.0]];
+    assert_eq!(modified_partition_coefficient(&memberships, 1), 1.0);
+    let memberships = array![[0.5, 0.5], [0.5, 0.5]];
+    assert_eq!(modified_partition_coefficient(&memberships, 2), 0.0);
+}
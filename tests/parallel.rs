@@ -0,0 +1,77 @@
+//! Results of the parallel fit don't depend on the number of threads
+#![cfg(feature = "parallel")]
+
+use k_means_rs::algo::fit_restarts;
+use k_means_rs::{ClusteringAlgorithm, ClusteringResult, FuzzyKMeansConfig};
+use ndarray::Array2;
+
+/// Points in four overlapping clouds, many more rows than a single chunk holds
+fn data() -> Array2<f64> {
+    Array2::from_shape_fn((5000, 3), |(i, j)| {
+        let cloud = (i % 4) as f64;
+        cloud * 1.5 + ((i * 7919 + j * 104_729) % 1000) as f64 / 500.0
+    })
+}
+
+/// Run `fit` on a rayon pool of `threads` threads
+fn with_threads<F>(threads: usize, fit: F) -> ClusteringResult<f64>
+where
+    F: FnOnce() -> ClusteringResult<f64> + Send,
+{
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+        .install(fit)
+}
+
+fn assert_bitwise_equal(a: &ClusteringResult<f64>, b: &ClusteringResult<f64>) {
+    let bits = |result: &ClusteringResult<f64>| {
+        result
+            .centers
+            .iter()
+            .map(|val| val.to_bits())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bits(a), bits(b));
+    assert_eq!(a.labels, b.labels);
+    assert_eq!(a.objective.to_bits(), b.objective.to_bits());
+}
+
+#[test]
+fn fit_is_independent_of_thread_count() {
+    let data = data();
+    let config = FuzzyKMeansConfig {
+        seed: Some(42),
+        ..FuzzyKMeansConfig::new(4, 30, 2.0)
+    };
+    let reference = with_threads(1, || config.fit(&data).unwrap());
+    for threads in [2, 8] {
+        assert_bitwise_equal(
+            &reference,
+            &with_threads(threads, || config.fit(&data).unwrap()),
+        );
+    }
+}
+
+#[test]
+fn restarts_are_independent_of_thread_count() {
+    let data = data();
+    let config = FuzzyKMeansConfig {
+        seed: Some(7),
+        ..FuzzyKMeansConfig::new(4, 10, 2.0)
+    };
+    let best = |threads| {
+        with_threads(threads, || {
+            fit_restarts(&config, &data, 4)
+                .unwrap()
+                .into_iter()
+                .min_by(|a, b| a.objective.total_cmp(&b.objective))
+                .unwrap()
+        })
+    };
+    let reference = best(1);
+    for threads in [2, 8] {
+        assert_bitwise_equal(&reference, &best(threads));
+    }
+}
@@ -0,0 +1,34 @@
+//! Shell clustering through the public API of the library
+#![cfg(feature = "advanced-algo")]
+
+use k_means_rs::algo::cluster_fuzzy_c_shells;
+use ndarray::Array2;
+
+/// Two rings around the origin with radii 1 and 4
+fn rings() -> Array2<f64> {
+    Array2::from_shape_fn((80, 2), |(i, j)| {
+        let radius = if i < 40 { 1.0 } else { 4.0 };
+        let angle = i as f64 * std::f64::consts::PI / 20.0;
+        radius * if j == 0 { angle.cos() } else { angle.sin() }
+    })
+}
+
+#[test]
+fn shell_without_weight_keeps_its_center() {
+    // memberships of about 1/3 vanish to the power of a huge fuzzifier, leaving shells without
+    // any weight
+    for seed in 0..4 {
+        let result = cluster_fuzzy_c_shells(3, 20, 1000.0, &rings(), Some(seed)).unwrap();
+        assert!(
+            result.centers.iter().all(|val| val.is_finite()),
+            "seed {}",
+            seed
+        );
+        assert!(
+            result.radii.iter().all(|val| val.is_finite()),
+            "seed {}",
+            seed
+        );
+        assert!(result.objective.is_finite());
+    }
+}
@@ -0,0 +1,24 @@
+//! JavaScript bindings in a headless browser, run with
+//! `wasm-pack test --headless --firefox -- --features wasm`
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use k_means_rs::wasm::fit;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn fit_two_blobs() {
+    let data = [
+        0.0, 0.1, 0.2, 0.0, 0.1, 0.1, 0.0, 0.2, 5.0, 5.1, 5.2, 4.9, 4.9, 5.0,
+    ];
+    let result = fit(&data, 2, 2, 2.0, 100, 1).ok().expect("fit failed");
+
+    let labels = result.labels();
+    assert_eq!(labels.len(), 7);
+    let counts = [0, 1].map(|label| labels.iter().filter(|&&l| l == label).count());
+    assert_eq!(counts[labels[0] as usize], 4);
+    assert_eq!(counts[labels[6] as usize], 3);
+    assert_eq!(result.centers().len(), 4);
+    assert_eq!(result.memberships().len(), 14);
+}